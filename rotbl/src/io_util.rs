@@ -1,21 +1,73 @@
 use std::io;
 
-use codeq::Span;
+use bytes::Bytes;
 
-use crate::buf::new_uninitialized;
+use crate::storage::ArcReadAt;
+use crate::storage::AsyncReader;
+use crate::storage::ReadAt;
+use crate::v001::error::Location;
+use crate::v001::RotblError;
 
 pub(crate) const DEFAULT_READ_BUF_SIZE: usize = 8 * 1024 * 1024;
 pub(crate) const DEFAULT_WRITE_BUF_SIZE: usize = 64 * 1024 * 1024;
 
-/// Read a segment of bytes from a seekable reader.
-pub(crate) fn read_segment<R: io::Read + io::Seek>(
-    mut r: R,
-    segment: impl Span,
-) -> Result<Vec<u8>, io::Error> {
-    r.seek(io::SeekFrom::Start(segment.start().0))?;
+/// Read the bytes of a section at `loc` from a positional reader.
+///
+/// A section beyond `file_size` is not read, because the size may come from a corrupted file.
+/// A failed or short read is reported with the location.
+pub(crate) fn read_segment(
+    r: &dyn ReadAt,
+    loc: &Location,
+    file_size: u64,
+) -> Result<Bytes, RotblError> {
+    check_bounds(loc, file_size)?;
+    let buf = r.read_at(loc.offset(), loc.size()).map_err(|e| loc.read_error(e))?;
+    check_read_size(buf, loc)
+}
+
+/// Read the bytes of a section at `loc` from an async reader.
+pub(crate) async fn read_segment_async(
+    r: &dyn AsyncReader,
+    loc: &Location<'_>,
+    file_size: u64,
+) -> Result<Bytes, RotblError> {
+    check_bounds(loc, file_size)?;
+    let buf = r.read_at(loc.offset(), loc.size()).await.map_err(|e| loc.read_error(e))?;
+    check_read_size(buf, loc)
+}
+
+/// Read the bytes of a section at `loc` from a positional reader in a blocking thread.
+///
+/// It is run with [`tokio::task::spawn_blocking`], thus it does not block the runtime thread,
+/// and works on either a current-thread or a multi-thread runtime.
+pub(crate) async fn read_segment_blocking(
+    r: ArcReadAt,
+    loc: &Location<'_>,
+    file_size: u64,
+) -> Result<Bytes, RotblError> {
+    check_bounds(loc, file_size)?;
 
-    let mut buf = new_uninitialized(segment.size().0 as usize);
-    r.read_exact(&mut buf)?;
+    let (offset, size) = (loc.offset(), loc.size());
+    let res = tokio::task::spawn_blocking(move || r.read_at(offset, size)).await;
+
+    let buf = res.map_err(io::Error::other).and_then(|r| r).map_err(|e| loc.read_error(e))?;
+    check_read_size(buf, loc)
+}
+
+fn check_bounds(loc: &Location, file_size: u64) -> Result<(), RotblError> {
+    match loc.offset().checked_add(loc.size()) {
+        Some(end) if end <= file_size => Ok(()),
+        _ => Err(loc.corruption(format!("beyond the file size {}", file_size))),
+    }
+}
 
+fn check_read_size(buf: Bytes, loc: &Location) -> Result<Bytes, RotblError> {
+    if buf.len() as u64 != loc.size() {
+        return Err(loc.corruption(format!(
+            "truncated: read {} bytes, expected {}",
+            buf.len(),
+            loc.size()
+        )));
+    }
     Ok(buf)
 }
@@ -0,0 +1,124 @@
+//! Provides an adapter to use a sync [`Storage`] as an [`AsyncStorage`].
+
+use std::io;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+use crate::storage::ArcReadAt;
+use crate::storage::AsyncReader;
+use crate::storage::AsyncStorage;
+use crate::storage::BoxAsyncReader;
+use crate::storage::Storage;
+
+/// Adapts a sync [`Storage`] to [`AsyncStorage`].
+///
+/// Every blocking IO operation of the inner storage is run with [`tokio::task::spawn_blocking`],
+/// thus it works on both multi-thread and current-thread runtime,
+/// and does not occupy a runtime worker thread during IO.
+#[derive(Debug, Clone)]
+pub struct BlockingAdapter<S>
+where S: Storage
+{
+    inner: S,
+}
+
+impl<S> BlockingAdapter<S>
+where S: Storage
+{
+    pub fn new(inner: S) -> Self {
+        Self { inner }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S> AsyncStorage for BlockingAdapter<S>
+where S: Storage
+{
+    fn async_reader(&mut self, key: &str) -> BoxFuture<'_, Result<BoxAsyncReader, io::Error>> {
+        let mut storage = self.inner.clone();
+        let key = key.to_string();
+
+        async move {
+            let r = spawn_blocking(move || storage.positional_reader(&key)).await?;
+
+            let r = BlockingReader { inner: r };
+            Ok(Box::new(r) as BoxAsyncReader)
+        }
+        .boxed()
+    }
+}
+
+/// An [`AsyncReader`] that runs the blocking positional read of a sync reader in a blocking
+/// thread.
+#[derive(Debug)]
+pub struct BlockingReader {
+    inner: ArcReadAt,
+}
+
+impl AsyncReader for BlockingReader {
+    fn read_at(&self, offset: u64, len: u64) -> BoxFuture<'_, Result<Bytes, io::Error>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || inner.read_at(offset, len)).boxed()
+    }
+
+    fn size(&self) -> BoxFuture<'_, Result<u64, io::Error>> {
+        let inner = self.inner.clone();
+
+        spawn_blocking(move || inner.size()).boxed()
+    }
+}
+
+async fn spawn_blocking<T, F>(f: F) -> Result<T, io::Error>
+where
+    F: FnOnce() -> Result<T, io::Error> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f).await.map_err(io::Error::other)?
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::storage::impls::fs::FsStorage;
+
+    #[test]
+    fn test_blocking_adapter_current_thread() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+
+        let mut storage = FsStorage::new(temp_dir.path().to_path_buf());
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+        writer.commit()?;
+
+        let rt = tokio::runtime::Builder::new_current_thread().build()?;
+
+        rt.block_on(async move {
+            let mut adapter = BlockingAdapter::new(storage);
+            let r = adapter.async_reader("test.txt").await?;
+
+            assert_eq!(r.size().await?, 13);
+            assert_eq!(r.read_at(7, 5).await?, Bytes::from_static(b"world"));
+
+            let res = r.read_at(7, 10).await;
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+            let res = adapter.async_reader("not-exist").await;
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,686 @@
+//! Provides a storage wrapper that encrypts data at rest.
+//!
+//! The whole file, including the header, index and meta of a table, is encrypted
+//! with XChaCha20-Poly1305 in fixed size chunks, so that any chunk can be decrypted
+//! and authenticated independently, and random block access is still possible.
+//!
+//! An encrypted file is organized as follows:
+//!
+//! ```text
+//! | magic: [u8; 8] | key_id: u32 | chunk_size: u32 | salt: [u8; 16]
+//! | chunk 0: ciphertext + tag
+//! | chunk 1: ciphertext + tag
+//! | ...
+//! | last chunk: ciphertext + tag
+//! ```
+//!
+//! The nonce of a chunk is `salt || chunk_index`, where `salt` is randomly generated for every
+//! file. The associated data of a chunk is the file header and a flag indicating whether it is
+//! the last chunk, so that a tampered header or a truncated file fails authentication.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::aead::AeadCore;
+use chacha20poly1305::aead::KeyInit;
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::aead::Payload;
+use chacha20poly1305::XChaCha20Poly1305;
+use chacha20poly1305::XNonce;
+
+use crate::storage;
+use crate::storage::ArcReadAt;
+use crate::storage::BoxReader;
+use crate::storage::BoxWriter;
+use crate::storage::ReadAt;
+use crate::storage::ReadAtReader;
+use crate::storage::Storage;
+
+/// A 256-bit encryption key.
+pub type Key = [u8; 32];
+
+const MAGIC: &[u8; 8] = b"ROTBLE01";
+const HEADER_SIZE: usize = 32;
+const SALT_SIZE: usize = 16;
+const TAG_SIZE: u64 = 16;
+
+const DEFAULT_CHUNK_SIZE: u32 = 4 * 1024;
+
+/// Provides encryption keys by key id.
+///
+/// Every encrypted file records the id of the key it is encrypted with,
+/// so that keys can be rotated: new files are encrypted with the current key,
+/// while old files are still decrypted with the key they were written with.
+pub trait KeyProvider
+where Self: Send + Sync + fmt::Debug + 'static
+{
+    /// Return the id of the key to encrypt new files.
+    fn current_key_id(&self) -> u32;
+
+    /// Return the key of the given id.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] if the key is unknown.
+    fn key(&self, key_id: u32) -> Result<Key, io::Error>;
+}
+
+/// A [`KeyProvider`] that holds all keys in memory.
+#[derive(Clone)]
+pub struct StaticKeyProvider {
+    current: u32,
+    keys: BTreeMap<u32, Key>,
+}
+
+impl fmt::Debug for StaticKeyProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Do not print the keys.
+        f.debug_struct("StaticKeyProvider")
+            .field("current", &self.current)
+            .field("key_ids", &self.keys.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl StaticKeyProvider {
+    /// Create a provider with a single key that is used as the current key.
+    pub fn new(key_id: u32, key: Key) -> Self {
+        Self {
+            current: key_id,
+            keys: BTreeMap::from([(key_id, key)]),
+        }
+    }
+
+    /// Add a key and use it as the current key to encrypt new files.
+    pub fn rotate(mut self, key_id: u32, key: Key) -> Self {
+        self.keys.insert(key_id, key);
+        self.current = key_id;
+        self
+    }
+}
+
+impl KeyProvider for StaticKeyProvider {
+    fn current_key_id(&self) -> u32 {
+        self.current
+    }
+
+    fn key(&self, key_id: u32) -> Result<Key, io::Error> {
+        self.keys.get(&key_id).copied().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("unknown key id: {}", key_id),
+            )
+        })
+    }
+}
+
+/// A [`Storage`] wrapper that encrypts all data written to and decrypts all data read from the
+/// inner storage.
+#[derive(Debug)]
+pub struct EncryptedStorage<S, K>
+where
+    S: Storage,
+    K: KeyProvider,
+{
+    inner: S,
+    key_provider: Arc<K>,
+    chunk_size: u32,
+}
+
+impl<S, K> Clone for EncryptedStorage<S, K>
+where
+    S: Storage,
+    K: KeyProvider,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            key_provider: self.key_provider.clone(),
+            chunk_size: self.chunk_size,
+        }
+    }
+}
+
+impl<S, K> EncryptedStorage<S, K>
+where
+    S: Storage,
+    K: KeyProvider,
+{
+    pub fn new(inner: S, key_provider: K) -> Self {
+        Self {
+            inner,
+            key_provider: Arc::new(key_provider),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+        }
+    }
+
+    /// Set the plaintext size of a chunk for new files.
+    ///
+    /// A smaller chunk reduces the amount of data to decrypt for a small read,
+    /// at the cost of more space for tags.
+    ///
+    /// It returns an [`io::ErrorKind::InvalidInput`] error if `chunk_size` is 0.
+    pub fn with_chunk_size(mut self, chunk_size: u32) -> Result<Self, io::Error> {
+        if chunk_size == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "chunk_size must be greater than 0",
+            ));
+        }
+
+        self.chunk_size = chunk_size;
+        Ok(self)
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn open_read_at(&mut self, key: &str) -> Result<EncryptedReadAt, io::Error> {
+        let inner = self.inner.positional_reader(key)?;
+        EncryptedReadAt::open(inner, self.key_provider.as_ref())
+    }
+}
+
+impl<S, K> Storage for EncryptedStorage<S, K>
+where
+    S: Storage,
+    K: KeyProvider,
+{
+    fn reader(&mut self, key: &str) -> Result<BoxReader, io::Error> {
+        let r = self.open_read_at(key)?;
+        let chunk_size = r.chunk_size;
+        let r = ReadAtReader::new(Arc::new(r), chunk_size)?;
+        Ok(Box::new(r))
+    }
+
+    fn positional_reader(&mut self, key: &str) -> Result<ArcReadAt, io::Error> {
+        Ok(Arc::new(self.open_read_at(key)?))
+    }
+
+    fn writer(&mut self, key: &str) -> Result<BoxWriter, io::Error> {
+        let key_id = self.key_provider.current_key_id();
+        let cipher = new_cipher(self.key_provider.as_ref(), key_id)?;
+
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+
+        let mut header = [0u8; HEADER_SIZE];
+        header[..8].copy_from_slice(MAGIC);
+        header[8..12].copy_from_slice(&key_id.to_be_bytes());
+        header[12..16].copy_from_slice(&self.chunk_size.to_be_bytes());
+        header[16..].copy_from_slice(&nonce[..SALT_SIZE]);
+
+        let mut inner = self.inner.writer(key)?;
+        inner.write_all(&header)?;
+
+        let w = EncryptedWriter {
+            inner,
+            cipher,
+            header,
+            chunk_size: self.chunk_size as usize,
+            chunk_index: 0,
+            buf: Vec::with_capacity(self.chunk_size as usize + 1),
+        };
+        Ok(Box::new(w))
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        self.inner.list(prefix)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), io::Error> {
+        self.inner.remove(key)
+    }
+
+    fn exists(&mut self, key: &str) -> Result<bool, io::Error> {
+        self.inner.exists(key)
+    }
+
+    /// Return the plaintext size.
+    fn len(&mut self, key: &str) -> Result<u64, io::Error> {
+        self.open_read_at(key)?.size()
+    }
+}
+
+fn new_cipher(key_provider: &dyn KeyProvider, key_id: u32) -> Result<XChaCha20Poly1305, io::Error> {
+    let key = key_provider.key(key_id)?;
+    Ok(XChaCha20Poly1305::new(&key.into()))
+}
+
+/// Build the nonce of a chunk.
+fn chunk_nonce(header: &[u8; HEADER_SIZE], chunk_index: u64) -> XNonce {
+    let mut nonce = XNonce::default();
+    nonce[..SALT_SIZE].copy_from_slice(&header[16..]);
+    nonce[SALT_SIZE..].copy_from_slice(&chunk_index.to_be_bytes());
+    nonce
+}
+
+/// Build the associated data of a chunk.
+fn chunk_aad(header: &[u8; HEADER_SIZE], is_last: bool) -> [u8; HEADER_SIZE + 1] {
+    let mut aad = [0u8; HEADER_SIZE + 1];
+    aad[..HEADER_SIZE].copy_from_slice(header);
+    aad[HEADER_SIZE] = is_last as u8;
+    aad
+}
+
+fn invalid_data(msg: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
+
+/// The positional reader that decrypts only the chunks covering a read.
+pub struct EncryptedReadAt {
+    inner: ArcReadAt,
+    cipher: XChaCha20Poly1305,
+    header: [u8; HEADER_SIZE],
+    key_id: u32,
+    chunk_size: u64,
+
+    /// The number of chunks, including the last one.
+    chunk_num: u64,
+
+    /// The size of the encrypted file.
+    cipher_size: u64,
+
+    /// The plaintext size.
+    size: u64,
+}
+
+impl fmt::Debug for EncryptedReadAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedReadAt")
+            .field("inner", &self.inner)
+            .field("key_id", &self.key_id)
+            .field("chunk_size", &self.chunk_size)
+            .field("chunk_num", &self.chunk_num)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+impl EncryptedReadAt {
+    /// Read the header and verify the last chunk to detect a truncated file.
+    fn open(inner: ArcReadAt, key_provider: &dyn KeyProvider) -> Result<Self, io::Error> {
+        let cipher_size = inner.size()?;
+        if cipher_size < HEADER_SIZE as u64 + TAG_SIZE {
+            return Err(invalid_data(format!(
+                "encrypted file size {} is smaller than header and tag",
+                cipher_size
+            )));
+        }
+
+        let buf = inner.read_at(0, HEADER_SIZE as u64)?;
+        let header: [u8; HEADER_SIZE] = buf.as_ref().try_into().map_err(invalid_data)?;
+
+        if &header[..8] != MAGIC {
+            return Err(invalid_data("invalid magic of encrypted file"));
+        }
+
+        let key_id = u32::from_be_bytes(header[8..12].try_into().unwrap());
+        let chunk_size = u32::from_be_bytes(header[12..16].try_into().unwrap()) as u64;
+        if chunk_size == 0 {
+            return Err(invalid_data("chunk_size of encrypted file is 0"));
+        }
+
+        let cipher = new_cipher(key_provider, key_id)?;
+
+        let body_size = cipher_size - HEADER_SIZE as u64;
+        let stride = chunk_size + TAG_SIZE;
+        let chunk_num = body_size.div_ceil(stride);
+
+        // Only the last chunk of an empty file has nothing but the tag.
+        let last_chunk_size = body_size - (chunk_num - 1) * stride;
+        if last_chunk_size < TAG_SIZE || (last_chunk_size == TAG_SIZE && chunk_num > 1) {
+            return Err(invalid_data(format!(
+                "last chunk size {} of encrypted file is invalid, chunk_size: {}",
+                last_chunk_size, chunk_size
+            )));
+        }
+
+        let size = body_size.checked_sub(chunk_num * TAG_SIZE).ok_or_else(|| {
+            invalid_data(format!(
+                "encrypted body size {} is smaller than the tags of {} chunks",
+                body_size, chunk_num
+            ))
+        })?;
+
+        let r = Self {
+            inner,
+            cipher,
+            header,
+            key_id,
+            chunk_size,
+            chunk_num,
+            cipher_size,
+            size,
+        };
+
+        r.read_chunks(chunk_num - 1, chunk_num)?;
+
+        Ok(r)
+    }
+
+    /// Read and decrypt chunks in `[start, end)`, return the concatenated plaintext.
+    fn read_chunks(&self, start: u64, end: u64) -> Result<Vec<u8>, io::Error> {
+        let stride = self.chunk_size + TAG_SIZE;
+
+        let offset = HEADER_SIZE as u64 + start * stride;
+        let cipher_end = (HEADER_SIZE as u64 + end * stride).min(self.cipher_size);
+
+        let data = self.inner.read_at(offset, cipher_end - offset)?;
+
+        // Plaintext is smaller than the ciphertext read.
+        // `chunk_size` is not trusted until a chunk is authenticated.
+        let mut plain = Vec::with_capacity(data.len());
+
+        for (i, chunk) in data.chunks(stride as usize).enumerate() {
+            let chunk_index = start + i as u64;
+            let is_last = chunk_index == self.chunk_num - 1;
+
+            let nonce = chunk_nonce(&self.header, chunk_index);
+            let aad = chunk_aad(&self.header, is_last);
+
+            let p = self
+                .cipher
+                .decrypt(&nonce, Payload {
+                    msg: chunk,
+                    aad: &aad,
+                })
+                .map_err(|_e| {
+                    invalid_data(format!(
+                        "failed to decrypt chunk {} with key id {}: authentication failed",
+                        chunk_index, self.key_id
+                    ))
+                })?;
+
+            plain.extend_from_slice(&p);
+        }
+
+        Ok(plain)
+    }
+}
+
+impl ReadAt for EncryptedReadAt {
+    fn read_at(&self, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+        let end = offset.checked_add(len).filter(|end| *end <= self.size);
+        let Some(end) = end else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("read {}+{} beyond size {}", offset, len, self.size),
+            ));
+        };
+
+        if len == 0 {
+            return Ok(Bytes::new());
+        }
+
+        let start_chunk = offset / self.chunk_size;
+        let end_chunk = end.div_ceil(self.chunk_size);
+
+        let plain = self.read_chunks(start_chunk, end_chunk)?;
+
+        let skip = (offset - start_chunk * self.chunk_size) as usize;
+        let plain = Bytes::from(plain);
+        Ok(plain.slice(skip..skip + len as usize))
+    }
+
+    fn size(&self) -> Result<u64, io::Error> {
+        Ok(self.size)
+    }
+}
+
+/// The writer that encrypts data chunk by chunk.
+///
+/// A full chunk is not written until more data arrives,
+/// because the last chunk is encrypted differently.
+pub struct EncryptedWriter {
+    inner: BoxWriter,
+    cipher: XChaCha20Poly1305,
+    header: [u8; HEADER_SIZE],
+    chunk_size: usize,
+    chunk_index: u64,
+
+    /// The plaintext not yet encrypted.
+    buf: Vec<u8>,
+}
+
+impl fmt::Debug for EncryptedWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedWriter")
+            .field("inner", &self.inner)
+            .field("chunk_size", &self.chunk_size)
+            .field("chunk_index", &self.chunk_index)
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+impl EncryptedWriter {
+    fn write_chunk(&mut self, plain: &[u8], is_last: bool) -> Result<(), io::Error> {
+        let nonce = chunk_nonce(&self.header, self.chunk_index);
+        let aad = chunk_aad(&self.header, is_last);
+
+        let c = self
+            .cipher
+            .encrypt(&nonce, Payload {
+                msg: plain,
+                aad: &aad,
+            })
+            .map_err(|_e| io::Error::other("failed to encrypt chunk"))?;
+
+        self.inner.write_all(&c)?;
+        self.chunk_index += 1;
+        Ok(())
+    }
+}
+
+impl Write for EncryptedWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        self.buf.extend_from_slice(buf);
+
+        // Keep at least one byte buffered, it may belong to the last chunk.
+        let full = (self.buf.len() - 1) / self.chunk_size * self.chunk_size;
+        if full > 0 {
+            let pending = self.buf.split_off(full);
+            let data = std::mem::replace(&mut self.buf, pending);
+
+            for chunk in data.chunks(self.chunk_size) {
+                self.write_chunk(chunk, false)?;
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        self.inner.flush()
+    }
+}
+
+impl storage::Writer for EncryptedWriter {
+    fn commit(&mut self) -> Result<(), io::Error> {
+        let last = std::mem::take(&mut self.buf);
+        self.write_chunk(&last, true)?;
+
+        self.inner.commit()
+    }
+
+    fn abort(&mut self) -> Result<(), io::Error> {
+        self.buf = Vec::new();
+        self.inner.abort()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::storage::impls::mem::MemStorage;
+
+    fn new_storage(chunk_size: u32) -> EncryptedStorage<MemStorage, StaticKeyProvider> {
+        let keys = StaticKeyProvider::new(1, [1; 32]);
+        EncryptedStorage::new(MemStorage::new(), keys).with_chunk_size(chunk_size).unwrap()
+    }
+
+    fn write(storage: &mut impl Storage, key: &str, data: &[u8]) -> Result<(), io::Error> {
+        let mut w = storage.writer(key)?;
+        // Write in small pieces to cross chunk boundaries.
+        for piece in data.chunks(3) {
+            w.write_all(piece)?;
+        }
+        w.commit()
+    }
+
+    #[test]
+    fn test_encrypted_storage_read_at() -> Result<(), io::Error> {
+        let mut storage = new_storage(8);
+
+        for size in [0, 1, 7, 8, 9, 16, 17, 40] {
+            let data = (0..size).map(|i| i as u8).collect::<Vec<_>>();
+            let key = format!("f-{}", size);
+
+            write(&mut storage, &key, &data)?;
+
+            assert_eq!(storage.len(&key)?, size as u64);
+
+            let r = storage.positional_reader(&key)?;
+            assert_eq!(r.size()?, size as u64);
+
+            for offset in 0..=size {
+                for len in 0..=(size - offset) {
+                    let got = r.read_at(offset as u64, len as u64)?;
+                    assert_eq!(
+                        got.as_ref(),
+                        &data[offset..offset + len],
+                        "size: {size}, {offset}+{len}"
+                    );
+                }
+            }
+
+            let res = r.read_at(size as u64, 1);
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+            // Sequential reader
+            let mut got = Vec::new();
+            storage.reader(&key)?.read_to_end(&mut got)?;
+            assert_eq!(got, data);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_storage_write_empty() -> Result<(), io::Error> {
+        let mut storage = new_storage(8);
+
+        let mut w = storage.writer("foo")?;
+        assert_eq!(0, w.write(&[])?);
+        w.write_all(b"01234567")?;
+        assert_eq!(0, w.write(&[])?);
+        w.write_all(b"89")?;
+        w.commit()?;
+
+        let r = storage.positional_reader("foo")?;
+        assert_eq!(b"0123456789", r.read_at(0, 10)?.as_ref());
+
+        let res = EncryptedStorage::new(MemStorage::new(), StaticKeyProvider::new(1, [1; 32]))
+            .with_chunk_size(0);
+        assert_eq!(io::ErrorKind::InvalidInput, res.unwrap_err().kind());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_storage_no_plaintext_at_rest() -> Result<(), io::Error> {
+        let mut storage = new_storage(16);
+
+        write(&mut storage, "foo", b"customer-data-customer-data")?;
+
+        let r = storage.inner().clone().positional_reader("foo")?;
+        let raw = r.read_at(0, r.size()?)?;
+
+        assert!(!raw.windows(8).any(|w| w == b"customer"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_storage_tampered() -> Result<(), io::Error> {
+        let data = (0..100u8).collect::<Vec<_>>();
+
+        let mut storage = new_storage(16);
+        write(&mut storage, "foo", &data)?;
+
+        let raw = {
+            let r = storage.inner().clone().positional_reader("foo")?;
+            r.read_at(0, r.size()?)?.to_vec()
+        };
+
+        let mut inner = storage.inner().clone();
+
+        // Flip a bit in every position: header or chunk
+        for i in [0, 9, 13, 20, 40, raw.len() - 1] {
+            let mut corrupted = raw.clone();
+            corrupted[i] ^= 1;
+            write(&mut inner, "bad", &corrupted)?;
+
+            let res = storage.positional_reader("bad").and_then(|r| r.read_at(0, 100));
+            assert!(res.is_err(), "corrupted at {}", i);
+        }
+
+        // Truncated at a chunk boundary
+        let truncated = &raw[..HEADER_SIZE + 2 * (16 + 16)];
+        write(&mut inner, "bad", truncated)?;
+        let res = storage.positional_reader("bad");
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // A corrupted chunk_size that does not fit the body size
+        let mut corrupted = raw[..HEADER_SIZE + 18].to_vec();
+        corrupted[12..16].copy_from_slice(&1u32.to_be_bytes());
+        write(&mut inner, "bad", &corrupted)?;
+        let res = storage.positional_reader("bad");
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Wrong key
+        let keys = StaticKeyProvider::new(1, [2; 32]);
+        let mut wrong = EncryptedStorage::new(inner.clone(), keys);
+        let res = wrong.positional_reader("foo");
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        // Unknown key id
+        let keys = StaticKeyProvider::new(2, [1; 32]);
+        let mut unknown = EncryptedStorage::new(inner.clone(), keys);
+        let res = unknown.positional_reader("foo");
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_encrypted_storage_key_rotation() -> Result<(), io::Error> {
+        let inner = MemStorage::new();
+
+        let keys = StaticKeyProvider::new(1, [1; 32]);
+        let mut storage = EncryptedStorage::new(inner.clone(), keys.clone());
+        write(&mut storage, "old", b"old data")?;
+
+        let keys = keys.rotate(2, [2; 32]);
+        let mut storage = EncryptedStorage::new(inner.clone(), keys);
+        write(&mut storage, "new", b"new data")?;
+
+        let r = storage.positional_reader("old")?;
+        assert_eq!(r.read_at(0, 8)?.as_ref(), b"old data");
+
+        let r = storage.positional_reader("new")?;
+        assert_eq!(r.read_at(0, 8)?.as_ref(), b"new data");
+
+        Ok(())
+    }
+}
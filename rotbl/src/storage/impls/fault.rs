@@ -0,0 +1,496 @@
+//! Provides a storage wrapper that injects faults for crash and corruption testing.
+//!
+//! Faults are decided by a pseudo random generator with a given seed,
+//! so that a failing case can be reproduced with the same seed.
+
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+
+use crate::num::format_num;
+use crate::storage;
+use crate::storage::ArcReadAt;
+use crate::storage::BoxReader;
+use crate::storage::BoxWriter;
+use crate::storage::ReadAt;
+use crate::storage::Storage;
+
+/// The probabilities of every kind of fault, and the seed to decide when to inject them.
+///
+/// Every probability is in `[0, 1]` and is applied to every single IO operation.
+#[derive(Debug, Clone)]
+#[derive(Default)]
+pub struct FaultConfig {
+    /// The seed of the pseudo random generator.
+    pub seed: u64,
+
+    /// Probability that a read returns an IO error.
+    pub read_error: f64,
+
+    /// Probability that a read returns fewer bytes than requested.
+    ///
+    /// For a [`ReadAt`], this violates its contract and simulates a buggy backend.
+    pub short_read: f64,
+
+    /// Probability that a read returns data with one bit flipped.
+    pub bit_flip: f64,
+
+    /// Probability that a write or a commit returns an IO error.
+    pub write_error: f64,
+
+    /// Probability that a write silently persists only a prefix of the data.
+    pub torn_write: f64,
+
+    /// Probability that a commit returns success but the data is never made visible,
+    /// which simulates a crash before the commit is persisted.
+    pub lost_commit: f64,
+}
+
+impl FaultConfig {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Default::default()
+        }
+    }
+}
+
+/// The kind of injected fault.
+#[derive(Debug, Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum Fault {
+    ReadError,
+    ShortRead,
+    BitFlip,
+    WriteError,
+    TornWrite,
+    LostCommit,
+}
+
+/// Counts of injected faults.
+#[derive(Debug)]
+#[derive(Default)]
+pub struct FaultStat {
+    read_error: AtomicU64,
+    short_read: AtomicU64,
+    bit_flip: AtomicU64,
+    write_error: AtomicU64,
+    torn_write: AtomicU64,
+    lost_commit: AtomicU64,
+}
+
+impl fmt::Display for FaultStat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "read error: {}, short read: {}, bit flip: {}, write error: {}, torn write: {}, lost commit: {}",
+            format_num(self.get(Fault::ReadError)),
+            format_num(self.get(Fault::ShortRead)),
+            format_num(self.get(Fault::BitFlip)),
+            format_num(self.get(Fault::WriteError)),
+            format_num(self.get(Fault::TornWrite)),
+            format_num(self.get(Fault::LostCommit)),
+        )
+    }
+}
+
+impl FaultStat {
+    fn counter(&self, fault: Fault) -> &AtomicU64 {
+        match fault {
+            Fault::ReadError => &self.read_error,
+            Fault::ShortRead => &self.short_read,
+            Fault::BitFlip => &self.bit_flip,
+            Fault::WriteError => &self.write_error,
+            Fault::TornWrite => &self.torn_write,
+            Fault::LostCommit => &self.lost_commit,
+        }
+    }
+
+    /// Return the number of injected faults of the given kind.
+    pub fn get(&self, fault: Fault) -> u64 {
+        self.counter(fault).load(Ordering::Relaxed)
+    }
+
+    /// Return the total number of injected faults.
+    pub fn total(&self) -> u64 {
+        [
+            Fault::ReadError,
+            Fault::ShortRead,
+            Fault::BitFlip,
+            Fault::WriteError,
+            Fault::TornWrite,
+            Fault::LostCommit,
+        ]
+        .into_iter()
+        .map(|f| self.get(f))
+        .sum()
+    }
+}
+
+/// A xorshift64* pseudo random generator.
+///
+/// It is good enough to spread faults and does not require an extra dependency.
+#[derive(Debug)]
+struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // splitmix64 to derive a non-zero state from any seed.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+
+        Self { state: z | 1 }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Return a float in `[0, 1)`.
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+/// The state shared by a [`FaultStorage`] and all the readers and writers it creates.
+#[derive(Debug)]
+struct FaultPlan {
+    config: FaultConfig,
+    rng: Mutex<Rng>,
+    enabled: AtomicBool,
+    stat: FaultStat,
+}
+
+impl FaultPlan {
+    /// Decide whether to inject the fault, and count it if injected.
+    fn inject(&self, fault: Fault) -> bool {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        let probability = match fault {
+            Fault::ReadError => self.config.read_error,
+            Fault::ShortRead => self.config.short_read,
+            Fault::BitFlip => self.config.bit_flip,
+            Fault::WriteError => self.config.write_error,
+            Fault::TornWrite => self.config.torn_write,
+            Fault::LostCommit => self.config.lost_commit,
+        };
+
+        if probability <= 0.0 {
+            return false;
+        }
+
+        let yes = self.rng.lock().unwrap().next_f64() < probability;
+        if yes {
+            self.stat.counter(fault).fetch_add(1, Ordering::Relaxed);
+        }
+        yes
+    }
+
+    /// Return a random number in `[0, n)`, `n` must be greater than 0.
+    fn random_below(&self, n: usize) -> usize {
+        (self.rng.lock().unwrap().next_u64() % n as u64) as usize
+    }
+
+    /// Flip a random bit in a non-empty buffer.
+    fn flip_bit(&self, buf: &mut [u8]) {
+        let i = self.random_below(buf.len());
+        let bit = self.random_below(8);
+        buf[i] ^= 1 << bit;
+    }
+
+    fn error(fault: Fault) -> io::Error {
+        io::Error::other(format!("injected fault: {:?}", fault))
+    }
+}
+
+/// A [`Storage`] wrapper that injects faults into IO operations of the inner storage.
+///
+/// Clones of a `FaultStorage` share the same fault plan and statistics.
+#[derive(Debug, Clone)]
+pub struct FaultStorage<S>
+where S: Storage
+{
+    inner: S,
+    plan: Arc<FaultPlan>,
+}
+
+impl<S> FaultStorage<S>
+where S: Storage
+{
+    /// Create a fault injection storage that is enabled.
+    pub fn new(inner: S, config: FaultConfig) -> Self {
+        let plan = FaultPlan {
+            rng: Mutex::new(Rng::new(config.seed)),
+            config,
+            enabled: AtomicBool::new(true),
+            stat: Default::default(),
+        };
+
+        Self {
+            inner,
+            plan: Arc::new(plan),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Enable or disable fault injection, for all clones and the readers and writers created.
+    pub fn set_enabled(&self, enabled: bool) {
+        self.plan.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn stat(&self) -> &FaultStat {
+        &self.plan.stat
+    }
+}
+
+impl<S> Storage for FaultStorage<S>
+where S: Storage
+{
+    fn reader(&mut self, key: &str) -> Result<BoxReader, io::Error> {
+        let r = FaultReader {
+            inner: self.inner.reader(key)?,
+            plan: self.plan.clone(),
+        };
+        Ok(Box::new(io::BufReader::new(r)))
+    }
+
+    fn positional_reader(&mut self, key: &str) -> Result<ArcReadAt, io::Error> {
+        let r = FaultReadAt {
+            inner: self.inner.positional_reader(key)?,
+            plan: self.plan.clone(),
+        };
+        Ok(Arc::new(r))
+    }
+
+    fn writer(&mut self, key: &str) -> Result<BoxWriter, io::Error> {
+        let w = FaultWriter {
+            inner: self.inner.writer(key)?,
+            plan: self.plan.clone(),
+        };
+        Ok(Box::new(w))
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        self.inner.list(prefix)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), io::Error> {
+        self.inner.remove(key)
+    }
+
+    fn exists(&mut self, key: &str) -> Result<bool, io::Error> {
+        self.inner.exists(key)
+    }
+
+    fn len(&mut self, key: &str) -> Result<u64, io::Error> {
+        self.inner.len(key)
+    }
+}
+
+/// The reader that injects faults into every `read()` of the inner reader.
+#[derive(Debug)]
+pub struct FaultReader {
+    inner: BoxReader,
+    plan: Arc<FaultPlan>,
+}
+
+impl Read for FaultReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        if self.plan.inject(Fault::ReadError) {
+            return Err(FaultPlan::error(Fault::ReadError));
+        }
+
+        let mut len = buf.len();
+        if len > 1 && self.plan.inject(Fault::ShortRead) {
+            len = 1 + self.plan.random_below(len - 1);
+        }
+
+        let n = self.inner.read(&mut buf[..len])?;
+
+        if n > 0 && self.plan.inject(Fault::BitFlip) {
+            self.plan.flip_bit(&mut buf[..n]);
+        }
+
+        Ok(n)
+    }
+}
+
+impl Seek for FaultReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> Result<u64, io::Error> {
+        self.inner.seek(pos)
+    }
+}
+
+/// The positional reader that injects faults into every `read_at()` of the inner reader.
+#[derive(Debug)]
+pub struct FaultReadAt {
+    inner: ArcReadAt,
+    plan: Arc<FaultPlan>,
+}
+
+impl ReadAt for FaultReadAt {
+    fn read_at(&self, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+        if self.plan.inject(Fault::ReadError) {
+            return Err(FaultPlan::error(Fault::ReadError));
+        }
+
+        let mut data = self.inner.read_at(offset, len)?;
+
+        if !data.is_empty() && self.plan.inject(Fault::ShortRead) {
+            data.truncate(self.plan.random_below(data.len()));
+        }
+
+        if !data.is_empty() && self.plan.inject(Fault::BitFlip) {
+            let mut buf = data.to_vec();
+            self.plan.flip_bit(&mut buf);
+            data = Bytes::from(buf);
+        }
+
+        Ok(data)
+    }
+
+    fn size(&self) -> Result<u64, io::Error> {
+        if self.plan.inject(Fault::ReadError) {
+            return Err(FaultPlan::error(Fault::ReadError));
+        }
+
+        self.inner.size()
+    }
+}
+
+/// The writer that injects faults into every `write()` and `commit()` of the inner writer.
+#[derive(Debug)]
+pub struct FaultWriter {
+    inner: BoxWriter,
+    plan: Arc<FaultPlan>,
+}
+
+impl Write for FaultWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        if self.plan.inject(Fault::WriteError) {
+            return Err(FaultPlan::error(Fault::WriteError));
+        }
+
+        if !buf.is_empty() && self.plan.inject(Fault::TornWrite) {
+            // Persist only a prefix but report the whole buffer as written.
+            let n = self.plan.random_below(buf.len());
+            self.inner.write_all(&buf[..n])?;
+            return Ok(buf.len());
+        }
+
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        if self.plan.inject(Fault::WriteError) {
+            return Err(FaultPlan::error(Fault::WriteError));
+        }
+
+        self.inner.flush()
+    }
+}
+
+impl storage::Writer for FaultWriter {
+    fn commit(&mut self) -> Result<(), io::Error> {
+        if self.plan.inject(Fault::WriteError) {
+            return Err(FaultPlan::error(Fault::WriteError));
+        }
+
+        if self.plan.inject(Fault::LostCommit) {
+            return Ok(());
+        }
+
+        self.inner.commit()
+    }
+
+    fn abort(&mut self) -> Result<(), io::Error> {
+        self.inner.abort()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::impls::mem::MemStorage;
+
+    fn read_all_at(storage: &mut impl Storage, key: &str) -> Result<Vec<Bytes>, io::Error> {
+        let r = storage.positional_reader(key)?;
+        (0..16).map(|i| r.read_at(i % 8, 5)).collect()
+    }
+
+    #[test]
+    fn test_fault_storage_same_seed_same_faults() -> Result<(), io::Error> {
+        let mut inner = MemStorage::new();
+
+        let mut w = inner.writer("foo")?;
+        w.write_all(b"Hello, world!")?;
+        w.commit()?;
+
+        let config = FaultConfig {
+            bit_flip: 0.5,
+            ..FaultConfig::new(7)
+        };
+
+        let mut s1 = FaultStorage::new(inner.clone(), config.clone());
+        let mut s2 = FaultStorage::new(inner.clone(), config);
+
+        let got1 = read_all_at(&mut s1, "foo")?;
+        let got2 = read_all_at(&mut s2, "foo")?;
+
+        assert_eq!(got1, got2);
+        assert!(s1.stat().get(Fault::BitFlip) > 0);
+        assert_eq!(s1.stat().total(), s2.stat().total());
+
+        // Disabled storage does not inject faults.
+        s1.set_enabled(false);
+        let got = read_all_at(&mut s1, "foo")?;
+        for (i, b) in got.iter().enumerate() {
+            assert_eq!(b.as_ref(), &b"Hello, world!"[i % 8..i % 8 + 5]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fault_storage_lost_commit() -> Result<(), io::Error> {
+        let config = FaultConfig {
+            lost_commit: 1.0,
+            ..FaultConfig::new(0)
+        };
+        let mut s = FaultStorage::new(MemStorage::new(), config);
+
+        let mut w = s.writer("foo")?;
+        w.write_all(b"Hello, world!")?;
+        w.commit()?;
+
+        assert!(!s.exists("foo")?);
+        assert_eq!(s.stat().get(Fault::LostCommit), 1);
+        assert_eq!(
+            "read error: 0, short read: 0, bit flip: 0, write error: 0, torn write: 0, lost commit: 1",
+            s.stat().to_string()
+        );
+
+        Ok(())
+    }
+}
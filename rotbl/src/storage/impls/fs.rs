@@ -1,30 +1,71 @@
 //! Provides the file system based storage implementation.
 
+mod durability;
+
 use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 use std::time::SystemTime;
 use std::time::UNIX_EPOCH;
 
+use bytes::Bytes;
+
+use self::durability::Op;
+use crate::buf::new_uninitialized;
 use crate::io_util::DEFAULT_READ_BUF_SIZE;
 use crate::io_util::DEFAULT_WRITE_BUF_SIZE;
 use crate::storage;
+use crate::storage::impls::mem::MemReadAt;
+use crate::storage::impls::mem::MemReader;
+use crate::storage::ArcReadAt;
 use crate::storage::BoxReader;
 use crate::storage::BoxWriter;
 use crate::storage::Storage;
 
+/// Specifies how [`FsStorage`] reads a file.
+#[derive(Debug, Clone, Copy)]
+#[derive(Default)]
+#[derive(PartialEq, Eq)]
+pub enum ReadMode {
+    /// Read with a [`io::BufReader`] or with `pread(2)`.
+    #[default]
+    Buffered,
+
+    /// Map the file into memory and read from the mapping without copying.
+    ///
+    /// The OS page cache is used directly instead of an in-process buffer,
+    /// which is more efficient for random point lookups.
+    Mmap,
+}
+
 /// The storage implementation that uses the file system.
 #[derive(Debug, Clone)]
 pub struct FsStorage {
     base_dir: PathBuf,
+    read_mode: ReadMode,
 }
 
 impl FsStorage {
     pub fn new(base_dir: PathBuf) -> Self {
-        Self { base_dir }
+        Self {
+            base_dir,
+            read_mode: ReadMode::default(),
+        }
+    }
+
+    /// Set the mode to read a file.
+    pub fn with_read_mode(mut self, read_mode: ReadMode) -> Self {
+        self.read_mode = read_mode;
+        self
+    }
+
+    pub fn read_mode(&self) -> ReadMode {
+        self.read_mode
     }
 
     /// Return the containing directory of the storage.
@@ -36,6 +77,124 @@ impl FsStorage {
         self.base_dir.to_str().expect("base_dir should be valid UTF-8")
     }
 
+    /// Build the path of the temp file for writing `key`.
+    fn temp_path(&self, key: &str, micros: u64) -> PathBuf {
+        self.base_dir.join(format!("{key}.tmp-{micros}"))
+    }
+
+    /// Return `true` if the key is a temp file created by [`FsStorage::writer()`].
+    ///
+    /// Temp files are named `{key}.tmp-{micros}`.
+    pub(crate) fn is_temp_key(key: &str) -> bool {
+        Self::parse_temp_key(key).is_some()
+    }
+
+    /// Parse a temp file key `{key}.tmp-{micros}` and return the target key and `micros`.
+    fn parse_temp_key(key: &str) -> Option<(&str, u64)> {
+        let (target, micros) = key.rsplit_once(".tmp-")?;
+
+        if micros.is_empty() || !micros.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+
+        Some((target, micros.parse().ok()?))
+    }
+
+    /// Remove temp files that are left by writers that are never committed,
+    /// such as after a crash, and return the keys of the removed files.
+    ///
+    /// Only temp files created more than `older_than` ago are removed.
+    /// `older_than` must be greater than the time to build the largest table,
+    /// or a temp file that is still being written will be removed.
+    /// It is safe to use [`Duration::ZERO`] at startup, before any writer is created.
+    pub fn remove_stale_temp_files(
+        &mut self,
+        older_than: Duration,
+    ) -> Result<Vec<String>, io::Error> {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_micros() as u64;
+
+        let mut keys = Vec::new();
+        self.collect_keys("", &mut keys)?;
+        keys.sort();
+
+        let mut removed = Vec::new();
+
+        for key in keys {
+            let Some((_target, micros)) = Self::parse_temp_key(&key) else {
+                continue;
+            };
+
+            if now.saturating_sub(micros) < older_than.as_micros() as u64 {
+                continue;
+            }
+
+            match fs::remove_file(self.base_dir.join(&key)) {
+                Ok(()) => {}
+                // Removed by others, such as the writer.
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+
+            removed.push(key);
+        }
+
+        Ok(removed)
+    }
+
+    /// Recursively collect keys of all files in `rel_dir`, relative to the base dir.
+    fn collect_keys(&self, rel_dir: &str, keys: &mut Vec<String>) -> Result<(), io::Error> {
+        let entries = match fs::read_dir(self.base_dir.join(rel_dir)) {
+            Ok(x) => x,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for ent in entries {
+            let ent = ent?;
+
+            // Keys are `&str`, a file with non-UTF-8 name can not be a key.
+            let Some(name) = ent.file_name().to_str().map(|x| x.to_string()) else {
+                continue;
+            };
+
+            let key = if rel_dir.is_empty() {
+                name
+            } else {
+                format!("{}/{}", rel_dir.trim_end_matches('/'), name)
+            };
+
+            let file_type = ent.file_type()?;
+            if file_type.is_dir() {
+                self.collect_keys(&key, keys)?;
+            } else if file_type.is_file() {
+                keys.push(key);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Open a file for reading.
+    fn open_file(&self, key: &str) -> Result<File, io::Error> {
+        let path = self.base_dir.join(key);
+        fs::OpenOptions::new().create(false).create_new(false).read(true).open(&path)
+    }
+
+    /// Map the file of `key` into memory and return the mapping as [`Bytes`].
+    fn mmap(&self, key: &str) -> Result<Bytes, io::Error> {
+        let f = self.open_file(key)?;
+
+        if f.metadata()?.len() == 0 {
+            return Ok(Bytes::new());
+        }
+
+        // Safety: A committed file is never modified in place:
+        // a writer writes to a temp file and renames it to the target path.
+        let m = unsafe { memmap2::Mmap::map(&f)? };
+
+        Ok(Bytes::from_owner(m))
+    }
+
     fn temp_fn_num() -> u64 {
         // Sleep to avoid timestamp collision when this function is called twice in a short time.
         std::thread::sleep(std::time::Duration::from_micros(2));
@@ -47,35 +206,139 @@ impl FsStorage {
 
 impl Storage for FsStorage {
     fn reader(&mut self, key: &str) -> Result<BoxReader, io::Error> {
-        let path = self.base_dir.join(key);
+        if self.read_mode == ReadMode::Mmap {
+            let data = self.mmap(key)?;
+            return Ok(Box::new(MemReader::new(data)));
+        }
 
-        let f = fs::OpenOptions::new().create(false).create_new(false).read(true).open(&path)?;
+        let f = self.open_file(key)?;
         let f = io::BufReader::with_capacity(DEFAULT_READ_BUF_SIZE, f);
 
         let f = Box::new(f) as Box<dyn storage::Reader>;
         Ok(f)
     }
 
+    fn positional_reader(&mut self, key: &str) -> Result<ArcReadAt, io::Error> {
+        if self.read_mode == ReadMode::Mmap {
+            let data = self.mmap(key)?;
+            return Ok(Arc::new(MemReadAt::new(data)));
+        }
+
+        let f = self.open_file(key)?;
+        Ok(Arc::new(FsReadAt::new(f)))
+    }
+
     fn writer(&mut self, key: &str) -> Result<BoxWriter, io::Error> {
         let target_path = self.base_dir.join(key);
         let micros = Self::temp_fn_num();
 
-        let temp_path = self.base_dir.join(format!("{key}.tmp-{micros}"));
+        let temp_path = self.temp_path(key, micros);
 
         let w = FsWriter::new(temp_path, target_path)?;
         Ok(Box::new(w))
     }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        // Only the dir the prefix resides in needs to be scanned.
+        let rel_dir = prefix.rsplit_once('/').map(|(dir, _)| dir).unwrap_or_default();
+
+        let mut keys = Vec::new();
+        self.collect_keys(rel_dir, &mut keys)?;
+
+        keys.retain(|k| k.starts_with(prefix) && !Self::is_temp_key(k));
+        keys.sort();
+
+        Ok(keys)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), io::Error> {
+        fs::remove_file(self.base_dir.join(key))
+    }
+
+    fn exists(&mut self, key: &str) -> Result<bool, io::Error> {
+        match fs::metadata(self.base_dir.join(key)) {
+            Ok(meta) => Ok(meta.is_file()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn len(&mut self, key: &str) -> Result<u64, io::Error> {
+        let meta = fs::metadata(self.base_dir.join(key))?;
+        Ok(meta.len())
+    }
+}
+
+/// The positional reader implementation that uses the file system.
+///
+/// On unix it reads with `pread(2)`, so concurrent reads do not block each other.
+/// On other platforms the reads are serialized with a lock.
+#[derive(Debug)]
+pub struct FsReadAt {
+    #[cfg(unix)]
+    file: File,
+
+    #[cfg(not(unix))]
+    file: std::sync::Mutex<File>,
+}
+
+impl FsReadAt {
+    pub fn new(file: File) -> Self {
+        Self {
+            #[cfg(unix)]
+            file,
+            #[cfg(not(unix))]
+            file: std::sync::Mutex::new(file),
+        }
+    }
+}
+
+impl storage::ReadAt for FsReadAt {
+    fn read_at(&self, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+        let mut buf = new_uninitialized(len as usize);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileExt;
+            self.file.read_exact_at(&mut buf, offset)?;
+        }
+
+        #[cfg(not(unix))]
+        {
+            use std::io::Read;
+            use std::io::Seek;
+
+            let mut f = self.file.lock().unwrap();
+            f.seek(io::SeekFrom::Start(offset))?;
+            f.read_exact(&mut buf)?;
+        }
+
+        Ok(Bytes::from(buf))
+    }
+
+    fn size(&self) -> Result<u64, io::Error> {
+        #[cfg(unix)]
+        let meta = self.file.metadata()?;
+
+        #[cfg(not(unix))]
+        let meta = self.file.lock().unwrap().metadata()?;
+
+        Ok(meta.len())
+    }
 }
 
 /// The writer implementation that uses the file system.
 ///
 /// This writer writes data to a temporary file and then moves it to the target file.
 /// This ensures that the target file is always in a consistent state.
+///
+/// If it is dropped without being committed, the temporary file is removed.
 #[derive(Debug)]
 pub struct FsWriter {
     file: Option<io::BufWriter<File>>,
     target_path: PathBuf,
     temp_path: PathBuf,
+    committed: bool,
 }
 
 impl FsWriter {
@@ -88,14 +351,35 @@ impl FsWriter {
             .write(true)
             .open(&temp_path)?;
 
+        durability::record(|| Op::Create(temp_path.clone()));
+
         let file = io::BufWriter::with_capacity(DEFAULT_WRITE_BUF_SIZE, f);
 
         Ok(Self {
             file: Some(file),
             target_path,
             temp_path,
+            committed: false,
         })
     }
+
+    /// Close the file and remove the temp file if it is not committed.
+    fn remove_temp_file(&mut self) -> Result<(), io::Error> {
+        if self.committed {
+            return Ok(());
+        }
+
+        // Discard buffered data: close the file without flushing.
+        if let Some(f) = self.file.take() {
+            let (_f, _buf) = f.into_parts();
+        }
+
+        match fs::remove_file(&self.temp_path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
 }
 
 impl Write for FsWriter {
@@ -117,16 +401,60 @@ impl storage::Writer for FsWriter {
         let f = f.into_inner().map_err(|e| e.into_error())?;
 
         f.sync_all()?;
+        durability::record(|| Op::SyncFile(self.temp_path.clone()));
 
         fs::rename(&self.temp_path, &self.target_path)?;
+        durability::record(|| Op::Rename(self.temp_path.clone(), self.target_path.clone()));
+
+        // The file is committed once it is visible: it must not be removed on drop.
+        self.committed = true;
+
+        // Without syncing the dir, the new entry may be lost after a power loss.
+        if let Some(dir) = self.target_path.parent() {
+            sync_dir(dir)?;
+            durability::record(|| Op::SyncDir(dir.to_path_buf()));
+        }
 
         Ok(())
     }
+
+    fn abort(&mut self) -> Result<(), io::Error> {
+        self.remove_temp_file()
+    }
+}
+
+/// Sync the entries of a directory to disk, so that a newly created or renamed entry survives
+/// a crash.
+fn sync_dir(dir: &Path) -> Result<(), io::Error> {
+    #[cfg(unix)]
+    {
+        File::open(dir)?.sync_all()
+    }
+
+    // Directories can not be opened as files on other platforms.
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+        Ok(())
+    }
+}
+
+impl Drop for FsWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.remove_temp_file() {
+            log::warn!(
+                "failed to remove temp file {}: {}",
+                self.temp_path.display(),
+                e
+            );
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::io::Read;
+    use std::io::Seek;
 
     use super::*;
     use crate::storage::Writer;
@@ -157,6 +485,78 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_fs_writer_abort() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+        let temp_path = temp_dir.path().join("test.txt.tmp");
+        let target_path = temp_dir.path().join("test.txt");
+
+        // Abort explicitly
+        {
+            let mut writer = FsWriter::new(temp_path.clone(), target_path.clone())?;
+            writer.write_all(b"Hello, world!")?;
+            assert!(temp_path.exists());
+
+            writer.abort()?;
+            assert!(!temp_path.exists());
+            assert!(!target_path.exists());
+        }
+
+        // Abort on drop
+        {
+            let mut writer = FsWriter::new(temp_path.clone(), target_path.clone())?;
+            writer.write_all(b"Hello, world!")?;
+            assert!(temp_path.exists());
+        }
+        assert!(!temp_path.exists());
+        assert!(!target_path.exists());
+
+        // A committed writer does not remove the target on drop
+        {
+            let mut writer = FsWriter::new(temp_path.clone(), target_path.clone())?;
+            writer.write_all(b"Hello, world!")?;
+            writer.commit()?;
+        }
+        assert!(target_path.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fs_storage_remove_stale_temp_files() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+
+        let mut storage = FsStorage::new(temp_dir.path().to_path_buf());
+
+        let mut w = storage.writer("a")?;
+        w.write_all(b"a")?;
+        w.commit()?;
+
+        // Temp files left by a crash
+        fs::create_dir(temp_dir.path().join("d"))?;
+        fs::write(temp_dir.path().join("b.tmp-100"), "b")?;
+        fs::write(temp_dir.path().join("d/c.tmp-200"), "c")?;
+
+        // A writer that is still being written
+        let _w = storage.writer("e")?;
+
+        let removed = storage.remove_stale_temp_files(Duration::from_secs(3600))?;
+        assert_eq!(removed, vec!["b.tmp-100", "d/c.tmp-200"]);
+
+        let mut keys = Vec::new();
+        storage.collect_keys("", &mut keys)?;
+        keys.sort();
+        assert_eq!(keys.len(), 2);
+        assert_eq!(keys[0], "a");
+        assert!(FsStorage::is_temp_key(&keys[1]));
+
+        let removed = storage.remove_stale_temp_files(Duration::ZERO)?;
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].starts_with("e.tmp-"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_fs_storage() -> Result<(), io::Error> {
         let temp_dir = tempfile::tempdir()?;
@@ -174,6 +574,158 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_fs_storage_positional_reader() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+
+        let mut storage = FsStorage::new(temp_dir.path().to_path_buf());
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+        writer.commit()?;
+
+        let r = storage.positional_reader("test.txt")?;
+
+        assert_eq!(r.size()?, 13);
+
+        // Concurrent reads at different positions
+        std::thread::scope(|s| {
+            let handles = (0..8)
+                .map(|i| {
+                    let r = r.clone();
+                    s.spawn(move || r.read_at(i, 5))
+                })
+                .collect::<Vec<_>>();
+
+            for (i, h) in handles.into_iter().enumerate() {
+                let got = h.join().unwrap()?;
+                assert_eq!(got, &b"Hello, world!"[i..i + 5]);
+            }
+            Ok::<(), io::Error>(())
+        })?;
+
+        let res = r.read_at(7, 10);
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fs_storage_mmap() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+
+        let mut storage =
+            FsStorage::new(temp_dir.path().to_path_buf()).with_read_mode(ReadMode::Mmap);
+        assert_eq!(storage.read_mode(), ReadMode::Mmap);
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+        writer.commit()?;
+
+        let r = storage.positional_reader("test.txt")?;
+
+        assert_eq!(r.size()?, 13);
+        assert_eq!(r.read_at(7, 5)?, Bytes::from_static(b"world"));
+
+        let res = r.read_at(7, 10);
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        let mut reader = storage.reader("test.txt")?;
+        reader.seek(io::SeekFrom::Start(7))?;
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        assert_eq!(content, "world!");
+
+        // Empty file can be mapped
+        let mut writer = storage.writer("empty")?;
+        writer.commit()?;
+
+        let r = storage.positional_reader("empty")?;
+        assert_eq!(r.size()?, 0);
+
+        let res = storage.positional_reader("not-exist");
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fs_storage_list() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+        fs::create_dir_all(temp_dir.path().join("foo/bar"))?;
+
+        let mut storage = FsStorage::new(temp_dir.path().to_path_buf());
+
+        for key in ["a", "foo/b", "foo/c", "foo/bar/d", "fox"] {
+            let mut writer = storage.writer(key)?;
+            writer.write_all(key.as_bytes())?;
+            writer.commit()?;
+        }
+
+        // Uncommitted data is not listed
+        let _writer = storage.writer("foo/e")?;
+
+        assert_eq!(storage.list("")?, vec![
+            "a",
+            "foo/b",
+            "foo/bar/d",
+            "foo/c",
+            "fox"
+        ]);
+        assert_eq!(storage.list("fo")?, vec![
+            "foo/b",
+            "foo/bar/d",
+            "foo/c",
+            "fox"
+        ]);
+        assert_eq!(storage.list("foo/")?, vec!["foo/b", "foo/bar/d", "foo/c"]);
+        assert_eq!(storage.list("foo/b")?, vec!["foo/b", "foo/bar/d"]);
+        assert_eq!(storage.list("foo/bar/")?, vec!["foo/bar/d"]);
+        assert_eq!(storage.list("x/")?, Vec::<String>::new());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fs_storage_remove_exists_len() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+
+        let mut storage = FsStorage::new(temp_dir.path().to_path_buf());
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+
+        assert!(!storage.exists("test.txt")?);
+
+        writer.commit()?;
+
+        assert!(storage.exists("test.txt")?);
+        assert_eq!(storage.len("test.txt")?, 13);
+
+        storage.remove("test.txt")?;
+
+        assert!(!storage.exists("test.txt")?);
+        assert_eq!(
+            storage.len("test.txt").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            storage.remove("test.txt").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_temp_key() {
+        assert!(FsStorage::is_temp_key("foo.tmp-123"));
+        assert!(FsStorage::is_temp_key("a/foo.tmp-123"));
+        assert!(!FsStorage::is_temp_key("foo.tmp-"));
+        assert!(!FsStorage::is_temp_key("foo.tmp-12a"));
+        assert!(!FsStorage::is_temp_key("foo"));
+    }
+
     #[test]
     fn test_fs_storage_base_dir() -> Result<(), io::Error> {
         let temp_dir = tempfile::tempdir()?;
@@ -0,0 +1,246 @@
+//! Records file system operations that affect durability,
+//! and simulates what survives a crash in tests.
+//!
+//! A file committed by [`FsWriter`](`super::FsWriter`) survives a crash only if:
+//! - its data is synced before it is renamed, and
+//! - the directory containing it is synced after it is renamed.
+//!
+//! Without the latter, the new directory entry may be lost after a power loss,
+//! even though the data of the file is durable.
+
+use std::path::PathBuf;
+
+/// A file system operation that affects durability.
+#[derive(Debug, Clone)]
+#[derive(PartialEq, Eq)]
+#[cfg_attr(not(test), allow(dead_code))]
+pub(crate) enum Op {
+    /// A new file is created.
+    Create(PathBuf),
+
+    /// The data of a file is synced.
+    SyncFile(PathBuf),
+
+    /// A file is renamed.
+    Rename(PathBuf, PathBuf),
+
+    /// The entries of a directory are synced.
+    SyncDir(PathBuf),
+}
+
+#[cfg(test)]
+thread_local! {
+    static LOG: std::cell::RefCell<Vec<Op>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Record an operation. It does nothing except in tests.
+#[allow(unused_variables)]
+pub(crate) fn record(op: impl FnOnce() -> Op) {
+    #[cfg(test)]
+    LOG.with_borrow_mut(|log| log.push(op()));
+}
+
+/// Take all operations recorded in this thread.
+#[cfg(test)]
+pub(crate) fn take() -> Vec<Op> {
+    LOG.with_borrow_mut(std::mem::take)
+}
+
+#[cfg(test)]
+pub(crate) use crash::CrashModel;
+
+#[cfg(test)]
+mod crash {
+    use std::collections::BTreeMap;
+    use std::collections::BTreeSet;
+    use std::path::Path;
+    use std::path::PathBuf;
+
+    use super::Op;
+
+    /// A model of a file system that tells which files survive a crash.
+    ///
+    /// It follows the weakest guarantee POSIX file systems provide:
+    /// a directory entry is durable only after the directory is synced,
+    /// and the data of a file is durable only after the file is synced.
+    #[derive(Debug, Default)]
+    pub(crate) struct CrashModel {
+        next_inode: u64,
+
+        /// Directory entries visible before a crash.
+        volatile: BTreeMap<PathBuf, u64>,
+
+        /// Directory entries that survive a crash.
+        durable: BTreeMap<PathBuf, u64>,
+
+        /// Inodes whose data survives a crash.
+        synced: BTreeSet<u64>,
+    }
+
+    impl CrashModel {
+        pub(crate) fn apply(&mut self, ops: impl IntoIterator<Item = Op>) {
+            for op in ops {
+                match op {
+                    Op::Create(p) => {
+                        self.next_inode += 1;
+                        self.volatile.insert(p, self.next_inode);
+                    }
+                    Op::SyncFile(p) => {
+                        if let Some(ino) = self.volatile.get(&p) {
+                            self.synced.insert(*ino);
+                        }
+                    }
+                    Op::Rename(from, to) => {
+                        if let Some(ino) = self.volatile.remove(&from) {
+                            self.volatile.insert(to, ino);
+                        }
+                    }
+                    Op::SyncDir(dir) => {
+                        let in_dir = |p: &Path| p.parent() == Some(dir.as_path());
+
+                        self.durable.retain(|p, _| !in_dir(p));
+                        for (p, ino) in self.volatile.iter() {
+                            if in_dir(p) {
+                                self.durable.insert(p.clone(), *ino);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        /// Return the files that survive a crash with their data intact.
+        pub(crate) fn crash(&self) -> Vec<PathBuf> {
+            self.durable
+                .iter()
+                .filter(|(_p, ino)| self.synced.contains(ino))
+                .map(|(p, _)| p.clone())
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io;
+    use std::io::Read;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::storage::impls::fs::FsStorage;
+    use crate::storage::Storage;
+
+    fn p(s: &str) -> PathBuf {
+        PathBuf::from(s)
+    }
+
+    #[test]
+    fn test_crash_model() {
+        let create = || {
+            vec![
+                Op::Create(p("/d/a.tmp")),
+                Op::SyncFile(p("/d/a.tmp")),
+                Op::Rename(p("/d/a.tmp"), p("/d/a")),
+            ]
+        };
+
+        // Directory entry is lost without syncing the directory.
+        let mut m = CrashModel::default();
+        m.apply(create());
+        assert!(m.crash().is_empty());
+
+        // Data is lost without syncing the file.
+        let mut m = CrashModel::default();
+        m.apply([
+            Op::Create(p("/d/a.tmp")),
+            Op::Rename(p("/d/a.tmp"), p("/d/a")),
+            Op::SyncDir(p("/d")),
+        ]);
+        assert!(m.crash().is_empty());
+
+        // Syncing another directory does not help.
+        let mut m = CrashModel::default();
+        m.apply(create());
+        m.apply([Op::SyncDir(p("/e"))]);
+        assert!(m.crash().is_empty());
+
+        let mut m = CrashModel::default();
+        m.apply(create());
+        m.apply([Op::SyncDir(p("/d"))]);
+        assert_eq!(m.crash(), vec![p("/d/a")]);
+    }
+
+    /// This only checks the order of the operations `FsWriter`
+    /// records about itself, replayed on a [`CrashModel`].
+    /// It does not observe what reaches the disk: a missing directory sync is not visible
+    /// without a real power loss.
+    #[test]
+    fn test_fs_writer_commit_survives_crash() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+        std::fs::create_dir(temp_dir.path().join("sub"))?;
+
+        let mut storage = FsStorage::new(temp_dir.path().to_path_buf());
+
+        take();
+
+        for key in ["a", "sub/b"] {
+            let mut w = storage.writer(key)?;
+            w.write_all(b"Hello, world!")?;
+            w.commit()?;
+        }
+
+        // An uncommitted writer leaves nothing after a crash.
+        let mut w = storage.writer("c")?;
+        w.write_all(b"Hello, world!")?;
+
+        let mut m = CrashModel::default();
+        m.apply(take());
+
+        assert_eq!(m.crash(), vec![
+            temp_dir.path().join("a"),
+            temp_dir.path().join("sub/b"),
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fs_writer_partial_write_then_reopen() -> Result<(), io::Error> {
+        let temp_dir = tempfile::tempdir()?;
+
+        let mut storage = FsStorage::new(temp_dir.path().to_path_buf());
+
+        let mut w = storage.writer("a")?;
+        w.write_all(b"old")?;
+        w.commit()?;
+
+        // A partial write of a new version, flushed to the temp file,
+        // then the process is gone without committing or cleaning up.
+        let mut w = storage.writer("a")?;
+        w.write_all(b"ne")?;
+        w.flush()?;
+        std::mem::forget(w);
+
+        let mut storage = FsStorage::new(temp_dir.path().to_path_buf());
+
+        let mut keys = Vec::new();
+        storage.collect_keys("", &mut keys)?;
+        assert_eq!(keys.len(), 2);
+
+        // The committed version is intact.
+        assert_eq!(fs::read_to_string(temp_dir.path().join("a"))?, "old");
+
+        let removed = storage.remove_stale_temp_files(Duration::ZERO)?;
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].starts_with("a.tmp-"));
+
+        let mut content = String::new();
+        storage.reader("a")?.read_to_string(&mut content)?;
+        assert_eq!(content, "old");
+
+        Ok(())
+    }
+}
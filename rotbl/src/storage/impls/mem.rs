@@ -0,0 +1,414 @@
+//! Provides an in-memory storage implementation.
+//!
+//! Data is kept in process memory and is lost when the last clone of the storage is dropped.
+//! It is useful for tests and for short-lived tables that never need to touch the disk.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::io::BufRead;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+use std::ops::Bound;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+
+use crate::storage;
+use crate::storage::ArcReadAt;
+use crate::storage::AsyncReader;
+use crate::storage::AsyncStorage;
+use crate::storage::BoxAsyncReader;
+use crate::storage::BoxReader;
+use crate::storage::BoxWriter;
+use crate::storage::ReadAt;
+use crate::storage::Storage;
+
+/// The storage implementation that keeps all data in memory.
+///
+/// Clones of a `MemStorage` share the same underlying data.
+#[derive(Clone)]
+#[derive(Default)]
+pub struct MemStorage {
+    files: Arc<Mutex<BTreeMap<String, Bytes>>>,
+}
+
+impl fmt::Debug for MemStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let files = self.files.lock().unwrap();
+        let sizes = files.iter().map(|(k, v)| (k.as_str(), v.len())).collect::<BTreeMap<_, _>>();
+        f.debug_struct("MemStorage").field("files", &sizes).finish()
+    }
+}
+
+impl MemStorage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Storage for MemStorage {
+    fn reader(&mut self, key: &str) -> Result<BoxReader, io::Error> {
+        let files = self.files.lock().unwrap();
+
+        let data = files.get(key).cloned().ok_or_else(|| not_found(key))?;
+
+        Ok(Box::new(MemReader::new(data)))
+    }
+
+    fn positional_reader(&mut self, key: &str) -> Result<ArcReadAt, io::Error> {
+        let files = self.files.lock().unwrap();
+
+        let data = files.get(key).cloned().ok_or_else(|| not_found(key))?;
+
+        Ok(Arc::new(MemReadAt::new(data)))
+    }
+
+    fn writer(&mut self, key: &str) -> Result<BoxWriter, io::Error> {
+        let w = MemWriter::new(self.files.clone(), key);
+        Ok(Box::new(w))
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        let files = self.files.lock().unwrap();
+
+        let keys = files
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(k, _)| k)
+            .take_while(|k| k.starts_with(prefix))
+            .cloned()
+            .collect();
+
+        Ok(keys)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), io::Error> {
+        let mut files = self.files.lock().unwrap();
+
+        files.remove(key).map(|_| ()).ok_or_else(|| not_found(key))
+    }
+
+    fn exists(&mut self, key: &str) -> Result<bool, io::Error> {
+        let files = self.files.lock().unwrap();
+        Ok(files.contains_key(key))
+    }
+
+    fn len(&mut self, key: &str) -> Result<u64, io::Error> {
+        let files = self.files.lock().unwrap();
+
+        files.get(key).map(|v| v.len() as u64).ok_or_else(|| not_found(key))
+    }
+}
+
+impl AsyncStorage for MemStorage {
+    fn async_reader(&mut self, key: &str) -> BoxFuture<'_, Result<BoxAsyncReader, io::Error>> {
+        let res = {
+            let files = self.files.lock().unwrap();
+            files.get(key).cloned().ok_or_else(|| not_found(key))
+        };
+
+        async move {
+            let r = MemReadAt::new(res?);
+            Ok(Box::new(r) as BoxAsyncReader)
+        }
+        .boxed()
+    }
+}
+
+/// The positional reader implementation that returns zero-copy slices of a shared in-memory
+/// buffer, such as a memory-mapped file.
+///
+/// It is both a [`ReadAt`] and an [`AsyncReader`].
+pub struct MemReadAt {
+    data: Bytes,
+}
+
+impl MemReadAt {
+    pub fn new(data: Bytes) -> Self {
+        Self { data }
+    }
+}
+
+impl fmt::Debug for MemReadAt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemReadAt").field("size", &self.data.len()).finish()
+    }
+}
+
+impl ReadAt for MemReadAt {
+    fn read_at(&self, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+        slice_at(&self.data, offset, len)
+    }
+
+    fn size(&self) -> Result<u64, io::Error> {
+        Ok(self.data.len() as u64)
+    }
+}
+
+impl AsyncReader for MemReadAt {
+    fn read_at(&self, offset: u64, len: u64) -> BoxFuture<'_, Result<Bytes, io::Error>> {
+        let res = slice_at(&self.data, offset, len);
+        async move { res }.boxed()
+    }
+
+    fn size(&self) -> BoxFuture<'_, Result<u64, io::Error>> {
+        let size = self.data.len() as u64;
+        async move { Ok(size) }.boxed()
+    }
+}
+
+/// Return a zero-copy slice of `len` bytes at `offset`.
+fn slice_at(data: &Bytes, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+    let end = offset.checked_add(len).filter(|end| *end <= data.len() as u64);
+
+    let Some(end) = end else {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("read {}+{} beyond size {}", offset, len, data.len()),
+        ));
+    };
+
+    Ok(data.slice(offset as usize..end as usize))
+}
+
+fn not_found(key: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("key not found: {}", key))
+}
+
+/// The reader implementation that reads from a shared in-memory buffer, such as a memory-mapped
+/// file.
+pub struct MemReader {
+    cursor: io::Cursor<Bytes>,
+}
+
+impl MemReader {
+    pub fn new(data: Bytes) -> Self {
+        Self {
+            cursor: io::Cursor::new(data),
+        }
+    }
+}
+
+impl fmt::Debug for MemReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemReader")
+            .field("size", &self.cursor.get_ref().len())
+            .field("position", &self.cursor.position())
+            .finish()
+    }
+}
+
+impl Read for MemReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        self.cursor.read(buf)
+    }
+}
+
+impl BufRead for MemReader {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        self.cursor.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.cursor.consume(amt)
+    }
+}
+
+impl Seek for MemReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> Result<u64, io::Error> {
+        self.cursor.seek(pos)
+    }
+}
+
+/// The writer implementation that writes to an in-memory buffer.
+///
+/// The data is invisible to readers until [`commit()`](`storage::Writer::commit`) is called,
+/// the same as [`FsWriter`](`crate::storage::impls::fs::FsWriter`) does.
+pub struct MemWriter {
+    buf: Option<Vec<u8>>,
+    key: String,
+    files: Arc<Mutex<BTreeMap<String, Bytes>>>,
+}
+
+impl fmt::Debug for MemWriter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemWriter")
+            .field("key", &self.key)
+            .field("size", &self.buf.as_ref().map(|b| b.len()))
+            .finish()
+    }
+}
+
+impl MemWriter {
+    fn new(files: Arc<Mutex<BTreeMap<String, Bytes>>>, key: &str) -> Self {
+        Self {
+            buf: Some(Vec::new()),
+            key: key.to_string(),
+            files,
+        }
+    }
+}
+
+impl Write for MemWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        self.buf.as_mut().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+impl storage::Writer for MemWriter {
+    fn commit(&mut self) -> Result<(), io::Error> {
+        let Some(buf) = self.buf.take() else {
+            unreachable!("MemWriter::commit() should not be called multiple times");
+        };
+
+        let mut files = self.files.lock().unwrap();
+        files.insert(self.key.clone(), Bytes::from(buf));
+
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), io::Error> {
+        self.buf = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::io::Seek;
+
+    use super::*;
+
+    #[test]
+    fn test_mem_writer_commit() -> Result<(), io::Error> {
+        let mut storage = MemStorage::new();
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+
+        // check the data is not visible
+        let res = storage.reader("test.txt");
+        assert_eq!(io::ErrorKind::NotFound, res.unwrap_err().kind());
+
+        writer.commit()?;
+
+        // check the data is visible
+        let mut reader = storage.reader("test.txt")?;
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        assert_eq!(content, "Hello, world!");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mem_storage_list_remove() -> Result<(), io::Error> {
+        let mut storage = MemStorage::new();
+
+        for key in ["a", "foo/b", "foo/c", "fox"] {
+            let mut writer = storage.writer(key)?;
+            writer.write_all(key.as_bytes())?;
+            writer.commit()?;
+        }
+
+        // Uncommitted data is not listed
+        let _writer = storage.writer("foo/e")?;
+
+        assert_eq!(storage.list("")?, vec!["a", "foo/b", "foo/c", "fox"]);
+        assert_eq!(storage.list("foo/")?, vec!["foo/b", "foo/c"]);
+        assert_eq!(storage.list("x")?, Vec::<String>::new());
+
+        assert!(storage.exists("foo/b")?);
+        assert_eq!(storage.len("foo/b")?, 5);
+
+        storage.remove("foo/b")?;
+
+        assert!(!storage.exists("foo/b")?);
+        assert_eq!(
+            storage.len("foo/b").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(
+            storage.remove("foo/b").unwrap_err().kind(),
+            io::ErrorKind::NotFound
+        );
+        assert_eq!(storage.list("foo/")?, vec!["foo/c"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mem_storage_async_reader() -> Result<(), io::Error> {
+        let mut storage = MemStorage::new();
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+        writer.commit()?;
+
+        futures::executor::block_on(async move {
+            let r = storage.async_reader("test.txt").await?;
+
+            assert_eq!(r.size().await?, 13);
+            assert_eq!(r.read_at(7, 5).await?, Bytes::from_static(b"world"));
+
+            let res = r.read_at(7, 10).await;
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+            let res = storage.async_reader("not-exist").await;
+            assert_eq!(res.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_mem_storage_positional_reader() -> Result<(), io::Error> {
+        let mut storage = MemStorage::new();
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+        writer.commit()?;
+
+        let r = storage.positional_reader("test.txt")?;
+
+        assert_eq!(r.size()?, 13);
+        assert_eq!(r.read_at(7, 5)?, Bytes::from_static(b"world"));
+
+        let res = r.read_at(7, 10);
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mem_storage_shared_by_clones() -> Result<(), io::Error> {
+        let mut storage = MemStorage::new();
+        let mut cloned = storage.clone();
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+        writer.commit()?;
+
+        let mut reader = cloned.reader("test.txt")?;
+        reader.seek(io::SeekFrom::Start(7))?;
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        assert_eq!(content, "world!");
+
+        assert_eq!(
+            r#"MemStorage { files: {"test.txt": 13} }"#,
+            format!("{:?}", cloned)
+        );
+
+        Ok(())
+    }
+}
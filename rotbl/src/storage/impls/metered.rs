@@ -0,0 +1,383 @@
+//! Provides a storage wrapper that accounts the IO of every key.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::io::Seek;
+use std::io::Write;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+use bytes::Bytes;
+
+use crate::num::format_num;
+use crate::storage;
+use crate::storage::ArcReadAt;
+use crate::storage::BoxReader;
+use crate::storage::BoxWriter;
+use crate::storage::ReadAt;
+use crate::storage::Storage;
+
+/// IO counters of a key.
+#[derive(Debug)]
+#[derive(Default)]
+pub struct IoStat {
+    read_ops: AtomicU64,
+    bytes_read: AtomicU64,
+    seek_ops: AtomicU64,
+    read_latency_ns: AtomicU64,
+
+    write_ops: AtomicU64,
+    bytes_written: AtomicU64,
+    write_latency_ns: AtomicU64,
+}
+
+impl fmt::Display for IoStat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "read: ({}, bytes: {}, seek: {}, latency: {:?}), write: ({}, bytes: {}, latency: {:?})",
+            format_num(self.read_ops()),
+            format_num(self.bytes_read()),
+            format_num(self.seek_ops()),
+            self.read_latency(),
+            format_num(self.write_ops()),
+            format_num(self.bytes_written()),
+            self.write_latency(),
+        )
+    }
+}
+
+impl IoStat {
+    /// Number of reads, including positional reads.
+    pub fn read_ops(&self) -> u64 {
+        self.read_ops.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.load(Ordering::Relaxed)
+    }
+
+    pub fn seek_ops(&self) -> u64 {
+        self.seek_ops.load(Ordering::Relaxed)
+    }
+
+    /// Cumulative time spent in reads and seeks.
+    pub fn read_latency(&self) -> Duration {
+        Duration::from_nanos(self.read_latency_ns.load(Ordering::Relaxed))
+    }
+
+    /// Number of writes, flushes and commits.
+    pub fn write_ops(&self) -> u64 {
+        self.write_ops.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.load(Ordering::Relaxed)
+    }
+
+    /// Cumulative time spent in writes, flushes and commits.
+    pub fn write_latency(&self) -> Duration {
+        Duration::from_nanos(self.write_latency_ns.load(Ordering::Relaxed))
+    }
+
+    /// Reset all counters to zero.
+    pub fn reset(&self) {
+        for c in [
+            &self.read_ops,
+            &self.bytes_read,
+            &self.seek_ops,
+            &self.read_latency_ns,
+            &self.write_ops,
+            &self.bytes_written,
+            &self.write_latency_ns,
+        ] {
+            c.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn add_read(&self, bytes: u64, start: Instant) {
+        self.read_ops.fetch_add(1, Ordering::Relaxed);
+        self.bytes_read.fetch_add(bytes, Ordering::Relaxed);
+        self.add_read_latency(start);
+    }
+
+    fn add_seek(&self, start: Instant) {
+        self.seek_ops.fetch_add(1, Ordering::Relaxed);
+        self.add_read_latency(start);
+    }
+
+    fn add_read_latency(&self, start: Instant) {
+        let ns = start.elapsed().as_nanos() as u64;
+        self.read_latency_ns.fetch_add(ns, Ordering::Relaxed);
+    }
+
+    fn add_write(&self, bytes: u64, start: Instant) {
+        self.write_ops.fetch_add(1, Ordering::Relaxed);
+        self.bytes_written.fetch_add(bytes, Ordering::Relaxed);
+
+        let ns = start.elapsed().as_nanos() as u64;
+        self.write_latency_ns.fetch_add(ns, Ordering::Relaxed);
+    }
+}
+
+/// A [`Storage`] wrapper that records the IO of every key in an [`IoStat`].
+///
+/// Clones of a `MeteredStorage` share the same statistics.
+#[derive(Debug, Clone)]
+pub struct MeteredStorage<S>
+where S: Storage
+{
+    inner: S,
+    stats: Arc<Mutex<BTreeMap<String, Arc<IoStat>>>>,
+}
+
+impl<S> MeteredStorage<S>
+where S: Storage
+{
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            stats: Default::default(),
+        }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Return the IO statistics of a key, if it has ever been opened.
+    pub fn stat(&self, key: &str) -> Option<Arc<IoStat>> {
+        let stats = self.stats.lock().unwrap();
+        stats.get(key).cloned()
+    }
+
+    /// Return the IO statistics of all keys that have ever been opened.
+    pub fn stats(&self) -> BTreeMap<String, Arc<IoStat>> {
+        self.stats.lock().unwrap().clone()
+    }
+
+    fn stat_entry(&self, key: &str) -> Arc<IoStat> {
+        let mut stats = self.stats.lock().unwrap();
+        stats.entry(key.to_string()).or_default().clone()
+    }
+}
+
+impl<S> Storage for MeteredStorage<S>
+where S: Storage
+{
+    fn reader(&mut self, key: &str) -> Result<BoxReader, io::Error> {
+        let r = MeteredReader {
+            inner: self.inner.reader(key)?,
+            stat: self.stat_entry(key),
+        };
+        Ok(Box::new(io::BufReader::new(r)))
+    }
+
+    fn positional_reader(&mut self, key: &str) -> Result<ArcReadAt, io::Error> {
+        let r = MeteredReadAt {
+            inner: self.inner.positional_reader(key)?,
+            stat: self.stat_entry(key),
+        };
+        Ok(Arc::new(r))
+    }
+
+    fn writer(&mut self, key: &str) -> Result<BoxWriter, io::Error> {
+        let w = MeteredWriter {
+            inner: self.inner.writer(key)?,
+            stat: self.stat_entry(key),
+        };
+        Ok(Box::new(w))
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        self.inner.list(prefix)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), io::Error> {
+        self.inner.remove(key)
+    }
+
+    fn exists(&mut self, key: &str) -> Result<bool, io::Error> {
+        self.inner.exists(key)
+    }
+
+    fn len(&mut self, key: &str) -> Result<u64, io::Error> {
+        self.inner.len(key)
+    }
+}
+
+/// The reader that records every `read()` and `seek()` of the inner reader.
+#[derive(Debug)]
+pub struct MeteredReader {
+    inner: BoxReader,
+    stat: Arc<IoStat>,
+}
+
+impl Read for MeteredReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let start = Instant::now();
+        let n = self.inner.read(buf)?;
+        self.stat.add_read(n as u64, start);
+        Ok(n)
+    }
+}
+
+impl Seek for MeteredReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> Result<u64, io::Error> {
+        let start = Instant::now();
+        let p = self.inner.seek(pos)?;
+        self.stat.add_seek(start);
+        Ok(p)
+    }
+}
+
+/// The positional reader that records every `read_at()` of the inner reader.
+#[derive(Debug)]
+pub struct MeteredReadAt {
+    inner: ArcReadAt,
+    stat: Arc<IoStat>,
+}
+
+impl ReadAt for MeteredReadAt {
+    fn read_at(&self, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+        let start = Instant::now();
+        let data = self.inner.read_at(offset, len)?;
+        self.stat.add_read(data.len() as u64, start);
+        Ok(data)
+    }
+
+    fn size(&self) -> Result<u64, io::Error> {
+        self.inner.size()
+    }
+}
+
+/// The writer that records every `write()`, `flush()` and `commit()` of the inner writer.
+#[derive(Debug)]
+pub struct MeteredWriter {
+    inner: BoxWriter,
+    stat: Arc<IoStat>,
+}
+
+impl Write for MeteredWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        let start = Instant::now();
+        let n = self.inner.write(buf)?;
+        self.stat.add_write(n as u64, start);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        let start = Instant::now();
+        self.inner.flush()?;
+        self.stat.add_write(0, start);
+        Ok(())
+    }
+}
+
+impl storage::Writer for MeteredWriter {
+    fn commit(&mut self) -> Result<(), io::Error> {
+        let start = Instant::now();
+        self.inner.commit()?;
+        self.stat.add_write(0, start);
+        Ok(())
+    }
+
+    fn abort(&mut self) -> Result<(), io::Error> {
+        self.inner.abort()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::impls::mem::MemStorage;
+    use crate::v001::Config;
+    use crate::v001::Rotbl;
+    use crate::v001::RotblMeta;
+    use crate::v001::SeqMarked;
+
+    #[test]
+    fn test_metered_storage_reader() -> Result<(), io::Error> {
+        let mut storage = MeteredStorage::new(MemStorage::new());
+
+        let mut w = storage.writer("foo")?;
+        w.write_all(b"Hello, world!")?;
+        w.commit()?;
+
+        let stat = storage.stat("foo").unwrap();
+        assert_eq!(stat.bytes_written(), 13);
+        assert_eq!(stat.write_ops(), 2);
+
+        let mut r = storage.reader("foo")?;
+        r.seek(io::SeekFrom::Start(7))?;
+        let mut content = String::new();
+        r.read_to_string(&mut content)?;
+        assert_eq!(content, "world!");
+
+        assert_eq!(stat.bytes_read(), 6);
+        assert_eq!(stat.seek_ops(), 1);
+
+        assert!(storage.stat("bar").is_none());
+        assert_eq!(storage.stats().keys().collect::<Vec<_>>(), vec!["foo"]);
+
+        stat.reset();
+        assert_eq!(
+            "read: (0, bytes: 0, seek: 0, latency: 0ns), write: (0, bytes: 0, latency: 0ns)",
+            stat.to_string()
+        );
+
+        Ok(())
+    }
+
+    /// Compare the IO cost of opening a table with loading a block.
+    #[test]
+    fn test_metered_storage_rotbl_open() -> anyhow::Result<()> {
+        let storage = MeteredStorage::new(MemStorage::new());
+
+        let mut config = Config::default();
+        config.block_config.max_items = Some(3);
+
+        let kvs = ["a", "b", "c", "d"].into_iter().map(|k| {
+            (
+                k.to_string(),
+                SeqMarked::new_normal(1, k.as_bytes().to_vec()),
+            )
+        });
+
+        let t = Rotbl::create_table(
+            storage.clone(),
+            config.clone(),
+            "foo.rot",
+            RotblMeta::new(1, "hello"),
+            kvs,
+        )?;
+
+        let file_size = t.file_size();
+        let stat = storage.stat("foo.rot").unwrap();
+        assert_eq!(stat.bytes_written(), file_size);
+        assert_eq!(stat.read_ops(), 0);
+
+        let t: Rotbl = Rotbl::open(storage.clone(), config, "foo.rot")?;
+
+        // header+table_id, footer, index, meta, stat
+        assert_eq!(stat.read_ops(), 5);
+
+        let block_size = |i| t.block_index().get_index_entry_by_num(i).unwrap().size;
+        let blocks_size = block_size(0) + block_size(1);
+        assert_eq!(stat.bytes_read(), file_size - blocks_size);
+
+        stat.reset();
+        t.load_block(1)?;
+
+        assert_eq!(stat.read_ops(), 1);
+        assert_eq!(stat.bytes_read(), block_size(1));
+
+        Ok(())
+    }
+}
@@ -1 +1,7 @@
+pub mod blocking;
+pub mod encrypted;
+pub mod fault;
 pub mod fs;
+pub mod mem;
+pub mod metered;
+pub mod object_store;
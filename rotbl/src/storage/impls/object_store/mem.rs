@@ -0,0 +1,163 @@
+//! An in-process [`ObjectStore`] stand-in for testing.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::ops::Bound;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+
+use bytes::Bytes;
+
+use crate::num::format_num;
+use crate::storage::impls::object_store::ObjectStore;
+
+/// An in-memory [`ObjectStore`] that counts the requests it serves.
+///
+/// It is used as a stand-in of a remote object store in tests,
+/// to assert what is fetched and how many round trips are made.
+#[derive(Default)]
+pub struct MemObjectStore {
+    objects: Mutex<BTreeMap<String, Bytes>>,
+    stat: ObjectStoreStat,
+}
+
+impl fmt::Debug for MemObjectStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let objects = self.objects.lock().unwrap();
+        let sizes = objects.iter().map(|(k, v)| (k.as_str(), v.len())).collect::<BTreeMap<_, _>>();
+        f.debug_struct("MemObjectStore").field("objects", &sizes).field("stat", &self.stat).finish()
+    }
+}
+
+impl MemObjectStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stat(&self) -> &ObjectStoreStat {
+        &self.stat
+    }
+}
+
+impl ObjectStore for MemObjectStore {
+    fn get_range(&self, key: &str, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+        self.stat.get.fetch_add(1, Ordering::Relaxed);
+
+        let data = {
+            let objects = self.objects.lock().unwrap();
+            objects.get(key).cloned().ok_or_else(|| not_found(key))?
+        };
+
+        let end = offset.checked_add(len).filter(|end| *end <= data.len() as u64);
+        let Some(end) = end else {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("range {}+{} beyond size {}", offset, len, data.len()),
+            ));
+        };
+
+        self.stat.get_bytes.fetch_add(len, Ordering::Relaxed);
+        Ok(data.slice(offset as usize..end as usize))
+    }
+
+    fn head(&self, key: &str) -> Result<u64, io::Error> {
+        self.stat.head.fetch_add(1, Ordering::Relaxed);
+
+        let objects = self.objects.lock().unwrap();
+        objects.get(key).map(|v| v.len() as u64).ok_or_else(|| not_found(key))
+    }
+
+    fn put(&self, key: &str, data: Bytes) -> Result<(), io::Error> {
+        self.stat.put.fetch_add(1, Ordering::Relaxed);
+        self.stat.put_bytes.fetch_add(data.len() as u64, Ordering::Relaxed);
+
+        let mut objects = self.objects.lock().unwrap();
+        objects.insert(key.to_string(), data);
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), io::Error> {
+        let mut objects = self.objects.lock().unwrap();
+        objects.remove(key).map(|_| ()).ok_or_else(|| not_found(key))
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        let objects = self.objects.lock().unwrap();
+
+        let keys = objects
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(k, _)| k)
+            .take_while(|k| k.starts_with(prefix))
+            .cloned()
+            .collect();
+
+        Ok(keys)
+    }
+}
+
+fn not_found(key: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("object not found: {}", key),
+    )
+}
+
+/// Counts of requests served by a [`MemObjectStore`].
+#[derive(Debug)]
+#[derive(Default)]
+pub struct ObjectStoreStat {
+    get: AtomicU64,
+    get_bytes: AtomicU64,
+    head: AtomicU64,
+    put: AtomicU64,
+    put_bytes: AtomicU64,
+}
+
+impl fmt::Display for ObjectStoreStat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "get: ({}, bytes: {}), head: {}, put: ({}, bytes: {})",
+            format_num(self.get()),
+            format_num(self.get_bytes()),
+            format_num(self.head()),
+            format_num(self.put()),
+            format_num(self.put_bytes()),
+        )
+    }
+}
+
+impl ObjectStoreStat {
+    /// Number of ranged GET requests.
+    pub fn get(&self) -> u64 {
+        self.get.load(Ordering::Relaxed)
+    }
+
+    /// Total bytes returned by ranged GET requests.
+    pub fn get_bytes(&self) -> u64 {
+        self.get_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn head(&self) -> u64 {
+        self.head.load(Ordering::Relaxed)
+    }
+
+    pub fn put(&self) -> u64 {
+        self.put.load(Ordering::Relaxed)
+    }
+
+    pub fn put_bytes(&self) -> u64 {
+        self.put_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Reset all counters to zero.
+    pub fn reset(&self) {
+        self.get.store(0, Ordering::Relaxed);
+        self.get_bytes.store(0, Ordering::Relaxed);
+        self.head.store(0, Ordering::Relaxed);
+        self.put.store(0, Ordering::Relaxed);
+        self.put_bytes.store(0, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,350 @@
+//! Provides a storage implementation upon an S3-compatible object store.
+//!
+//! An object is read with ranged GETs,
+//! thus opening a table only fetches the header, footer, index, meta and stat,
+//! and a block is fetched only when it is loaded.
+//!
+//! The underlying object store is abstracted by the [`ObjectStore`] trait,
+//! so that a real client or the in-process stand-in [`MemObjectStore`] can be plugged in.
+
+pub mod mem;
+
+use std::fmt;
+use std::fmt::Debug;
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
+
+use bytes::Bytes;
+pub use mem::MemObjectStore;
+
+use crate::storage;
+use crate::storage::ArcReadAt;
+use crate::storage::BoxReader;
+use crate::storage::BoxWriter;
+use crate::storage::ReadAt;
+use crate::storage::ReadAtReader;
+use crate::storage::Storage;
+
+/// The size of a ranged GET issued by the sequential reader returned by
+/// [`ObjectStoreStorage::reader()`].
+const READ_CHUNK_SIZE: u64 = 1024 * 1024;
+
+/// The client API of an S3-compatible object store.
+///
+/// A missing object is reported with an error of kind [`io::ErrorKind::NotFound`].
+pub trait ObjectStore
+where Self: Send + Sync + Debug + 'static
+{
+    /// Fetch `len` bytes of the object starting at `offset`, i.e., a GET with a `Range` header.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::UnexpectedEof`]
+    /// if the range is beyond the end of the object.
+    fn get_range(&self, key: &str, offset: u64, len: u64) -> Result<Bytes, io::Error>;
+
+    /// Return the size in bytes of the object, i.e., a HEAD request.
+    fn head(&self, key: &str) -> Result<u64, io::Error>;
+
+    /// Upload the whole object. The object becomes visible atomically when it returns.
+    fn put(&self, key: &str, data: Bytes) -> Result<(), io::Error>;
+
+    /// Delete the object.
+    fn delete(&self, key: &str) -> Result<(), io::Error>;
+
+    /// List keys of all objects starting with `prefix`, in lexicographical order.
+    fn list(&self, prefix: &str) -> Result<Vec<String>, io::Error>;
+}
+
+/// The storage implementation that stores every key as an object in an [`ObjectStore`].
+#[derive(Debug)]
+pub struct ObjectStoreStorage<C>
+where C: ObjectStore
+{
+    client: Arc<C>,
+}
+
+impl<C> Clone for ObjectStoreStorage<C>
+where C: ObjectStore
+{
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+        }
+    }
+}
+
+impl<C> ObjectStoreStorage<C>
+where C: ObjectStore
+{
+    pub fn new(client: C) -> Self {
+        Self {
+            client: Arc::new(client),
+        }
+    }
+
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+
+    fn read_at(&self, key: &str) -> Result<ObjectReadAt<C>, io::Error> {
+        let size = self.client.head(key)?;
+
+        Ok(ObjectReadAt {
+            client: self.client.clone(),
+            key: key.to_string(),
+            size,
+        })
+    }
+}
+
+impl<C> Storage for ObjectStoreStorage<C>
+where C: ObjectStore
+{
+    fn reader(&mut self, key: &str) -> Result<BoxReader, io::Error> {
+        let r = ReadAtReader::new(Arc::new(self.read_at(key)?), READ_CHUNK_SIZE)?;
+        Ok(Box::new(r))
+    }
+
+    fn positional_reader(&mut self, key: &str) -> Result<ArcReadAt, io::Error> {
+        Ok(Arc::new(self.read_at(key)?))
+    }
+
+    fn writer(&mut self, key: &str) -> Result<BoxWriter, io::Error> {
+        let w = ObjectWriter {
+            buf: Some(Vec::new()),
+            key: key.to_string(),
+            client: self.client.clone(),
+        };
+        Ok(Box::new(w))
+    }
+
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        self.client.list(prefix)
+    }
+
+    fn remove(&mut self, key: &str) -> Result<(), io::Error> {
+        self.client.delete(key)
+    }
+
+    fn exists(&mut self, key: &str) -> Result<bool, io::Error> {
+        match self.client.head(key) {
+            Ok(_) => Ok(true),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn len(&mut self, key: &str) -> Result<u64, io::Error> {
+        self.client.head(key)
+    }
+}
+
+/// The positional reader that issues a ranged GET for every read.
+///
+/// The object size is fetched once when it is opened.
+#[derive(Debug)]
+pub struct ObjectReadAt<C>
+where C: ObjectStore
+{
+    client: Arc<C>,
+    key: String,
+    size: u64,
+}
+
+impl<C> ReadAt for ObjectReadAt<C>
+where C: ObjectStore
+{
+    fn read_at(&self, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+        let end = offset.checked_add(len).filter(|end| *end <= self.size);
+        if end.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "read {}+{} beyond size {} of object: {}",
+                    offset, len, self.size, self.key
+                ),
+            ));
+        }
+
+        self.client.get_range(&self.key, offset, len)
+    }
+
+    fn size(&self) -> Result<u64, io::Error> {
+        Ok(self.size)
+    }
+}
+
+/// The writer that buffers all data in memory and uploads it on commit.
+///
+/// The object is invisible until [`commit()`](`storage::Writer::commit`) is called,
+/// the same as [`FsWriter`](`crate::storage::impls::fs::FsWriter`) does.
+pub struct ObjectWriter<C>
+where C: ObjectStore
+{
+    buf: Option<Vec<u8>>,
+    key: String,
+    client: Arc<C>,
+}
+
+impl<C> fmt::Debug for ObjectWriter<C>
+where C: ObjectStore
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ObjectWriter")
+            .field("key", &self.key)
+            .field("size", &self.buf.as_ref().map(|b| b.len()))
+            .finish()
+    }
+}
+
+impl<C> Write for ObjectWriter<C>
+where C: ObjectStore
+{
+    fn write(&mut self, buf: &[u8]) -> Result<usize, io::Error> {
+        self.buf.as_mut().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
+}
+
+impl<C> storage::Writer for ObjectWriter<C>
+where C: ObjectStore
+{
+    fn commit(&mut self) -> Result<(), io::Error> {
+        let Some(buf) = self.buf.take() else {
+            unreachable!("ObjectWriter::commit() should not be called multiple times");
+        };
+
+        self.client.put(&self.key, Bytes::from(buf))
+    }
+
+    fn abort(&mut self) -> Result<(), io::Error> {
+        self.buf = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v001::Config;
+    use crate::v001::Rotbl;
+    use crate::v001::RotblMeta;
+    use crate::v001::SeqMarked;
+
+    #[test]
+    fn test_object_writer_commit() -> Result<(), io::Error> {
+        let mut storage = ObjectStoreStorage::new(MemObjectStore::new());
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+
+        // check the object is not visible
+        assert!(!storage.exists("test.txt")?);
+        assert_eq!(storage.client().stat().put(), 0);
+
+        writer.commit()?;
+
+        assert!(storage.exists("test.txt")?);
+        assert_eq!(storage.len("test.txt")?, 13);
+        assert_eq!(storage.list("test")?, vec!["test.txt"]);
+        assert_eq!(storage.client().stat().put(), 1);
+
+        let mut reader = storage.reader("test.txt")?;
+        reader.seek(io::SeekFrom::Start(7))?;
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        assert_eq!(content, "world!");
+
+        reader.seek(io::SeekFrom::End(-6))?;
+        let mut content = String::new();
+        reader.read_to_string(&mut content)?;
+        assert_eq!(content, "world!");
+
+        let res = reader.seek(io::SeekFrom::Current(-100));
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+
+        storage.remove("test.txt")?;
+        assert!(!storage.exists("test.txt")?);
+
+        let res = storage.reader("test.txt");
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::NotFound);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_read_at() -> Result<(), io::Error> {
+        let mut storage = ObjectStoreStorage::new(MemObjectStore::new());
+
+        let mut writer = storage.writer("test.txt")?;
+        writer.write_all(b"Hello, world!")?;
+        writer.commit()?;
+
+        let r = storage.positional_reader("test.txt")?;
+        assert_eq!(r.size()?, 13);
+        assert_eq!(r.read_at(7, 5)?, Bytes::from_static(b"world"));
+
+        let res = r.read_at(7, 10);
+        assert_eq!(res.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+        // Out of range read is rejected without a request
+        let stat = storage.client().stat();
+        assert_eq!(stat.get(), 1);
+        assert_eq!(stat.get_bytes(), 5);
+
+        Ok(())
+    }
+
+    /// Opening a table and loading a block only fetches the required ranges.
+    #[test]
+    fn test_object_store_rotbl_ranged_read() -> anyhow::Result<()> {
+        let mut storage = ObjectStoreStorage::new(MemObjectStore::new());
+
+        let mut config = Config::default();
+        config.block_config.max_items = Some(3);
+
+        let kvs = ["a", "b", "c", "d"].into_iter().map(|k| {
+            (
+                k.to_string(),
+                SeqMarked::new_normal(1, k.as_bytes().to_vec()),
+            )
+        });
+
+        let t = Rotbl::create_table(
+            storage.clone(),
+            config.clone(),
+            "foo.rot",
+            RotblMeta::new(1, "hello"),
+            kvs,
+        )?;
+        let file_size = t.file_size();
+        assert_eq!(storage.len("foo.rot")?, file_size);
+
+        let stat = storage.client().stat();
+        stat.reset();
+
+        let t: Rotbl = Rotbl::open(storage.clone(), config, "foo.rot")?;
+
+        // header+table_id, footer, index, meta, stat
+        assert_eq!(stat.get(), 5);
+        assert_eq!(stat.head(), 1);
+
+        let block_0_size = t.block_index().get_index_entry_by_num(0).unwrap().size;
+        let block_1_size = t.block_index().get_index_entry_by_num(1).unwrap().size;
+        assert_eq!(stat.get_bytes(), file_size - block_0_size - block_1_size);
+
+        let b = t.load_block(1)?;
+        assert_eq!(
+            b.range::<Vec<u8>, _>(..)?.map(|(k, _)| k.clone()).collect::<Vec<_>>(),
+            vec![b"d".to_vec()]
+        );
+
+        assert_eq!(stat.get(), 6);
+        assert_eq!(stat.get_bytes(), file_size - block_0_size);
+
+        Ok(())
+    }
+}
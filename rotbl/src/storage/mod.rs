@@ -2,14 +2,26 @@
 
 pub mod impls;
 
+use std::fmt;
 use std::fmt::Debug;
 use std::io;
 use std::io::BufRead;
+use std::io::Read;
 use std::io::Seek;
 use std::io::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use bytes::Buf;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+
+use crate::buf::new_uninitialized;
 
 pub type BoxReader = Box<dyn Reader + Send>;
 pub type BoxWriter = Box<dyn Writer + Send>;
+pub type BoxAsyncReader = Box<dyn AsyncReader>;
+pub type ArcReadAt = Arc<dyn ReadAt>;
 
 /// The type of the reader.
 ///
@@ -25,6 +37,158 @@ where Self: Seek + BufRead + Send + Debug + 'static
 
 impl<T: Seek + BufRead + Send + Debug + 'static> Reader for T {}
 
+/// A reader that reads a range of bytes at a specific position, like `pread(2)`.
+///
+/// Unlike [`Reader`], it does not have a cursor and reads with `&self`,
+/// so that multiple threads can read different parts of the data concurrently
+/// without a lock.
+pub trait ReadAt
+where Self: Send + Sync + Debug + 'static
+{
+    /// Read exactly `len` bytes starting at `offset`.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::UnexpectedEof`] if there are not enough bytes.
+    fn read_at(&self, offset: u64, len: u64) -> Result<Bytes, io::Error>;
+
+    /// Return the total size in bytes of the data.
+    fn size(&self) -> Result<u64, io::Error>;
+}
+
+/// A [`ReadAt`] implementation built upon a seekable [`Reader`].
+///
+/// Every read holds a lock to the inner reader to seek and read,
+/// thus concurrent reads are serialized.
+/// It is the default positional reader for a [`Storage`] that does not provide its own.
+#[derive(Debug)]
+pub struct SeekReadAt {
+    inner: Mutex<BoxReader>,
+}
+
+impl SeekReadAt {
+    pub fn new(inner: BoxReader) -> Self {
+        Self {
+            inner: Mutex::new(inner),
+        }
+    }
+}
+
+impl ReadAt for SeekReadAt {
+    fn read_at(&self, offset: u64, len: u64) -> Result<Bytes, io::Error> {
+        let mut buf = new_uninitialized(len as usize);
+        {
+            let mut f = self.inner.lock().unwrap();
+            f.seek(io::SeekFrom::Start(offset))?;
+            f.read_exact(&mut buf)?;
+        }
+        Ok(Bytes::from(buf))
+    }
+
+    fn size(&self) -> Result<u64, io::Error> {
+        let mut f = self.inner.lock().unwrap();
+        f.seek(io::SeekFrom::End(0))
+    }
+}
+
+/// A sequential [`Reader`] built upon a [`ReadAt`], the inverse of [`SeekReadAt`].
+///
+/// It reads `chunk_size` bytes at a time from the positional reader and buffers them.
+/// It is used by a [`Storage`] whose backend only supports positional reads.
+pub struct ReadAtReader {
+    inner: ArcReadAt,
+
+    chunk_size: u64,
+
+    /// The total size of the data.
+    size: u64,
+
+    /// The position of the first byte in `buf`.
+    pos: u64,
+
+    /// The read but not yet consumed bytes.
+    buf: Bytes,
+}
+
+impl fmt::Debug for ReadAtReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadAtReader")
+            .field("inner", &self.inner)
+            .field("chunk_size", &self.chunk_size)
+            .field("size", &self.size)
+            .field("position", &self.pos)
+            .field("buffered", &self.buf.len())
+            .finish()
+    }
+}
+
+impl ReadAtReader {
+    pub fn new(inner: ArcReadAt, chunk_size: u64) -> Result<Self, io::Error> {
+        let size = inner.size()?;
+
+        Ok(Self {
+            inner,
+            chunk_size,
+            size,
+            pos: 0,
+            buf: Bytes::new(),
+        })
+    }
+}
+
+impl Read for ReadAtReader {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, io::Error> {
+        let available = self.fill_buf()?;
+
+        let n = available.len().min(buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+
+        self.consume(n);
+        Ok(n)
+    }
+}
+
+impl BufRead for ReadAtReader {
+    fn fill_buf(&mut self) -> Result<&[u8], io::Error> {
+        if self.buf.is_empty() && self.pos < self.size {
+            let len = self.chunk_size.min(self.size - self.pos);
+            self.buf = self.inner.read_at(self.pos, len)?;
+        }
+
+        Ok(&self.buf)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.buf.advance(amt);
+        self.pos += amt as u64;
+    }
+}
+
+impl Seek for ReadAtReader {
+    fn seek(&mut self, pos: io::SeekFrom) -> Result<u64, io::Error> {
+        let new_pos = match pos {
+            io::SeekFrom::Start(p) => Some(p),
+            io::SeekFrom::End(d) => self.size.checked_add_signed(d),
+            io::SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+        };
+
+        let Some(new_pos) = new_pos else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            ));
+        };
+
+        // Keep the buffered bytes if the new position is still in the buffer.
+        if new_pos >= self.pos && new_pos - self.pos <= self.buf.len() as u64 {
+            self.buf.advance((new_pos - self.pos) as usize);
+        } else {
+            self.buf = Bytes::new();
+        }
+        self.pos = new_pos;
+
+        Ok(new_pos)
+    }
+}
+
 /// Represents a writer for the storage system.
 ///
 /// The writer must implement the `Write` trait to handle data writing operations.
@@ -46,6 +210,18 @@ where Self: Write + Debug + 'static
     ///
     /// Returns an `io::Error` if the commit operation fails.
     fn commit(&mut self) -> Result<(), io::Error>;
+
+    /// Discards all written data without making it visible.
+    ///
+    /// After calling this method, the writer object should not be used again.
+    /// Dropping an uncommitted writer should have the same effect,
+    /// but this method gives the caller a chance to see the error.
+    ///
+    /// The default implementation does nothing,
+    /// which is correct for a writer that keeps uncommitted data only in memory.
+    fn abort(&mut self) -> Result<(), io::Error> {
+        Ok(())
+    }
 }
 
 /// This trait defines the behavior required to read and write data to persistent storage.
@@ -55,6 +231,134 @@ where Self: Debug + Clone + Send + 'static
     /// Get a reader to read the data of the given key.
     fn reader(&mut self, key: &str) -> Result<BoxReader, io::Error>;
 
+    /// Get a positional reader to read the data of the given key concurrently.
+    ///
+    /// The default implementation wraps [`Storage::reader()`] with a lock.
+    /// An implementation should override it if the backend supports reading at a position
+    /// without a shared cursor.
+    fn positional_reader(&mut self, key: &str) -> Result<ArcReadAt, io::Error> {
+        let r = self.reader(key)?;
+        Ok(Arc::new(SeekReadAt::new(r)))
+    }
+
     /// Get a writer to write data to a specific key in the storage.
     fn writer(&mut self, key: &str) -> Result<BoxWriter, io::Error>;
+
+    /// List all committed keys that start with `prefix`, in lexicographic order.
+    ///
+    /// Data that is being written but not yet committed is not listed.
+    ///
+    /// The default implementation returns an error of kind [`io::ErrorKind::Unsupported`].
+    fn list(&mut self, prefix: &str) -> Result<Vec<String>, io::Error> {
+        Err(unsupported("list", prefix))
+    }
+
+    /// Remove the data of the given key.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] if the key does not exist.
+    ///
+    /// The default implementation returns an error of kind [`io::ErrorKind::Unsupported`].
+    fn remove(&mut self, key: &str) -> Result<(), io::Error> {
+        Err(unsupported("remove", key))
+    }
+
+    /// Return `true` if the data of the given key exists.
+    ///
+    /// The default implementation returns an error of kind [`io::ErrorKind::Unsupported`].
+    fn exists(&mut self, key: &str) -> Result<bool, io::Error> {
+        Err(unsupported("exists", key))
+    }
+
+    /// Return the size in bytes of the data of the given key.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::NotFound`] if the key does not exist.
+    ///
+    /// The default implementation returns an error of kind [`io::ErrorKind::Unsupported`].
+    fn len(&mut self, key: &str) -> Result<u64, io::Error> {
+        Err(unsupported("len", key))
+    }
+}
+
+/// The error returned by a [`Storage`] method that the implementation does not provide.
+fn unsupported(op: &str, key: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("Storage::{}() is not supported, key: {}", op, key),
+    )
+}
+
+/// The async counterpart of [`Reader`].
+///
+/// Instead of `Seek` + `Read`, an async reader reads a range of bytes at a specific position,
+/// so that the caller does not need to hold a cursor across await points.
+/// Reading from an async reader must not block the runtime thread.
+pub trait AsyncReader
+where Self: Send + Sync + Debug + 'static
+{
+    /// Read exactly `len` bytes starting at `offset`.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::UnexpectedEof`] if there are not enough bytes.
+    fn read_at(&self, offset: u64, len: u64) -> BoxFuture<'_, Result<Bytes, io::Error>>;
+
+    /// Return the total size in bytes of the data.
+    fn size(&self) -> BoxFuture<'_, Result<u64, io::Error>>;
+}
+
+/// The async counterpart of [`Storage`] to read data without blocking the runtime.
+///
+/// A sync [`Storage`] can be used as an `AsyncStorage` by wrapping it with
+/// [`BlockingAdapter`](`crate::storage::impls::blocking::BlockingAdapter`).
+pub trait AsyncStorage
+where Self: Debug + Clone + Send + 'static
+{
+    /// Get an async reader to read the data of the given key.
+    fn async_reader(&mut self, key: &str) -> BoxFuture<'_, Result<BoxAsyncReader, io::Error>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::storage::impls::mem::MemStorage;
+    use crate::storage::BoxReader;
+    use crate::storage::BoxWriter;
+    use crate::storage::Storage;
+
+    /// A storage that implements only the required methods.
+    #[derive(Debug, Clone)]
+    struct ReadWriteOnly(MemStorage);
+
+    impl Storage for ReadWriteOnly {
+        fn reader(&mut self, key: &str) -> Result<BoxReader, io::Error> {
+            self.0.reader(key)
+        }
+
+        fn writer(&mut self, key: &str) -> Result<BoxWriter, io::Error> {
+            self.0.writer(key)
+        }
+    }
+
+    #[test]
+    fn test_storage_default_methods_unsupported() -> Result<(), io::Error> {
+        let mut s = ReadWriteOnly(MemStorage::new());
+
+        let mut w = s.writer("foo")?;
+        w.write_all(b"bar")?;
+        w.commit()?;
+
+        assert_eq!(b"bar", s.positional_reader("foo")?.read_at(0, 3)?.as_ref());
+
+        assert_eq!(io::ErrorKind::Unsupported, s.list("").unwrap_err().kind());
+        assert_eq!(
+            io::ErrorKind::Unsupported,
+            s.remove("foo").unwrap_err().kind()
+        );
+        assert_eq!(
+            io::ErrorKind::Unsupported,
+            s.exists("foo").unwrap_err().kind()
+        );
+        assert_eq!(io::ErrorKind::Unsupported, s.len("foo").unwrap_err().kind());
+
+        Ok(())
+    }
 }
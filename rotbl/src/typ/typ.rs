@@ -11,6 +11,10 @@ const ROTBL: [u8; 8] = *b"rotbl\0\0\0";
 const ROTBL_META: [u8; 8] = *b"rotbl_m\0";
 const BLOCK: [u8; 8] = *b"blk\0\0\0\0\0";
 const BLOCK_INDEX: [u8; 8] = *b"blk_idx\0";
+const SECTIONS: [u8; 8] = *b"sections";
+const BLOOM_FILTER: [u8; 8] = *b"bloom\0\0\0";
+const PREFIX_BLOOM_FILTER: [u8; 8] = *b"pfxbloom";
+const PROPERTIES: [u8; 8] = *b"props\0\0\0";
 
 #[derive(Debug)]
 #[derive(Clone, Copy)]
@@ -21,6 +25,10 @@ pub enum Type {
     RotblMeta,
     Block,
     BlockIndex,
+    Sections,
+    BloomFilter,
+    PrefixBloomFilter,
+    Properties,
 }
 
 impl fmt::Display for Type {
@@ -43,6 +51,10 @@ impl codeq::Encode for Type {
             Type::RotblMeta => &ROTBL_META,
             Type::Block => &BLOCK,
             Type::BlockIndex => &BLOCK_INDEX,
+            Type::Sections => &SECTIONS,
+            Type::BloomFilter => &BLOOM_FILTER,
+            Type::PrefixBloomFilter => &PREFIX_BLOOM_FILTER,
+            Type::Properties => &PROPERTIES,
         };
         w.write_all(b)?;
 
@@ -61,6 +73,10 @@ impl codeq::Decode for Type {
             ROTBL_META => Ok(Type::RotblMeta),
             BLOCK => Ok(Type::Block),
             BLOCK_INDEX => Ok(Type::BlockIndex),
+            SECTIONS => Ok(Type::Sections),
+            BLOOM_FILTER => Ok(Type::BloomFilter),
+            PREFIX_BLOOM_FILTER => Ok(Type::PrefixBloomFilter),
+            PROPERTIES => Ok(Type::Properties),
             _ => Err(Error::new(
                 io::ErrorKind::InvalidData,
                 format!("invalid type: {:?}", buf),
@@ -76,8 +92,12 @@ mod tests {
 
     use crate::typ::typ::BLOCK;
     use crate::typ::typ::BLOCK_INDEX;
+    use crate::typ::typ::BLOOM_FILTER;
+    use crate::typ::typ::PREFIX_BLOOM_FILTER;
+    use crate::typ::typ::PROPERTIES;
     use crate::typ::typ::ROTBL;
     use crate::typ::typ::ROTBL_META;
+    use crate::typ::typ::SECTIONS;
     use crate::typ::typ::VL_ARRAY;
     use crate::typ::Type;
 
@@ -122,6 +142,38 @@ mod tests {
             assert_eq!(b, BLOCK_INDEX);
             assert_eq!(Type::decode(&mut b.as_slice())?, Type::BlockIndex);
         }
+
+        {
+            let mut b = Vec::new();
+            let n = Type::Sections.encode(&mut b)?;
+            assert_eq!(n, 8);
+            assert_eq!(b, SECTIONS);
+            assert_eq!(Type::decode(&mut b.as_slice())?, Type::Sections);
+        }
+
+        {
+            let mut b = Vec::new();
+            let n = Type::BloomFilter.encode(&mut b)?;
+            assert_eq!(n, 8);
+            assert_eq!(b, BLOOM_FILTER);
+            assert_eq!(Type::decode(&mut b.as_slice())?, Type::BloomFilter);
+        }
+
+        {
+            let mut b = Vec::new();
+            let n = Type::PrefixBloomFilter.encode(&mut b)?;
+            assert_eq!(n, 8);
+            assert_eq!(b, PREFIX_BLOOM_FILTER);
+            assert_eq!(Type::decode(&mut b.as_slice())?, Type::PrefixBloomFilter);
+        }
+
+        {
+            let mut b = Vec::new();
+            let n = Type::Properties.encode(&mut b)?;
+            assert_eq!(n, 8);
+            assert_eq!(b, PROPERTIES);
+            assert_eq!(Type::decode(&mut b.as_slice())?, Type::Properties);
+        }
         Ok(())
     }
 }
@@ -4,7 +4,9 @@ use std::collections::BTreeMap;
 use std::io::Error;
 use std::io::Read;
 use std::io::Write;
+use std::ops::Bound;
 use std::ops::RangeBounds;
+use std::sync::OnceLock;
 
 use codeq::config::CodeqConfig;
 use codeq::Decode;
@@ -15,92 +17,326 @@ use crate::typ::Type;
 use crate::v001::bincode_config::bincode_config;
 use crate::v001::block_encoding_meta::BlockEncodingMeta;
 use crate::v001::header::Header;
+use crate::v001::packed_entries;
+use crate::v001::packed_entries::PackedEntries;
+use crate::v001::prefix_keys;
+use crate::v001::prefix_keys::PrefixKeys;
 use crate::v001::types::Checksum;
+use crate::v001::value::Value;
+use crate::v001::Compression;
 use crate::v001::SeqMarked;
 use crate::version::Version;
 
 /// Iterator of key-values inside a block.
-pub struct BlockIter<'a> {
-    inner: Range<'a, String, SeqMarked>,
+pub struct BlockIter<'a, V = SeqMarked> {
+    inner: Range<'a, Vec<u8>, V>,
 }
 
-impl<'a> Iterator for BlockIter<'a> {
-    type Item = (&'a String, &'a SeqMarked);
+impl<'a, V> Iterator for BlockIter<'a, V> {
+    type Item = (&'a Vec<u8>, &'a V);
 
     fn next(&mut self) -> Option<Self::Item> {
         self.inner.next()
     }
 }
 
+/// Iterator of key-values inside a block, without decoding all of them.
+///
+/// Keys are slices of the block and values are decoded one at a time.
+pub struct BlockScan<'a, V = SeqMarked> {
+    inner: BlockScanInner<'a, V>,
+}
+
+enum BlockScanInner<'a, V> {
+    Map(Range<'a, Vec<u8>, V>),
+    Packed {
+        entries: &'a PackedEntries,
+        indexes: std::ops::Range<usize>,
+    },
+}
+
+impl<'a, V> Iterator for BlockScan<'a, V>
+where V: Value
+{
+    type Item = Result<(&'a [u8], V), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.inner {
+            BlockScanInner::Map(range) => range.next().map(|(k, v)| Ok((k.as_slice(), v.clone()))),
+            BlockScanInner::Packed { entries, indexes } => {
+                let i = indexes.next()?;
+                Some(entries.value(i).map(|v| (entries.key(i), v)))
+            }
+        }
+    }
+}
+
+/// A block of sorted key-values.
+///
+/// The data part of a block is encoded in one of the layouts:
+/// - [`Version::V001`]: a bincode encoded `BTreeMap`.
+/// - [`Version::V002`]: the same as V001, compressed.
+/// - [`Version::V003`]: keys share prefixes with restart points, optionally compressed.
+/// - [`Version::V004`]: keys and values are packed with offset tables, optionally compressed.
+///
+/// A decoded V003 or V004 block keeps the raw data to serve point lookups without decoding all
+/// of it, and decodes the map on the first [`Block::range()`] access.
+/// A V004 block is also scanned in place with [`Block::scan()`], without decoding the map.
+///
+/// Values are of type `V`, which is [`SeqMarked`] by default.
 #[derive(Debug)]
 #[derive(Clone)]
-#[derive(PartialEq, Eq)]
-pub struct Block {
+pub struct Block<V = SeqMarked> {
     header: Header,
 
     meta: BlockEncodingMeta,
 
-    data: BTreeMap<String, SeqMarked>,
+    data: OnceLock<BTreeMap<Vec<u8>, V>>,
+
+    /// The undecoded data of a V003 block.
+    prefix_keys: Option<PrefixKeys>,
+
+    /// Store a full key every so many entries. `None` for the layouts before V003.
+    restart_interval: Option<usize>,
+
+    /// The undecoded data of a V004 block.
+    packed_entries: Option<PackedEntries>,
+
+    /// Whether to encode in the packed layout of V004.
+    packed: bool,
 }
 
-impl Block {
-    pub fn new(block_num: u32, data: BTreeMap<String, SeqMarked>) -> Self {
+impl<V: Value> PartialEq for Block<V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header
+            && self.meta == other.meta
+            && self.restart_interval == other.restart_interval
+            && self.packed == other.packed
+            && self.entries().ok() == other.entries().ok()
+    }
+}
+
+impl<V: Value + Eq> Eq for Block<V> {}
+
+impl<V> Block<V> {
+    pub fn new(block_num: u32, data: BTreeMap<Vec<u8>, V>) -> Self {
         let header = Header::new(Type::Block, Version::V001);
         let meta = BlockEncodingMeta::new(block_num, 0);
-        Self { header, meta, data }
+        Self {
+            header,
+            meta,
+            data: OnceLock::from(data),
+            prefix_keys: None,
+            restart_interval: None,
+            packed_entries: None,
+            packed: false,
+        }
     }
 
-    pub fn data_encoded_size(&self) -> u64 {
-        self.meta.data_encoded_size()
+    /// Compress the data part with the given algorithm when encoding.
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.meta = self.meta.with_compression(compression, 0);
+        self.header = Header::new(Type::Block, self.version());
+        self
     }
 
-    pub fn get(&self, key: &str) -> Option<&SeqMarked> {
-        self.data.get(key)
+    /// Encode keys in the prefix-keys layout, storing a full key every `restart_interval` entries.
+    ///
+    /// Encoding returns an error if `restart_interval` is 0.
+    pub fn with_restart_interval(mut self, restart_interval: usize) -> Self {
+        self.restart_interval = Some(restart_interval);
+        self.header = Header::new(Type::Block, self.version());
+        self
     }
 
-    pub fn range<Q, R>(&self, range: R) -> BlockIter
-    where
-        R: RangeBounds<Q>,
-        String: Borrow<Q>,
-        Q: Ord + ?Sized,
-    {
-        BlockIter {
-            inner: self.data.range(range),
+    /// Encode keys and values in the packed layout, which is searched without decoding.
+    ///
+    /// It takes precedence over [`Block::with_restart_interval()`].
+    pub fn with_packed(mut self) -> Self {
+        self.packed = true;
+        self.header = Header::new(Type::Block, self.version());
+        self
+    }
+
+    /// The oldest block format version that can encode this block.
+    fn version(&self) -> Version {
+        if self.packed {
+            Version::V004
+        } else if self.restart_interval.is_some() {
+            Version::V003
+        } else {
+            self.meta.version()
         }
     }
+
+    pub fn data_encoded_size(&self) -> u64 {
+        self.meta.data_encoded_size()
+    }
+
+    /// The size of the data part before compression.
+    pub fn data_raw_size(&self) -> u64 {
+        self.meta.raw_size()
+    }
 }
 
-impl Encode for Block {
-    fn encode<W: Write>(&self, mut w: W) -> Result<usize, Error> {
+impl<V: bincode::Encode> Block<V> {
+    /// Encode a block created by [`Block::new()`], whose data is in memory.
+    ///
+    /// Unlike [`Block::encode_with_meta()`], `V` only has to be encodable.
+    pub(crate) fn encode_new_with_meta<W: Write>(
+        &self,
+        w: W,
+    ) -> Result<(usize, BlockEncodingMeta), Error> {
+        let entries = self.data.get().ok_or_else(|| {
+            Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "block data is not in memory",
+            )
+        })?;
+        self.encode_entries_with_meta(entries, w)
+    }
+
+    /// Encode this block with `entries` as its data.
+    fn encode_entries_with_meta<W: Write>(
+        &self,
+        entries: &BTreeMap<Vec<u8>, V>,
+        mut w: W,
+    ) -> Result<(usize, BlockEncodingMeta), Error> {
         let mut n = 0usize;
-        let encoded_data = bincode::encode_to_vec(&self.data, bincode_config())
-            .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+        let raw_data = if self.packed {
+            packed_entries::encode(entries)?
+        } else {
+            match self.restart_interval {
+                None => bincode::encode_to_vec(entries, bincode_config())
+                    .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?,
+                Some(restart_interval) => prefix_keys::encode(entries, restart_interval)?,
+            }
+        };
+
+        let raw_size = raw_data.len() as u64;
+
+        let compression = self.meta.compression();
+        let encoded_data = match compression {
+            Compression::None => raw_data,
+            _ => compression.compress(&raw_data)?,
+        };
         let encoded_size = encoded_data.len() as u64;
 
         let mut cw = Checksum::new_writer(&mut w);
 
-        n += self.header.encode(&mut cw)?;
-
         // Decide the size of the encoded data part.
-        let meta = BlockEncodingMeta::new(self.meta.block_num(), encoded_size);
-        n += meta.encode(&mut cw)?;
+        let meta = BlockEncodingMeta::new(self.meta.block_num(), encoded_size)
+            .with_compression(compression, raw_size);
+
+        let version = self.version();
+        n += Header::new(Type::Block, version).encode(&mut cw)?;
+        n += meta.encode_version(version, &mut cw)?;
 
         cw.write_all(&encoded_data)?;
         n += encoded_size as usize;
         n += cw.write_checksum()?;
 
+        Ok((n, meta))
+    }
+}
+
+impl<V: Value> Block<V> {
+    /// Encode this block and return the encoded size and the meta that is written.
+    ///
+    /// The returned meta contains the actual encoded sizes of the data part.
+    pub fn encode_with_meta<W: Write>(&self, w: W) -> Result<(usize, BlockEncodingMeta), Error> {
+        self.encode_entries_with_meta(self.entries()?, w)
+    }
+
+    /// Return all the key-values, decoding them if they are not yet.
+    fn entries(&self) -> Result<&BTreeMap<Vec<u8>, V>, Error> {
+        if let Some(data) = self.data.get() {
+            return Ok(data);
+        }
+
+        // `data` is only uninitialized for a decoded V003 or V004 block.
+        let data = match &self.packed_entries {
+            Some(packed_entries) => packed_entries.decode_all()?,
+            None => self.prefix_keys.as_ref().unwrap().decode_all()?,
+        };
+        Ok(self.data.get_or_init(|| data))
+    }
+
+    /// Return the value of `key`.
+    ///
+    /// For a V003 block whose entries are not yet decoded,
+    /// it binary searches the restart points and decodes at most one restart interval.
+    /// For a V004 block, it binary searches the keys in place and decodes only the value found.
+    pub fn get(&self, key: &[u8]) -> Result<Option<V>, Error> {
+        if let Some(packed_entries) = &self.packed_entries {
+            return packed_entries.get(key);
+        }
+
+        if let Some(data) = self.data.get() {
+            return Ok(data.get(key).cloned());
+        }
+
+        self.prefix_keys.as_ref().unwrap().get(key)
+    }
+
+    /// Iterate the key-values in the range without decoding all of them.
+    ///
+    /// For a V004 block, keys are slices of the block data, and only the values iterated are
+    /// decoded. Other blocks are decoded on the first access, the same as [`Block::range()`].
+    pub fn scan<Q, R>(&self, range: R) -> Result<BlockScan<V>, Error>
+    where
+        R: RangeBounds<Q>,
+        Q: AsRef<[u8]> + ?Sized,
+    {
+        let start = range.start_bound().map(|k| k.as_ref());
+        let end = range.end_bound().map(|k| k.as_ref());
+
+        let inner = match &self.packed_entries {
+            Some(entries) => BlockScanInner::Packed {
+                entries,
+                indexes: entries.range_indexes(start, end),
+            },
+            None => BlockScanInner::Map(
+                self.entries()?.range::<[u8], (Bound<&[u8]>, Bound<&[u8]>)>((start, end)),
+            ),
+        };
+
+        Ok(BlockScan { inner })
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Result<BlockIter<V>, Error>
+    where
+        R: RangeBounds<Q>,
+        Vec<u8>: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        Ok(BlockIter {
+            inner: self.entries()?.range(range),
+        })
+    }
+}
+
+impl<V: Value> Encode for Block<V> {
+    fn encode<W: Write>(&self, w: W) -> Result<usize, Error> {
+        let (n, _meta) = self.encode_with_meta(w)?;
         Ok(n)
     }
 }
 
-impl Decode for Block {
+impl<V: Value> Decode for Block<V> {
     fn decode<R: Read>(r: R) -> Result<Self, Error> {
         let mut cr = Checksum::new_reader(r);
 
         let header = Header::decode(&mut cr)?;
-        assert_eq!(header, Header::new(Type::Block, Version::V001));
+        header.check(Type::Block, &[
+            Version::V001,
+            Version::V002,
+            Version::V003,
+            Version::V004,
+        ])?;
 
-        let meta = BlockEncodingMeta::decode(&mut cr)?;
+        let meta = BlockEncodingMeta::decode_version(header.version(), &mut cr)?;
 
         let data_size = meta.data_encoded_size() as usize;
 
@@ -108,10 +344,47 @@ impl Decode for Block {
         cr.read_exact(&mut buf)?;
         cr.verify_checksum(|| "Block::decode()")?;
 
-        let (data, _size) = bincode::decode_from_slice(&buf, bincode_config())
-            .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let compression = meta.compression();
+        if compression != Compression::None {
+            buf = compression.decompress(&buf, meta.raw_size() as usize)?;
+        }
 
-        let block = Self { header, meta, data };
+        let block = if header.version() == Version::V004 {
+            Self {
+                header,
+                meta,
+                data: OnceLock::new(),
+                prefix_keys: None,
+                restart_interval: None,
+                packed_entries: Some(PackedEntries::new(buf.into())?),
+                packed: true,
+            }
+        } else if header.version() == Version::V003 {
+            let prefix_keys = PrefixKeys::new(buf)?;
+            Self {
+                header,
+                meta,
+                data: OnceLock::new(),
+                restart_interval: Some(prefix_keys.restart_interval()),
+                prefix_keys: Some(prefix_keys),
+                packed_entries: None,
+                packed: false,
+            }
+        } else {
+            let (data, _size): (BTreeMap<_, _>, _) =
+                bincode::decode_from_slice(&buf, bincode_config())
+                    .map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+
+            Self {
+                header,
+                meta,
+                data: OnceLock::from(data),
+                prefix_keys: None,
+                restart_interval: None,
+                packed_entries: None,
+                packed: false,
+            }
+        };
 
         Ok(block)
     }
@@ -121,21 +394,24 @@ impl Decode for Block {
 #[allow(clippy::redundant_clone)]
 mod tests {
     use codeq::testing::test_codec;
+    use codeq::Decode;
     use codeq::Encode;
     use pretty_assertions::assert_eq;
 
     use crate::v001::bincode_config::bincode_config;
     use crate::v001::block::Block;
     use crate::v001::testing::bb;
-    use crate::v001::testing::ss;
+    use crate::v001::testing::bb_vec;
     use crate::v001::testing::vec_chain;
+    use crate::v001::Compression;
     use crate::v001::SeqMarked;
+    use crate::version::Version;
 
     #[test]
     fn test_block_codec() -> anyhow::Result<()> {
         let block_data = maplit::btreemap! {
-            ss("a") => SeqMarked::new_normal(1, bb("A")),
-            ss("b") => SeqMarked::new_tombstone(2),
+            bb("a") => SeqMarked::new_normal(1, bb("A")),
+            bb("b") => SeqMarked::new_tombstone(2),
         };
         let mut block = Block::new(5, block_data.clone());
 
@@ -180,30 +456,201 @@ mod tests {
 
         // Block does not know about the encoded size when it is created.
         block.meta.data_encoded_size = encoded_data.len() as u64;
+        block.meta.raw_size = encoded_data.len() as u64;
 
         test_codec(&b[..], &block)?;
 
         Ok(())
     }
 
+    #[test]
+    fn test_block_codec_compressed() -> anyhow::Result<()> {
+        let block_data = (0..10)
+            .map(|i| {
+                let v = format!(r#"{{"id":{},"name":"foo"}}"#, i);
+                (
+                    bb(format!("k{}", i)),
+                    SeqMarked::new_normal(i, v.into_bytes()),
+                )
+            })
+            .collect::<std::collections::BTreeMap<_, _>>();
+
+        let raw_size = bincode::encode_to_vec(&block_data, bincode_config())?.len() as u64;
+
+        for compression in [Compression::Lz4, Compression::Zstd, Compression::Snappy] {
+            let mut block = Block::new(5, block_data.clone()).with_compression(compression);
+            assert_eq!(block.header.version(), Version::V002);
+
+            let mut b = Vec::new();
+            let (n, meta) = block.encode_with_meta(&mut b)?;
+            assert_eq!(n, b.len());
+
+            assert_eq!(meta.compression(), compression);
+            assert_eq!(meta.raw_size(), raw_size);
+            assert!(meta.data_encoded_size() < raw_size, "{}", compression);
+
+            block.meta = meta;
+            test_codec(&b[..], &block)?;
+
+            assert_eq!(block.data_raw_size(), raw_size);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_codec_prefix_keys() -> anyhow::Result<()> {
+        let block_data = (0..10)
+            .map(|i| {
+                let k = format!("tenant/123/objects/{}", i);
+                (bb(k), SeqMarked::new_normal(i, bb(i)))
+            })
+            .collect::<std::collections::BTreeMap<_, _>>();
+
+        for compression in [Compression::None, Compression::Lz4] {
+            let mut block = Block::new(5, block_data.clone())
+                .with_compression(compression)
+                .with_restart_interval(4);
+            assert_eq!(block.header.version(), Version::V003);
+
+            let mut b = Vec::new();
+            let (n, meta) = block.encode_with_meta(&mut b)?;
+            assert_eq!(n, b.len());
+
+            block.meta = meta;
+            test_codec(&b[..], &block)?;
+
+            let decoded = Block::decode(&b[..])?;
+
+            assert_eq!(
+                Some(SeqMarked::new_normal(7, bb(7))),
+                decoded.get(b"tenant/123/objects/7")?
+            );
+            assert_eq!(None, decoded.get(b"tenant/123/objects/70")?);
+            assert!(
+                decoded.data.get().is_none(),
+                "point lookup does not decode all entries"
+            );
+
+            let got = decoded
+                .range(bb("tenant/123/objects/7")..)?
+                .map(|(k, _)| k.clone())
+                .collect::<Vec<_>>();
+            assert_eq!(
+                got,
+                bb_vec([
+                    "tenant/123/objects/7",
+                    "tenant/123/objects/8",
+                    "tenant/123/objects/9"
+                ])
+            );
+
+            assert!(decoded.data.get().is_some());
+        }
+
+        // Keys sharing long prefixes are smaller than the V001 layout.
+        let mut full = Vec::new();
+        Block::new(5, block_data.clone()).encode(&mut full)?;
+
+        let mut prefixed = Vec::new();
+        Block::new(5, block_data.clone()).with_restart_interval(4).encode(&mut prefixed)?;
+
+        assert!(prefixed.len() < full.len());
+
+        // A restart interval of 0 is an error, not a panic.
+        let res =
+            Block::new(5, block_data.clone()).with_restart_interval(0).encode(&mut Vec::new());
+        assert_eq!(std::io::ErrorKind::InvalidInput, res.unwrap_err().kind());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_codec_packed() -> anyhow::Result<()> {
+        let block_data = (0..10)
+            .map(|i| (bb(format!("k{}", i)), SeqMarked::new_normal(i, bb(i))))
+            .collect::<std::collections::BTreeMap<_, _>>();
+
+        for compression in [Compression::None, Compression::Zstd] {
+            let mut block =
+                Block::new(5, block_data.clone()).with_compression(compression).with_packed();
+            assert_eq!(block.header.version(), Version::V004);
+
+            let mut b = Vec::new();
+            let (n, meta) = block.encode_with_meta(&mut b)?;
+            assert_eq!(n, b.len());
+
+            block.meta = meta;
+            test_codec(&b[..], &block)?;
+
+            let decoded = Block::<SeqMarked>::decode(&b[..])?;
+
+            assert_eq!(Some(SeqMarked::new_normal(7, bb(7))), decoded.get(b"k7")?);
+            assert_eq!(None, decoded.get(b"k70")?);
+
+            let got = decoded
+                .scan(bb("k3")..bb("k6"))?
+                .map(|res| res.map(|(k, v)| (k.to_vec(), v)))
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(got, vec![
+                (bb("k3"), SeqMarked::new_normal(3, bb(3))),
+                (bb("k4"), SeqMarked::new_normal(4, bb(4))),
+                (bb("k5"), SeqMarked::new_normal(5, bb(5))),
+            ]);
+
+            assert!(
+                decoded.data.get().is_none(),
+                "get and scan do not decode all entries"
+            );
+
+            let got = decoded.range(bb("k8")..)?.map(|(k, _)| k.clone()).collect::<Vec<_>>();
+            assert_eq!(got, bb_vec(["k8", "k9"]));
+
+            assert!(decoded.data.get().is_some());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_scan() -> anyhow::Result<()> {
+        let block_data = maplit::btreemap! {
+            bb("a") => SeqMarked::new_tombstone(1),
+            bb("b") => SeqMarked::new_normal(2, bb("B")),
+            bb("c") => SeqMarked::new_normal(3, bb("C")),
+        };
+        let block = Block::new(5, block_data.clone());
+
+        let got = block
+            .scan(bb("a")..bb("c"))?
+            .map(|res| res.map(|(k, v)| (k.to_vec(), v)))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(got, vec![
+            (bb("a"), SeqMarked::new_tombstone(1)),
+            (bb("b"), SeqMarked::new_normal(2, bb("B"))),
+        ]);
+
+        Ok(())
+    }
+
     #[test]
     fn test_block_get_range() -> anyhow::Result<()> {
         let block_data = maplit::btreemap! {
-            ss("a") => SeqMarked::new_tombstone(1),
-            ss("b") => SeqMarked::new_normal(2, bb("B")),
-            ss("c") => SeqMarked::new_normal(3, bb("C")),
-            ss("d") => SeqMarked::new_normal(4, bb("D")),
+            bb("a") => SeqMarked::new_tombstone(1),
+            bb("b") => SeqMarked::new_normal(2, bb("B")),
+            bb("c") => SeqMarked::new_normal(3, bb("C")),
+            bb("d") => SeqMarked::new_normal(4, bb("D")),
         };
         let block = Block::new(5, block_data.clone());
 
-        assert_eq!(None, block.get("z"));
-        assert_eq!(Some(&SeqMarked::new_tombstone(1)), block.get("a"));
+        assert_eq!(None, block.get(b"z")?);
+        assert_eq!(Some(SeqMarked::new_tombstone(1)), block.get(b"a")?);
 
-        let got = block.range(ss("b")..ss("e")).collect::<Vec<_>>();
+        let got = block.range(bb("b")..bb("e"))?.collect::<Vec<_>>();
         assert_eq!(got, vec![
-            (&ss("b"), &SeqMarked::new_normal(2, bb("B"))),
-            (&ss("c"), &SeqMarked::new_normal(3, bb("C"))),
-            (&ss("d"), &SeqMarked::new_normal(4, bb("D"))),
+            (&bb("b"), &SeqMarked::new_normal(2, bb("B"))),
+            (&bb("c"), &SeqMarked::new_normal(3, bb("C"))),
+            (&bb("d"), &SeqMarked::new_normal(4, bb("D"))),
         ]);
 
         Ok(())
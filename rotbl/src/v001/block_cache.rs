@@ -7,16 +7,37 @@ use lru_cache_map::LruCache;
 
 use crate::v001::block::Block;
 use crate::v001::block_id::BlockId;
+use crate::v001::value::Value;
+use crate::v001::BlockIndex;
+use crate::v001::SeqMarked;
 
+/// A data block or an index partition in the block cache.
+#[derive(Debug)]
+#[derive(Clone)]
+pub enum CachedBlock<V = SeqMarked>
+where V: Value
+{
+    Data(Arc<Block<V>>),
+    IndexPartition(Arc<BlockIndex>),
+}
+
+/// Measures a cached item by the encoded size of its data part in the table file,
+/// the unit of [`BlockCacheConfig::capacity`](crate::v001::BlockCacheConfig::capacity).
 pub struct BlockMeter;
 
-impl<K> Meter<K, Arc<Block>> for BlockMeter {
+impl<K, V> Meter<K, CachedBlock<V>> for BlockMeter
+where V: Value
+{
     type Measure = usize;
 
-    fn measure<Q: ?Sized>(&self, _: &Q, v: &Arc<Block>) -> usize
+    fn measure<Q: ?Sized>(&self, _: &Q, v: &CachedBlock<V>) -> usize
     where K: Borrow<Q> {
-        v.data_encoded_size() as usize
+        match v {
+            CachedBlock::Data(block) => block.data_encoded_size() as usize,
+            CachedBlock::IndexPartition(index) => index.data_encoded_size as usize,
+        }
     }
 }
 
-pub type BlockCache = LruCache<BlockId, Arc<Block>, DefaultHashBuilder, BlockMeter>;
+pub type BlockCache<V = SeqMarked> =
+    LruCache<BlockId, CachedBlock<V>, DefaultHashBuilder, BlockMeter>;
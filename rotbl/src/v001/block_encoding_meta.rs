@@ -8,8 +8,14 @@ use byteorder::WriteBytesExt;
 use codeq::config::CodeqConfig;
 
 use crate::v001::types::Checksum;
+use crate::v001::Compression;
+use crate::version::Version;
 
 /// The metadata of an encoded block
+///
+/// An uncompressed block is encoded in the [`Version::V001`] layout.
+/// A compressed block, or a block in [`Version::V003`] or later, is encoded in the layout
+/// that additionally stores the compression algorithm and the size of the raw data.
 #[derive(Debug)]
 #[derive(Clone)]
 #[derive(PartialEq, Eq)]
@@ -18,7 +24,15 @@ pub struct BlockEncodingMeta {
     block_num: u32,
 
     /// The size of the encoded `data` part of a block.
+    ///
+    /// For a compressed block, it is the size after compression.
     pub(crate) data_encoded_size: u64,
+
+    /// The algorithm used to compress the `data` part of a block.
+    pub(crate) compression: Compression,
+
+    /// The size of the `data` part of a block before compression.
+    pub(crate) raw_size: u64,
 }
 
 impl BlockEncodingMeta {
@@ -26,9 +40,17 @@ impl BlockEncodingMeta {
         Self {
             block_num,
             data_encoded_size,
+            compression: Compression::None,
+            raw_size: data_encoded_size,
         }
     }
 
+    pub fn with_compression(mut self, compression: Compression, raw_size: u64) -> Self {
+        self.compression = compression;
+        self.raw_size = raw_size;
+        self
+    }
+
     pub fn block_num(&self) -> u32 {
         self.block_num
     }
@@ -36,10 +58,53 @@ impl BlockEncodingMeta {
     pub fn data_encoded_size(&self) -> u64 {
         self.data_encoded_size
     }
-}
 
-impl codeq::Encode for BlockEncodingMeta {
-    fn encode<W: Write>(&self, mut w: W) -> Result<usize, Error> {
+    pub fn compression(&self) -> Compression {
+        self.compression
+    }
+
+    pub fn raw_size(&self) -> u64 {
+        self.raw_size
+    }
+
+    /// The oldest block format version that can store this meta.
+    pub fn version(&self) -> Version {
+        if self.compression == Compression::None {
+            Version::V001
+        } else {
+            Version::V002
+        }
+    }
+
+    /// Decode a meta in the layout of the given block format version.
+    pub fn decode_version<R: Read>(version: Version, r: R) -> Result<Self, Error> {
+        let mut cr = Checksum::new_reader(r);
+
+        let block_num = cr.read_u64::<BigEndian>()? as u32;
+        let data_encoded_size = cr.read_u64::<BigEndian>()?;
+
+        let mut meta = Self::new(block_num, data_encoded_size);
+
+        if version != Version::V001 {
+            let compression = Compression::from_u64(cr.read_u64::<BigEndian>()?)?;
+            let raw_size = cr.read_u64::<BigEndian>()?;
+            meta = meta.with_compression(compression, raw_size);
+        }
+
+        cr.verify_checksum(|| "BLockEncodingMeta::decode()")?;
+
+        Ok(meta)
+    }
+
+    /// Encode this meta in the layout of the given block format version.
+    pub fn encode_version<W: Write>(&self, version: Version, mut w: W) -> Result<usize, Error> {
+        if version == Version::V001 && self.compression != Compression::None {
+            return Err(Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("block {} can not store compression", version),
+            ));
+        }
+
         let mut n = 0;
         let mut cw = Checksum::new_writer(&mut w);
 
@@ -47,31 +112,42 @@ impl codeq::Encode for BlockEncodingMeta {
         n += 8;
         cw.write_u64::<BigEndian>(self.data_encoded_size)?;
         n += 8;
+
+        if version != Version::V001 {
+            cw.write_u64::<BigEndian>(self.compression.as_u64())?;
+            n += 8;
+            cw.write_u64::<BigEndian>(self.raw_size)?;
+            n += 8;
+        }
+
         n += cw.write_checksum()?;
 
         Ok(n)
     }
 }
 
+impl codeq::Encode for BlockEncodingMeta {
+    /// Encode this meta in the oldest layout that can store it.
+    fn encode<W: Write>(&self, w: W) -> Result<usize, Error> {
+        self.encode_version(self.version(), w)
+    }
+}
+
 impl codeq::Decode for BlockEncodingMeta {
+    /// Decode a meta in the [`Version::V001`] layout.
     fn decode<R: Read>(r: R) -> Result<Self, Error> {
-        let mut cr = Checksum::new_reader(r);
-
-        let block_num = cr.read_u64::<BigEndian>()? as u32;
-        let data_encoded_size = cr.read_u64::<BigEndian>()?;
-        cr.verify_checksum(|| "BLockEncodingMeta::decode()")?;
-
-        let meta = Self::new(block_num, data_encoded_size);
-
-        Ok(meta)
+        Self::decode_version(Version::V001, r)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use codeq::testing::test_codec;
+    use codeq::Encode;
 
     use crate::v001::block_encoding_meta::BlockEncodingMeta;
+    use crate::v001::Compression;
+    use crate::version::Version;
 
     #[test]
     fn test_block_meta_codec() -> anyhow::Result<()> {
@@ -87,4 +163,28 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_block_meta_codec_compressed() -> anyhow::Result<()> {
+        let meta = BlockEncodingMeta::new(1, 2).with_compression(Compression::Zstd, 3);
+        assert_eq!(Version::V002, meta.version());
+
+        let encoded = vec![
+            0, 0, 0, 0, 0, 0, 0, 1, // block_num
+            0, 0, 0, 0, 0, 0, 0, 2, // data_encoded_size
+            0, 0, 0, 0, 0, 0, 0, 2, // compression
+            0, 0, 0, 0, 0, 0, 0, 3, // raw_size
+            0, 0, 0, 0, 223, 66, 44, 88, // checksum
+        ];
+
+        let mut b = Vec::new();
+        let n = meta.encode(&mut b)?;
+        assert_eq!(n, b.len());
+        assert_eq!(encoded, b);
+
+        let decoded = BlockEncodingMeta::decode_version(Version::V002, encoded.as_slice())?;
+        assert_eq!(meta, decoded);
+
+        Ok(())
+    }
 }
@@ -6,6 +6,9 @@
 pub struct BlockId {
     table_id: u32,
     block_num: u32,
+
+    /// Whether it identifies an index partition, whose number is `block_num`.
+    index_partition: bool,
 }
 
 impl BlockId {
@@ -13,6 +16,16 @@ impl BlockId {
         Self {
             table_id,
             block_num,
+            index_partition: false,
+        }
+    }
+
+    /// Identify an index partition of a table, which is cached along with the data blocks.
+    pub fn new_index_partition(table_id: u32, partition_num: u32) -> Self {
+        Self {
+            table_id,
+            block_num: partition_num,
+            index_partition: true,
         }
     }
 
@@ -23,4 +36,8 @@ impl BlockId {
     pub fn block_num(&self) -> u32 {
         self.block_num
     }
+
+    pub fn is_index_partition(&self) -> bool {
+        self.index_partition
+    }
 }
@@ -4,14 +4,20 @@ use std::io;
 use std::io::Read;
 use std::io::Write;
 use std::ops::Bound;
+use std::ops::Range;
 use std::ops::RangeBounds;
 
+use byteorder::BigEndian;
+use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
 use codeq::config::CodeqConfig;
 use codeq::Span;
 
 use crate::buf::new_uninitialized;
 use crate::typ::Type;
 use crate::v001::header::Header;
+use crate::v001::key;
+use crate::v001::key::KeyDisplay;
 use crate::v001::types::Checksum;
 use crate::v001::types::Segment;
 use crate::v001::types::WithChecksum;
@@ -29,8 +35,10 @@ pub struct BlockIndexEntry {
     pub(crate) offset: u64,
     pub(crate) size: u64,
 
-    pub(crate) first_key: String,
-    pub(crate) last_key: String,
+    #[serde(with = "key::utf8_serde")]
+    pub(crate) first_key: Vec<u8>,
+    #[serde(with = "key::utf8_serde")]
+    pub(crate) last_key: Vec<u8>,
 }
 
 impl BlockIndexEntry {
@@ -42,7 +50,7 @@ impl BlockIndexEntry {
     /// * `segment` - The offset and size of the block in the rotbl.
     /// * `first_key` - The first key in the block.
     /// * `last_key` - The last key in the block.
-    pub fn new(block_num: u32, segment: Segment, first_key: String, last_key: String) -> Self {
+    pub fn new(block_num: u32, segment: Segment, first_key: Vec<u8>, last_key: Vec<u8>) -> Self {
         let offset = *segment.offset();
         let size = *segment.size();
         Self {
@@ -53,6 +61,23 @@ impl BlockIndexEntry {
             last_key,
         }
     }
+
+    pub fn block_num(&self) -> u32 {
+        self.block_num
+    }
+
+    /// The offset and size of the block in the rotbl.
+    pub fn segment(&self) -> Segment {
+        Segment::new(self.offset, self.size)
+    }
+
+    pub fn first_key(&self) -> &[u8] {
+        &self.first_key
+    }
+
+    pub fn last_key(&self) -> &[u8] {
+        &self.last_key
+    }
 }
 
 impl fmt::Display for BlockIndexEntry {
@@ -60,7 +85,11 @@ impl fmt::Display for BlockIndexEntry {
         write!(
             f,
             "{{ block_num: {:>04}, position: {}+{}, key_range: [\"{}\", \"{}\"] }}",
-            self.block_num, self.offset, self.size, self.first_key, self.last_key
+            self.block_num,
+            self.offset,
+            self.size,
+            KeyDisplay(&self.first_key),
+            KeyDisplay(&self.last_key)
         )
     }
 }
@@ -74,6 +103,19 @@ impl fmt::Display for BlockIndexEntry {
 /// | Data
 /// | Checksum
 /// ```
+///
+/// In [`Version::V001`], `Data` is the JSON of all entries, which only stores UTF-8 keys.
+/// In [`Version::V002`], `Data` is binary and big-endian:
+/// ```text
+/// | n: u64
+/// | block_num: u32 | offset: u64 | size: u64 | len: u32 | first_key | len: u32 | last_key
+/// | ... n entries
+/// ```
+///
+/// In [`Version::V003`], the index is partitioned: `Data` is the same as V002,
+/// but every entry refers to an index partition instead of a block.
+/// An index partition is a V002 index of consecutive blocks,
+/// and `block_num` of its entry is the number of its first block.
 #[derive(Debug)]
 #[derive(Clone)]
 #[derive(PartialEq, Eq)]
@@ -95,6 +137,25 @@ impl BlockIndex {
         }
     }
 
+    /// Encode the entries in the binary [`Version::V002`] layout instead of JSON.
+    pub fn with_binary_encoding(mut self) -> Self {
+        self.header = Header::new(Type::BlockIndex, Version::V002);
+        self
+    }
+
+    /// Mark this index as the top-level index whose entries refer to index partitions.
+    ///
+    /// It is encoded in the binary [`Version::V003`] layout.
+    pub fn with_partitions(mut self) -> Self {
+        self.header = Header::new(Type::BlockIndex, Version::V003);
+        self
+    }
+
+    /// Whether the entries of this index refer to index partitions instead of blocks.
+    pub fn is_partitioned(&self) -> bool {
+        self.header.version() == Version::V003
+    }
+
     pub fn with_encoded_size(mut self, size: u64) -> Self {
         self.data_encoded_size = size;
         self
@@ -106,34 +167,130 @@ impl BlockIndex {
 
     /// Returns block index entries that overlap with the given range.
     pub fn lookup_range<R>(&self, range: R) -> &[BlockIndexEntry]
-    where R: RangeBounds<String> {
+    where R: RangeBounds<Vec<u8>> {
+        &self.data[self.lookup_range_positions(range)]
+    }
+
+    /// Returns the positions of the entries that overlap with the given range.
+    pub(crate) fn lookup_range_positions<R>(&self, range: R) -> Range<usize>
+    where R: RangeBounds<Vec<u8>> {
         // Just a helper function to make the code below more readable.
-        fn contains(range: &(Bound<&String>, Bound<&String>), s: &String) -> bool {
-            <(Bound<&String>, Bound<&String>) as RangeBounds<String>>::contains(range, s)
+        fn contains(range: &(Bound<&Vec<u8>>, Bound<&Vec<u8>>), s: &Vec<u8>) -> bool {
+            <(Bound<&Vec<u8>>, Bound<&Vec<u8>>) as RangeBounds<Vec<u8>>>::contains(range, s)
         }
 
-        let left_to_inf = (range.start_bound(), Bound::<&String>::Unbounded);
+        let left_to_inf = (range.start_bound(), Bound::<&Vec<u8>>::Unbounded);
         let start = self.data.partition_point(|ent| !contains(&left_to_inf, &ent.last_key));
 
-        let inf_to_right = (Bound::<&String>::Unbounded, range.end_bound());
+        let inf_to_right = (Bound::<&Vec<u8>>::Unbounded, range.end_bound());
         let end = self.data.partition_point(|ent| contains(&inf_to_right, &ent.first_key));
 
-        &self.data[start..end]
+        start..end.max(start)
     }
 
     /// Return a block index entry that contains the given key.
-    pub fn lookup(&self, key: &str) -> Option<&BlockIndexEntry> {
-        let i = self.data.partition_point(|ent| key > ent.last_key.as_str());
+    pub fn lookup(&self, key: &[u8]) -> Option<&BlockIndexEntry> {
+        self.lookup_position(key).map(|i| &self.data[i])
+    }
+
+    /// Return the position of the entry that contains the given key.
+    pub(crate) fn lookup_position(&self, key: &[u8]) -> Option<usize> {
+        let i = self.data.partition_point(|ent| key > ent.last_key.as_slice());
         let ent = self.data.get(i)?;
-        if key >= ent.first_key.as_str() {
-            Some(ent)
+        if key >= ent.first_key.as_slice() {
+            Some(i)
         } else {
             None
         }
     }
 
+    /// Return the entry of the given block.
+    ///
+    /// Entries are numbered consecutively, starting from the `block_num` of the first one,
+    /// which is not 0 for an index partition.
     pub fn get_index_entry_by_num(&self, block_num: u32) -> Option<&BlockIndexEntry> {
-        self.data.get(block_num as usize)
+        let first = self.data.first()?.block_num;
+        let i = block_num.checked_sub(first)?;
+        self.data.get(i as usize)
+    }
+
+    /// Return the position of the partition entry that contains the given block,
+    /// for a partitioned index.
+    ///
+    /// The last partition is returned for a block after it.
+    pub(crate) fn partition_position_by_num(&self, block_num: u32) -> Option<usize> {
+        let i = self.data.partition_point(|ent| ent.block_num <= block_num);
+        i.checked_sub(1)
+    }
+
+    fn encode_entries_binary(entries: &[BlockIndexEntry]) -> Result<Vec<u8>, io::Error> {
+        let mut buf = Vec::new();
+
+        buf.write_u64::<BigEndian>(entries.len() as u64)?;
+
+        for ent in entries {
+            buf.write_u32::<BigEndian>(ent.block_num)?;
+            buf.write_u64::<BigEndian>(ent.offset)?;
+            buf.write_u64::<BigEndian>(ent.size)?;
+
+            for key in [&ent.first_key, &ent.last_key] {
+                buf.write_u32::<BigEndian>(key.len() as u32)?;
+                buf.extend_from_slice(key);
+            }
+        }
+
+        Ok(buf)
+    }
+
+    fn decode_entries_binary(mut buf: &[u8]) -> Result<Vec<BlockIndexEntry>, io::Error> {
+        fn read_key(buf: &mut &[u8]) -> Result<Vec<u8>, io::Error> {
+            let len = buf.read_u32::<BigEndian>()? as usize;
+            if len > buf.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!(
+                        "key length {} exceeds the remaining {} bytes",
+                        len,
+                        buf.len()
+                    ),
+                ));
+            }
+
+            let (key, rest) = buf.split_at(len);
+            *buf = rest;
+
+            Ok(key.to_vec())
+        }
+
+        let n = buf.read_u64::<BigEndian>()?;
+
+        // Every entry takes at least 28 bytes; do not trust `n` to allocate.
+        let mut entries = Vec::with_capacity((n as usize).min(buf.len() / 28));
+
+        for _ in 0..n {
+            let block_num = buf.read_u32::<BigEndian>()?;
+            let offset = buf.read_u64::<BigEndian>()?;
+            let size = buf.read_u64::<BigEndian>()?;
+            let first_key = read_key(&mut buf)?;
+            let last_key = read_key(&mut buf)?;
+
+            entries.push(BlockIndexEntry {
+                block_num,
+                offset,
+                size,
+                first_key,
+                last_key,
+            });
+        }
+
+        if !buf.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} trailing bytes after {} index entries", buf.len(), n),
+            ));
+        }
+
+        Ok(entries)
     }
 }
 
@@ -141,7 +298,10 @@ impl codeq::Encode for BlockIndex {
     fn encode<W: Write>(&self, mut w: W) -> Result<usize, io::Error> {
         let mut n = 0usize;
 
-        let encoded_data = serde_json::to_vec(&self.data)?;
+        let encoded_data = match self.header.version() {
+            Version::V001 => serde_json::to_vec(&self.data)?,
+            _ => Self::encode_entries_binary(&self.data)?,
+        };
         let encoded_size = encoded_data.len() as u64;
 
         let mut cw = Checksum::new_writer(&mut w);
@@ -164,7 +324,11 @@ impl codeq::Decode for BlockIndex {
         let mut cr = Checksum::new_reader(r);
 
         let header = Header::decode(&mut cr)?;
-        assert_eq!(header, Header::new(Type::BlockIndex, Version::V001));
+        header.check(Type::BlockIndex, &[
+            Version::V001,
+            Version::V002,
+            Version::V003,
+        ])?;
 
         let encoded_size = WithChecksum::<u64>::decode(&mut cr)?.into_inner();
 
@@ -173,7 +337,10 @@ impl codeq::Decode for BlockIndex {
 
         cr.verify_checksum(|| "BlockIndex::decode()")?;
 
-        let data = serde_json::from_slice(&buf)?;
+        let data = match header.version() {
+            Version::V001 => serde_json::from_slice(&buf)?,
+            _ => Self::decode_entries_binary(&buf)?,
+        };
 
         let block = Self {
             header,
@@ -198,8 +365,8 @@ mod tests {
 
     use crate::v001::block_index::BlockIndex;
     use crate::v001::block_index::BlockIndexEntry;
+    use crate::v001::testing::bb;
     use crate::v001::testing::bbs;
-    use crate::v001::testing::ss;
     use crate::v001::testing::vec_chain;
 
     #[test]
@@ -220,7 +387,7 @@ mod tests {
         }
 
         fn lookup_range<R>(idx: &BlockIndex, r: R) -> Vec<u32>
-        where R: RangeBounds<String> + Debug {
+        where R: RangeBounds<Vec<u8>> + Debug {
             dbg!(&r);
             let r = idx.lookup_range(r);
             to_block_nums(r)
@@ -232,38 +399,58 @@ mod tests {
 
         assert_eq!(vec![0, 1], lookup_range(&block_index, ..));
 
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ss("`")..));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ss("a")..));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ss("a1")..));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ss("b")..));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ss("p")..));
-        assert_eq!(vec![1], lookup_range(&block_index, ss("p0")..));
-        assert_eq!(vec![1], lookup_range(&block_index, ss("p1")..));
-        assert_eq!(vec![1], lookup_range(&block_index, ss("p2")..));
-        assert_eq!(vec![1], lookup_range(&block_index, ss("y")..));
-        assert_eq!(vec![1], lookup_range(&block_index, ss("z")..));
-        assert_eq!(empty, lookup_range(&block_index, ss("z1")..));
-
-        assert_eq!(empty, lookup_range(&block_index, ..ss("a")));
-        assert_eq!(vec![0], lookup_range(&block_index, ..=ss("a")));
-        assert_eq!(vec![0], lookup_range(&block_index, ..ss("b")));
-        assert_eq!(vec![0], lookup_range(&block_index, ..ss("b1")));
-        assert_eq!(vec![0], lookup_range(&block_index, ..ss("p")));
-        assert_eq!(vec![0], lookup_range(&block_index, ..ss("p0")));
-        assert_eq!(vec![0], lookup_range(&block_index, ..ss("p1")));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ..=ss("p1")));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ..ss("p2")));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ..ss("y")));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ..ss("z")));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ..ss("z1")));
-
-        assert_eq!(empty, lookup_range(&block_index, ss("a")..ss("a")));
-        assert_eq!(vec![0], lookup_range(&block_index, ss("p")..ss("p")));
-        assert_eq!(empty, lookup_range(&block_index, ss("p1")..ss("p1")));
-        assert_eq!(vec![0], lookup_range(&block_index, ss("p")..=ss("p")));
-        assert_eq!(vec![0], lookup_range(&block_index, ss("p")..ss("p1")));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ss("p")..ss("q")));
-        assert_eq!(vec![0, 1], lookup_range(&block_index, ss("p")..ss("z2")));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, bb("`")..));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, bb("a")..));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, bb("a1")..));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, bb("b")..));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, bb("p")..));
+        assert_eq!(vec![1], lookup_range(&block_index, bb("p0")..));
+        assert_eq!(vec![1], lookup_range(&block_index, bb("p1")..));
+        assert_eq!(vec![1], lookup_range(&block_index, bb("p2")..));
+        assert_eq!(vec![1], lookup_range(&block_index, bb("y")..));
+        assert_eq!(vec![1], lookup_range(&block_index, bb("z")..));
+        assert_eq!(empty, lookup_range(&block_index, bb("z1")..));
+
+        assert_eq!(empty, lookup_range(&block_index, ..bb("a")));
+        assert_eq!(vec![0], lookup_range(&block_index, ..=bb("a")));
+        assert_eq!(vec![0], lookup_range(&block_index, ..bb("b")));
+        assert_eq!(vec![0], lookup_range(&block_index, ..bb("b1")));
+        assert_eq!(vec![0], lookup_range(&block_index, ..bb("p")));
+        assert_eq!(vec![0], lookup_range(&block_index, ..bb("p0")));
+        assert_eq!(vec![0], lookup_range(&block_index, ..bb("p1")));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, ..=bb("p1")));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, ..bb("p2")));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, ..bb("y")));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, ..bb("z")));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, ..bb("z1")));
+
+        assert_eq!(empty, lookup_range(&block_index, bb("a")..bb("a")));
+        assert_eq!(vec![0], lookup_range(&block_index, bb("p")..bb("p")));
+        assert_eq!(empty, lookup_range(&block_index, bb("p1")..bb("p1")));
+        assert_eq!(vec![0], lookup_range(&block_index, bb("p")..=bb("p")));
+        assert_eq!(vec![0], lookup_range(&block_index, bb("p")..bb("p1")));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, bb("p")..bb("q")));
+        assert_eq!(vec![0, 1], lookup_range(&block_index, bb("p")..bb("z2")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_index_get_block_by_num_in_partition() -> anyhow::Result<()> {
+        let mut entries = create_testing_block_index().data;
+        entries[0].block_num = 5;
+        entries[1].block_num = 6;
+        let block_index = BlockIndex::new(entries);
+
+        assert_eq!(block_index.get_index_entry_by_num(4), None);
+        assert_eq!(block_index.get_index_entry_by_num(5).unwrap().block_num, 5);
+        assert_eq!(block_index.get_index_entry_by_num(6).unwrap().block_num, 6);
+        assert_eq!(block_index.get_index_entry_by_num(7), None);
+
+        assert_eq!(block_index.partition_position_by_num(4), None);
+        assert_eq!(block_index.partition_position_by_num(5), Some(0));
+        assert_eq!(block_index.partition_position_by_num(6), Some(1));
+        assert_eq!(block_index.partition_position_by_num(100), Some(1));
 
         Ok(())
     }
@@ -272,7 +459,7 @@ mod tests {
     fn test_block_index_lookup() -> anyhow::Result<()> {
         fn lookup(idx: &BlockIndex, key: &str) -> Option<u32> {
             dbg!(&key);
-            let r = idx.lookup(key);
+            let r = idx.lookup(key.as_bytes());
             r.map(|x| x.block_num)
         }
 
@@ -298,16 +485,16 @@ mod tests {
             block_num: 0,
             offset: 2,
             size: 3,
-            first_key: ss("a"),
-            last_key: ss("p"),
+            first_key: bb("a"),
+            last_key: bb("p"),
         };
 
         let ent2 = BlockIndexEntry {
             block_num: 1,
             offset: 5,
             size: 6,
-            first_key: ss("p1"),
-            last_key: ss("z"),
+            first_key: bb("p1"),
+            last_key: bb("z"),
         };
 
         let index_data = vec![ent1.clone(), ent2.clone()];
@@ -352,22 +539,122 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_block_index_codec_binary() -> anyhow::Result<()> {
+        let index_data = create_testing_block_index().data;
+        let mut block_index = BlockIndex::new(index_data.clone()).with_binary_encoding();
+
+        let mut b = Vec::new();
+        let n = block_index.encode(&mut b)?;
+        assert_eq!(n, b.len());
+
+        let encoded = vec_chain([
+            vec![
+                98, 108, 107, 95, 105, 100, 120, 0, // header.type
+                0, 0, 0, 0, 0, 0, 0, 2, // header.version
+                0, 0, 0, 0, 230, 232, 78, 85, // header checksum
+                0, 0, 0, 0, 0, 0, 0, 69, // data_encoded_size
+                0, 0, 0, 0, 99, 148, 106, 118, // data_encoded_size checksum
+            ],
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 2, // number of entries
+                //
+                0, 0, 0, 0, // block_num
+                0, 0, 0, 0, 0, 0, 0, 2, // offset
+                0, 0, 0, 0, 0, 0, 0, 3, // size
+                0, 0, 0, 1, 97, // first_key: "a"
+                0, 0, 0, 1, 112, // last_key: "p"
+                //
+                0, 0, 0, 1, // block_num
+                0, 0, 0, 0, 0, 0, 0, 5, // offset
+                0, 0, 0, 0, 0, 0, 0, 6, // size
+                0, 0, 0, 2, 112, 49, // first_key: "p1"
+                0, 0, 0, 1, 122, // last_key: "z"
+            ],
+            vec![
+                0, 0, 0, 0, 18, 149, 105, 12, // block_index checksum
+            ],
+        ]);
+
+        assert_eq!(encoded, b);
+
+        // Block does not know about the encoded size when it is created.
+        block_index.data_encoded_size = 69;
+
+        test_codec(&b[..], &block_index)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_index_codec_partitioned() -> anyhow::Result<()> {
+        let index_data = create_testing_block_index().data;
+        let mut block_index = BlockIndex::new(index_data.clone()).with_partitions();
+        assert!(block_index.is_partitioned());
+
+        let mut b = Vec::new();
+        let n = block_index.encode(&mut b)?;
+        assert_eq!(n, b.len());
+
+        // The same as the binary index except the version.
+        let mut binary = Vec::new();
+        BlockIndex::new(index_data.clone()).with_binary_encoding().encode(&mut binary)?;
+        assert_eq!(b.len(), binary.len());
+        assert_eq!(b[0..8], binary[0..8]);
+        assert_eq!(b[15], 3);
+        assert_eq!(b[24..b.len() - 8], binary[24..binary.len() - 8]);
+
+        block_index.data_encoded_size = 69;
+
+        test_codec(&b[..], &block_index)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_index_decode_binary_invalid_entries() -> anyhow::Result<()> {
+        let entries = create_testing_block_index().data;
+        let buf = BlockIndex::encode_entries_binary(&entries)?;
+
+        assert_eq!(entries, BlockIndex::decode_entries_binary(&buf)?);
+
+        // Truncated
+        assert!(BlockIndex::decode_entries_binary(&buf[..buf.len() - 1]).is_err());
+
+        // Trailing bytes
+        let mut b = buf.clone();
+        b.push(0);
+        assert!(BlockIndex::decode_entries_binary(&b).is_err());
+
+        // Huge key length
+        let mut b = buf.clone();
+        b[8 + 20] = 0xff;
+        assert!(BlockIndex::decode_entries_binary(&b).is_err());
+
+        // Huge number of entries
+        let mut b = buf.clone();
+        b[0] = 0xff;
+        assert!(BlockIndex::decode_entries_binary(&b).is_err());
+
+        Ok(())
+    }
+
     /// Build a index of `[a..=p, p1..=z]`
     fn create_testing_block_index() -> BlockIndex {
         let ent1 = BlockIndexEntry {
             block_num: 0,
             offset: 2,
             size: 3,
-            first_key: ss("a"),
-            last_key: ss("p"),
+            first_key: bb("a"),
+            last_key: bb("p"),
         };
 
         let ent2 = BlockIndexEntry {
             block_num: 1,
             offset: 5,
             size: 6,
-            first_key: ss("p1"),
-            last_key: ss("z"),
+            first_key: bb("p1"),
+            last_key: bb("z"),
         };
 
         let index_data = vec![ent1.clone(), ent2.clone()];
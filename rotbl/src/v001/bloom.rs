@@ -0,0 +1,241 @@
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+use byteorder::BigEndian;
+use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
+use codeq::config::CodeqConfig;
+
+use crate::typ::Type;
+use crate::v001::header::Header;
+use crate::v001::types::Checksum;
+use crate::version::Version;
+
+/// A bloom filter to tell if a key is definitely absent.
+///
+/// The `k` bit positions of a key are derived from one 64-bit hash by double hashing.
+/// The hash is implemented here rather than borrowed from `std`,
+/// because the filter is persisted and must be stable across Rust versions.
+///
+/// Encoded data layout:
+/// ```text
+/// | Header
+/// | num_hashes: u32
+/// | num_bits: u64
+/// | bits: [u8; (num_bits + 7) / 8]
+/// | Checksum
+/// ```
+#[derive(Clone)]
+#[derive(PartialEq, Eq)]
+pub struct BloomFilter {
+    header: Header,
+
+    num_hashes: u32,
+
+    num_bits: u64,
+
+    bits: Vec<u8>,
+}
+
+impl fmt::Debug for BloomFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BloomFilter")
+            .field("header", &self.header)
+            .field("num_hashes", &self.num_hashes)
+            .field("num_bits", &self.num_bits)
+            .finish()
+    }
+}
+
+impl BloomFilter {
+    /// Build a filter of the given key hashes, using about `bits_per_key` bits for each key.
+    ///
+    /// The hashes are computed with [`BloomFilter::hash()`].
+    pub fn build(hashes: &[u64], bits_per_key: usize) -> Self {
+        // ln(2) * bits_per_key minimizes the false positive rate.
+        let num_hashes = ((bits_per_key as f64 * 0.69) as u32).clamp(1, 30);
+
+        // A tiny filter has a high false positive rate; use at least 64 bits.
+        let num_bits = (hashes.len() as u64 * bits_per_key as u64).max(64);
+
+        let mut f = Self {
+            header: Header::new(Type::BloomFilter, Version::V001),
+            num_hashes,
+            num_bits,
+            bits: vec![0; num_bits.div_ceil(8) as usize],
+        };
+
+        for h in hashes {
+            for bit in bit_positions(*h, num_hashes, num_bits) {
+                f.bits[(bit / 8) as usize] |= 1 << (bit % 8);
+            }
+        }
+
+        f
+    }
+
+    /// The 64-bit hash of a key: FNV-1a followed by the `splitmix64` finalizer.
+    pub fn hash(key: &[u8]) -> u64 {
+        let mut h: u64 = 0xcbf29ce484222325;
+        for b in key {
+            h ^= *b as u64;
+            h = h.wrapping_mul(0x100000001b3);
+        }
+
+        h ^= h >> 30;
+        h = h.wrapping_mul(0xbf58476d1ce4e5b9);
+        h ^= h >> 27;
+        h = h.wrapping_mul(0x94d049bb133111eb);
+        h ^= h >> 31;
+        h
+    }
+
+    /// Return `false` if the key is definitely not in the filter.
+    pub fn may_contain(&self, key: &[u8]) -> bool {
+        let h = Self::hash(key);
+        bit_positions(h, self.num_hashes, self.num_bits)
+            .all(|bit| self.bits[(bit / 8) as usize] & (1 << (bit % 8)) != 0)
+    }
+
+    /// Size in bytes of the filter bits.
+    pub fn size(&self) -> usize {
+        self.bits.len()
+    }
+}
+
+/// The bit positions of a key hash, derived by double hashing.
+fn bit_positions(h: u64, num_hashes: u32, num_bits: u64) -> impl Iterator<Item = u64> {
+    let h1 = h & 0xffff_ffff;
+    let h2 = (h >> 32) | 1;
+
+    (0..num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+}
+
+impl codeq::Encode for BloomFilter {
+    fn encode<W: Write>(&self, mut w: W) -> Result<usize, io::Error> {
+        let mut n = 0usize;
+
+        let mut cw = Checksum::new_writer(&mut w);
+
+        n += self.header.encode(&mut cw)?;
+
+        cw.write_u32::<BigEndian>(self.num_hashes)?;
+        n += 4;
+        cw.write_u64::<BigEndian>(self.num_bits)?;
+        n += 8;
+
+        cw.write_all(&self.bits)?;
+        n += self.bits.len();
+
+        n += cw.write_checksum()?;
+
+        Ok(n)
+    }
+}
+
+impl codeq::Decode for BloomFilter {
+    fn decode<R: Read>(r: R) -> Result<Self, io::Error> {
+        let mut cr = Checksum::new_reader(r);
+
+        let header = Header::decode(&mut cr)?;
+        header.check(Type::BloomFilter, &[Version::V001])?;
+
+        let num_hashes = cr.read_u32::<BigEndian>()?;
+        let num_bits = cr.read_u64::<BigEndian>()?;
+
+        // Read incrementally: `num_bits` is not verified by the checksum yet,
+        // and a corrupted one must not allocate a huge buffer.
+        let bits_size = num_bits.div_ceil(8);
+        let mut bits = Vec::new();
+        (&mut cr).take(bits_size).read_to_end(&mut bits)?;
+        if bits.len() as u64 != bits_size {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!(
+                    "bloom filter bits: expect {} bytes, got {}",
+                    bits_size,
+                    bits.len()
+                ),
+            ));
+        }
+
+        cr.verify_checksum(|| "BloomFilter::decode()")?;
+
+        if num_hashes == 0 || num_bits == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "invalid bloom filter: num_hashes: {}, num_bits: {}",
+                    num_hashes, num_bits
+                ),
+            ));
+        }
+
+        Ok(Self {
+            header,
+            num_hashes,
+            num_bits,
+            bits,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codeq::testing::test_codec;
+    use codeq::Encode;
+
+    use crate::v001::bloom::BloomFilter;
+
+    fn keys(prefix: &str, n: usize) -> Vec<String> {
+        (0..n).map(|i| format!("{}{}", prefix, i)).collect()
+    }
+
+    #[test]
+    fn test_bloom_filter() -> anyhow::Result<()> {
+        let present = keys("key-", 1000);
+        let hashes = present.iter().map(|k| BloomFilter::hash(k.as_bytes())).collect::<Vec<_>>();
+
+        let f = BloomFilter::build(&hashes, 10);
+        assert_eq!(f.size(), 1250);
+
+        for k in present.iter() {
+            assert!(f.may_contain(k.as_bytes()), "no false negative: {}", k);
+        }
+
+        let false_positives =
+            keys("absent-", 10_000).iter().filter(|k| f.may_contain(k.as_bytes())).count();
+
+        // The theoretical rate is about 1% with 10 bits per key.
+        assert!(
+            false_positives < 300,
+            "false positives: {}",
+            false_positives
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bloom_filter_hash_is_stable() {
+        // The hash is persisted; it must never change.
+        assert_eq!(BloomFilter::hash(b""), 0xf52a15e9a9b5e89b);
+        assert_eq!(BloomFilter::hash(b"a"), 0x02c0bdbf481420f8);
+    }
+
+    #[test]
+    fn test_bloom_filter_codec() -> anyhow::Result<()> {
+        let f = BloomFilter::build(&[BloomFilter::hash(b"a")], 10);
+
+        let mut b = Vec::new();
+        let n = f.encode(&mut b)?;
+        assert_eq!(n, b.len());
+        assert_eq!(n, 24 + 4 + 8 + 8 + 8);
+
+        test_codec(b.as_slice(), &f)?;
+
+        Ok(())
+    }
+}
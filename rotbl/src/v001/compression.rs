@@ -0,0 +1,141 @@
+use std::fmt;
+use std::io;
+
+/// The algorithm to compress the data part of a block.
+#[derive(Debug)]
+#[derive(Default)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum Compression {
+    /// Store block data as is.
+    #[default]
+    None,
+
+    /// LZ4 block format: fast, with a moderate ratio.
+    Lz4,
+
+    /// Zstandard at [`Compression::ZSTD_LEVEL`]: slower, with a better ratio.
+    Zstd,
+
+    /// Snappy raw format.
+    Snappy,
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::None => write!(f, "none"),
+            Compression::Lz4 => write!(f, "lz4"),
+            Compression::Zstd => write!(f, "zstd"),
+            Compression::Snappy => write!(f, "snappy"),
+        }
+    }
+}
+
+impl Compression {
+    /// The zstd level, the same as the default of the `zstd` command line tool.
+    pub const ZSTD_LEVEL: i32 = 3;
+
+    /// The id of the algorithm stored in a block.
+    pub fn as_u64(&self) -> u64 {
+        match self {
+            Compression::None => 0,
+            Compression::Lz4 => 1,
+            Compression::Zstd => 2,
+            Compression::Snappy => 3,
+        }
+    }
+
+    pub fn from_u64(v: u64) -> Result<Self, io::Error> {
+        match v {
+            0 => Ok(Compression::None),
+            1 => Ok(Compression::Lz4),
+            2 => Ok(Compression::Zstd),
+            3 => Ok(Compression::Snappy),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid compression: {}", v),
+            )),
+        }
+    }
+
+    pub fn compress(&self, data: &[u8]) -> Result<Vec<u8>, io::Error> {
+        match self {
+            Compression::None => Ok(data.to_vec()),
+            Compression::Lz4 => Ok(lz4_flex::block::compress(data)),
+            Compression::Zstd => zstd::bulk::compress(data, Self::ZSTD_LEVEL),
+            Compression::Snappy => snap::raw::Encoder::new()
+                .compress_vec(data)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e)),
+        }
+    }
+
+    /// Decompress `data` that is `raw_size` bytes before compression.
+    ///
+    /// It returns an error if the decompressed size is not `raw_size`.
+    pub fn decompress(&self, data: &[u8], raw_size: usize) -> Result<Vec<u8>, io::Error> {
+        let invalid = |e: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("failed to decompress {}: {}", self, e),
+            )
+        };
+
+        let raw = match self {
+            Compression::None => data.to_vec(),
+            Compression::Lz4 => {
+                lz4_flex::block::decompress(data, raw_size).map_err(|e| invalid(e.to_string()))?
+            }
+            Compression::Zstd => {
+                zstd::bulk::decompress(data, raw_size).map_err(|e| invalid(e.to_string()))?
+            }
+            Compression::Snappy => snap::raw::Decoder::new()
+                .decompress_vec(data)
+                .map_err(|e| invalid(e.to_string()))?,
+        };
+
+        if raw.len() != raw_size {
+            return Err(invalid(format!(
+                "expect {} bytes, got {} bytes",
+                raw_size,
+                raw.len()
+            )));
+        }
+
+        Ok(raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Compression;
+
+    #[test]
+    fn test_compression_round_trip() -> anyhow::Result<()> {
+        let data = br#"{"name":"foo","value":"bar"}"#.repeat(20);
+
+        for c in [
+            Compression::None,
+            Compression::Lz4,
+            Compression::Zstd,
+            Compression::Snappy,
+        ] {
+            let compressed = c.compress(&data)?;
+            if c != Compression::None {
+                assert!(compressed.len() < data.len() / 4, "{}", c);
+            }
+
+            let got = c.decompress(&compressed, data.len())?;
+            assert_eq!(data, got, "{}", c);
+
+            assert_eq!(c, Compression::from_u64(c.as_u64())?);
+
+            let res = c.decompress(&compressed, data.len() + 1);
+            assert!(res.is_err(), "{}: wrong raw size must be detected", c);
+        }
+
+        assert!(Compression::from_u64(4).is_err());
+
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
+use crate::v001::Compression;
+use crate::v001::PrefixExtractor;
+
 #[derive(Default)]
 #[derive(Debug)]
 #[derive(Clone)]
@@ -5,7 +8,11 @@ pub struct BlockCacheConfig {
     /// Max blocks to cache
     pub max_items: Option<usize>,
 
-    /// Max bytes to cache
+    /// Max bytes to cache.
+    ///
+    /// Cached data blocks and index partitions are both measured by the encoded size
+    /// of their data part in the table file, i.e., a compressed block is measured by its
+    /// compressed size, not by the memory it takes after decompression.
     pub capacity: Option<usize>,
 }
 
@@ -38,7 +45,31 @@ impl BlockCacheConfig {
 #[derive(Clone)]
 pub struct BlockConfig {
     /// Max item per block
+    ///
+    /// Default is 8192 if `target_size` is not set, otherwise a block is cut only by size.
     pub max_items: Option<usize>,
+
+    /// Cut a block once the encoded size of its key-values reaches this many bytes.
+    ///
+    /// The size is counted before compression. A block is also cut at `max_items` if it is set.
+    /// Default is `None`, which cuts blocks only by `max_items`.
+    pub target_size: Option<usize>,
+
+    /// The algorithm to compress the data of every block. Default is no compression.
+    pub compression: Option<Compression>,
+
+    /// Store keys sharing prefixes with the previous key, with a full key every so many entries.
+    ///
+    /// Default is `None`, which stores every key in full.
+    pub restart_interval: Option<usize>,
+
+    /// Pack keys and values with offset tables, so that a block is searched in place. Default is
+    /// false.
+    ///
+    /// A point read then decodes only the value found, and a scan decodes only the values
+    /// iterated, which saves CPU and the memory of cached blocks.
+    /// It can not be used together with `restart_interval`.
+    pub packed: Option<bool>,
 }
 
 impl BlockConfig {
@@ -49,9 +80,115 @@ impl BlockConfig {
         self
     }
 
+    pub fn with_target_size(mut self, target_size: usize) -> Self {
+        self.target_size = Some(target_size);
+        self
+    }
+
+    pub fn with_compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    pub fn with_restart_interval(mut self, restart_interval: usize) -> Self {
+        self.restart_interval = Some(restart_interval);
+        self
+    }
+
+    pub fn with_packed(mut self, packed: bool) -> Self {
+        self.packed = Some(packed);
+        self
+    }
+
     pub fn max_items(&self) -> usize {
         self.max_items.unwrap_or(Self::DEFAULT_MAX_ITEM)
     }
+
+    /// The max number of items per block, or `None` if blocks are cut only by `target_size`.
+    pub fn item_limit(&self) -> Option<usize> {
+        match (self.max_items, self.target_size) {
+            (None, Some(_)) => None,
+            _ => Some(self.max_items()),
+        }
+    }
+
+    pub fn compression(&self) -> Compression {
+        self.compression.unwrap_or_default()
+    }
+
+    pub fn packed(&self) -> bool {
+        self.packed.unwrap_or(false)
+    }
+}
+
+#[derive(Default)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BlockIndexConfig {
+    /// Encode the block index in binary instead of JSON. Default is false.
+    ///
+    /// A binary index is smaller and faster to load,
+    /// but can not be read by versions before it is introduced.
+    pub binary: Option<bool>,
+
+    /// Split the block index into partitions of this many entries. Default is `None`.
+    ///
+    /// Only a small top-level index is kept in memory after opening a table,
+    /// and the partitions are loaded through the block cache when they are needed.
+    /// A partitioned index is always binary.
+    pub partition_size: Option<usize>,
+}
+
+impl BlockIndexConfig {
+    pub fn with_binary(mut self, binary: bool) -> Self {
+        self.binary = Some(binary);
+        self
+    }
+
+    pub fn with_partition_size(mut self, partition_size: usize) -> Self {
+        self.partition_size = Some(partition_size);
+        self
+    }
+
+    pub fn binary(&self) -> bool {
+        self.binary.unwrap_or(false)
+    }
+}
+
+#[derive(Default)]
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct BloomFilterConfig {
+    /// Bits per key of a bloom filter of all keys. Default is `None`, which builds no filter.
+    ///
+    /// With a filter, a `get` of an absent key can be answered without reading a block,
+    /// but the table can not be read by versions before it is introduced.
+    /// 10 bits per key gives a false positive rate of about 1%.
+    pub bits_per_key: Option<usize>,
+
+    /// Build a filter of key prefixes for every block. Default is `None`, which builds no filter.
+    ///
+    /// With it, a range scan within a prefix skips the blocks without the prefix.
+    /// The prefix filters use `bits_per_key` bits per distinct prefix, or 10 if it is not set.
+    pub prefix_extractor: Option<PrefixExtractor>,
+}
+
+impl BloomFilterConfig {
+    const DEFAULT_BITS_PER_KEY: usize = 10;
+
+    pub fn with_bits_per_key(mut self, bits_per_key: usize) -> Self {
+        self.bits_per_key = Some(bits_per_key);
+        self
+    }
+
+    pub fn with_prefix_extractor(mut self, prefix_extractor: PrefixExtractor) -> Self {
+        self.prefix_extractor = Some(prefix_extractor);
+        self
+    }
+
+    pub fn bits_per_key(&self) -> usize {
+        self.bits_per_key.unwrap_or(Self::DEFAULT_BITS_PER_KEY)
+    }
 }
 
 #[derive(Debug)]
@@ -60,6 +197,8 @@ pub struct Config {
     pub debug_check: Option<bool>,
     pub root_path: String,
     pub block_config: BlockConfig,
+    pub block_index: BlockIndexConfig,
+    pub bloom_filter: BloomFilterConfig,
     pub block_cache: BlockCacheConfig,
 }
 
@@ -69,6 +208,8 @@ impl Default for Config {
             debug_check: None,
             root_path: "./.rotbl/".to_string(),
             block_config: Default::default(),
+            block_index: Default::default(),
+            bloom_filter: Default::default(),
             block_cache: Default::default(),
         }
     }
@@ -98,6 +239,16 @@ impl Config {
         self
     }
 
+    pub fn with_block_index_config(mut self, block_index_config: BlockIndexConfig) -> Self {
+        self.block_index = block_index_config;
+        self
+    }
+
+    pub fn with_bloom_filter_config(mut self, bloom_filter_config: BloomFilterConfig) -> Self {
+        self.bloom_filter = bloom_filter_config;
+        self
+    }
+
     pub fn with_block_cache_config(mut self, block_cache_config: BlockCacheConfig) -> Self {
         self.block_cache = block_cache_config;
         self
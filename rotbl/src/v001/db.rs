@@ -1,4 +1,3 @@
-use std::io;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -7,6 +6,8 @@ use lru_cache_map::LruCache;
 use crate::v001::block_cache::BlockCache;
 use crate::v001::block_cache::BlockMeter;
 use crate::v001::config::Config;
+use crate::v001::error::RotblError;
+use crate::v001::value::Value;
 
 pub struct DB {
     #[allow(dead_code)]
@@ -17,7 +18,7 @@ pub struct DB {
 }
 
 impl DB {
-    pub fn open(config: Config) -> Result<Arc<Self>, io::Error> {
+    pub fn open(config: Config) -> Result<Arc<Self>, RotblError> {
         let block_cache = Self::new_cache(config.clone());
 
         let db = Self {
@@ -32,7 +33,8 @@ impl DB {
         self.config.clone()
     }
 
-    pub fn new_cache(config: Config) -> Arc<Mutex<BlockCache>> {
+    /// Create a block cache for the tables whose values are of type `V`.
+    pub fn new_cache<V: Value>(config: Config) -> Arc<Mutex<BlockCache<V>>> {
         let bc = &config.block_cache;
         let block_cache = LruCache::with_meter(bc.max_items(), bc.capacity(), BlockMeter);
         Arc::new(Mutex::new(block_cache))
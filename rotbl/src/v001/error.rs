@@ -0,0 +1,312 @@
+//! The error type of rotbl.
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use codeq::Span;
+
+use crate::typ::Type;
+use crate::version::Version;
+
+/// An error returned by the public API of a [`Rotbl`](crate::v001::Rotbl) and its
+/// [`Builder`](crate::v001::Builder).
+///
+/// A bad file is reported with this error instead of panicking.
+///
+/// The codecs of the parts of a table implement [`codeq`] traits that return [`io::Error`];
+/// a `RotblError` they return is carried inside the [`io::Error`],
+/// and is recovered by [`From<io::Error>`].
+#[derive(Debug)]
+pub enum RotblError {
+    /// An IO error from the underlying storage.
+    Io(io::Error),
+
+    /// The data of a section is invalid, such as a checksum mismatch or a truncated record.
+    Corruption {
+        /// The key of the table file in the storage.
+        table: String,
+
+        /// The name of the section, such as `block_index` or `block`.
+        section: String,
+
+        /// The number of the block, if the corrupted section is a block.
+        block_num: Option<u32>,
+
+        /// The offset of the section in the table file.
+        offset: u64,
+
+        /// The size in bytes of the section.
+        size: u64,
+
+        reason: String,
+    },
+
+    /// The data is written in a version that is not supported by this build.
+    UnsupportedVersion { typ: Type, version: Version },
+
+    /// The data is not of the expected type, such as opening a file that is not a rotbl.
+    WrongType { expected: Type, actual: Type },
+
+    /// An argument or a config value is invalid.
+    InvalidArgument(String),
+}
+
+impl RotblError {
+    pub fn invalid_argument(reason: impl ToString) -> Self {
+        Self::InvalidArgument(reason.to_string())
+    }
+
+    /// The [`io::ErrorKind`] of this error.
+    pub fn kind(&self) -> io::ErrorKind {
+        match self {
+            Self::Io(e) => e.kind(),
+            Self::Corruption { .. } => io::ErrorKind::InvalidData,
+            Self::UnsupportedVersion { .. } => io::ErrorKind::Unsupported,
+            Self::WrongType { .. } => io::ErrorKind::InvalidData,
+            Self::InvalidArgument(_) => io::ErrorKind::InvalidInput,
+        }
+    }
+}
+
+impl fmt::Display for RotblError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "io error: {}", e),
+            Self::Corruption {
+                table,
+                section,
+                block_num,
+                offset,
+                size,
+                reason,
+            } => {
+                write!(f, "corrupted {}", section)?;
+                if let Some(block_num) = block_num {
+                    write!(f, " {}", block_num)?;
+                }
+                write!(
+                    f,
+                    " of table '{}' at bytes [{}, {}): {}",
+                    table,
+                    offset,
+                    offset.saturating_add(*size),
+                    reason
+                )
+            }
+            Self::UnsupportedVersion { typ, version } => {
+                write!(f, "unsupported {} version: {}", typ, version)
+            }
+            Self::WrongType { expected, actual } => {
+                write!(f, "wrong type: expected {}, got {}", expected, actual)
+            }
+            Self::InvalidArgument(reason) => write!(f, "invalid argument: {}", reason),
+        }
+    }
+}
+
+impl Error for RotblError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RotblError {
+    fn from(e: io::Error) -> Self {
+        let carried = e.get_ref().is_some_and(|inner| inner.is::<RotblError>());
+        if carried {
+            *e.into_inner().unwrap().downcast::<RotblError>().unwrap()
+        } else {
+            Self::Io(e)
+        }
+    }
+}
+
+impl From<RotblError> for io::Error {
+    fn from(e: RotblError) -> Self {
+        match e {
+            RotblError::Io(e) => e,
+            other => io::Error::new(other.kind(), other),
+        }
+    }
+}
+
+/// Where a section is in a table file, to build an error about reading or decoding it.
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+pub(crate) struct Location<'a> {
+    table: &'a str,
+    section: &'a str,
+    block_num: Option<u32>,
+    offset: u64,
+    size: u64,
+}
+
+impl<'a> Location<'a> {
+    pub(crate) fn new(table: &'a str, section: &'a str, segment: impl Span) -> Self {
+        Self {
+            table,
+            section,
+            block_num: None,
+            offset: segment.offset().0,
+            size: segment.size().0,
+        }
+    }
+
+    /// The location of a data block.
+    pub(crate) fn block(table: &'a str, block_num: u32, segment: impl Span) -> Self {
+        Self {
+            block_num: Some(block_num),
+            ..Self::new(table, "block", segment)
+        }
+    }
+
+    pub(crate) fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(crate) fn corruption(&self, reason: impl ToString) -> RotblError {
+        RotblError::Corruption {
+            table: self.table.to_string(),
+            section: self.section.to_string(),
+            block_num: self.block_num,
+            offset: self.offset,
+            size: self.size,
+            reason: reason.to_string(),
+        }
+    }
+
+    /// Build an error from a failure to decode the section.
+    ///
+    /// A `RotblError` carried in `e` is returned as is, any other error is a corruption.
+    pub(crate) fn decode_error(&self, e: io::Error) -> RotblError {
+        match RotblError::from(e) {
+            RotblError::Io(e) => self.corruption(e),
+            other => other,
+        }
+    }
+
+    /// Build an error from a failure to read the section from the storage.
+    ///
+    /// A read beyond the end of the file means the file is truncated, which is a corruption.
+    /// Any other error is an IO error, with the location prepended to the message.
+    pub(crate) fn read_error(&self, e: io::Error) -> RotblError {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            return self.corruption(format!("truncated: {}", e));
+        }
+
+        RotblError::Io(io::Error::new(
+            e.kind(),
+            format!("failed to read {}: {}", self, e),
+        ))
+    }
+}
+
+impl fmt::Display for Location<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.section)?;
+        if let Some(block_num) = self.block_num {
+            write!(f, " {}", block_num)?;
+        }
+        write!(
+            f,
+            " of table '{}' at bytes [{}, {})",
+            self.table,
+            self.offset,
+            self.offset.saturating_add(self.size)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use crate::typ::Type;
+    use crate::v001::error::Location;
+    use crate::v001::error::RotblError;
+    use crate::v001::types::Segment;
+    use crate::version::Version;
+
+    #[test]
+    fn test_rotbl_error_io_round_trip() {
+        let e = RotblError::WrongType {
+            expected: Type::Rotbl,
+            actual: Type::Block,
+        };
+        assert_eq!("wrong type: expected Rotbl, got Block", e.to_string());
+
+        let io_err = io::Error::from(e);
+        assert_eq!(io::ErrorKind::InvalidData, io_err.kind());
+
+        let e = RotblError::from(io_err);
+        assert!(matches!(e, RotblError::WrongType {
+            expected: Type::Rotbl,
+            actual: Type::Block
+        }));
+
+        let e = RotblError::from(io::Error::new(io::ErrorKind::NotFound, "foo"));
+        assert!(matches!(e, RotblError::Io(_)));
+        assert_eq!(io::ErrorKind::NotFound, e.kind());
+    }
+
+    #[test]
+    fn test_rotbl_error_decode() {
+        let loc = Location::new("foo.rot", "stat", Segment::new(5, 10));
+        let e = loc.decode_error(io::Error::new(io::ErrorKind::InvalidData, "bad"));
+        assert_eq!(
+            "corrupted stat of table 'foo.rot' at bytes [5, 15): bad",
+            e.to_string()
+        );
+
+        let unsupported = RotblError::UnsupportedVersion {
+            typ: Type::Block,
+            version: Version::V004,
+        };
+        let e = loc.decode_error(io::Error::from(unsupported));
+        assert_eq!("unsupported Block version: V004", e.to_string());
+        assert_eq!(io::ErrorKind::Unsupported, e.kind());
+    }
+
+    #[test]
+    fn test_rotbl_error_block_location() {
+        let loc = Location::block("foo.rot", 3, Segment::new(36, 73));
+        let e = loc.decode_error(io::Error::new(io::ErrorKind::InvalidData, "bad checksum"));
+        assert_eq!(
+            "corrupted block 3 of table 'foo.rot' at bytes [36, 109): bad checksum",
+            e.to_string()
+        );
+        assert!(matches!(e, RotblError::Corruption {
+            block_num: Some(3),
+            offset: 36,
+            size: 73,
+            ..
+        }));
+    }
+
+    #[test]
+    fn test_rotbl_error_read() {
+        let loc = Location::block("foo.rot", 3, Segment::new(36, 73));
+
+        let e = loc.read_error(io::Error::new(io::ErrorKind::UnexpectedEof, "eof"));
+        assert_eq!(
+            "corrupted block 3 of table 'foo.rot' at bytes [36, 109): truncated: eof",
+            e.to_string()
+        );
+
+        let e = loc.read_error(io::Error::new(io::ErrorKind::PermissionDenied, "denied"));
+        assert!(matches!(e, RotblError::Io(_)));
+        assert_eq!(io::ErrorKind::PermissionDenied, e.kind());
+        assert_eq!(
+            "io error: failed to read block 3 of table 'foo.rot' at bytes [36, 109): denied",
+            e.to_string()
+        );
+    }
+}
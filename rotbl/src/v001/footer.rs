@@ -2,10 +2,18 @@ use std::io::Error;
 use std::io::Read;
 use std::io::Write;
 
+use codeq::Decode;
 use codeq::FixedSize;
 
 use crate::v001::types::Segment;
-
+use crate::version::Version;
+
+/// The footer at the end of a rotbl, locating the other sections.
+///
+/// The footer of a [`Version::V002`] rotbl has one more segment locating the
+/// [`Sections`](`crate::v001::Sections`) table.
+/// The version of the footer is the version in the rotbl header,
+/// because the footer size has to be known before reading it.
 #[derive(Debug)]
 #[derive(Clone)]
 #[derive(PartialEq, Eq)]
@@ -18,6 +26,9 @@ pub struct Footer {
 
     /// Offset and size of the stat.
     pub(crate) stat_segment: Segment,
+
+    /// Offset and size of the table of optional sections. Only in V002.
+    pub(crate) sections_segment: Option<Segment>,
 }
 
 impl Footer {
@@ -26,14 +37,56 @@ impl Footer {
             block_index_segment: block_index,
             meta_segment: meta,
             stat_segment: stat,
+            sections_segment: None,
+        }
+    }
+
+    pub fn with_sections(mut self, sections: Segment) -> Self {
+        self.sections_segment = Some(sections);
+        self
+    }
+
+    pub fn sections_segment(&self) -> Option<Segment> {
+        self.sections_segment
+    }
+
+    /// The rotbl version this footer belongs to.
+    pub fn version(&self) -> Version {
+        if self.sections_segment.is_some() {
+            Version::V002
+        } else {
+            Version::V001
         }
     }
-}
 
-impl FixedSize for Footer {
-    fn encoded_size() -> usize {
-        // Block index, meta, stat
-        Segment::encoded_size() * 3
+    /// The encoded size of the footer of the given rotbl version.
+    pub fn encoded_size(version: Version) -> usize {
+        match version {
+            // Block index, meta, stat
+            Version::V001 => Segment::encoded_size() * 3,
+            // Block index, meta, stat, sections
+            _ => Segment::encoded_size() * 4,
+        }
+    }
+
+    /// Decode the footer of the given rotbl version.
+    pub fn decode_version<R: Read>(version: Version, mut r: R) -> Result<Self, Error> {
+        let block_index = Segment::decode(&mut r)?;
+        let meta = Segment::decode(&mut r)?;
+        let stat = Segment::decode(&mut r)?;
+
+        let sections = if version == Version::V001 {
+            None
+        } else {
+            Some(Segment::decode(&mut r)?)
+        };
+
+        Ok(Self {
+            block_index_segment: block_index,
+            meta_segment: meta,
+            stat_segment: stat,
+            sections_segment: sections,
+        })
     }
 }
 
@@ -45,30 +98,29 @@ impl codeq::Encode for Footer {
         n += self.meta_segment.encode(&mut w)?;
         n += self.stat_segment.encode(&mut w)?;
 
+        if let Some(sections) = &self.sections_segment {
+            n += sections.encode(&mut w)?;
+        }
+
         Ok(n)
     }
 }
 
 impl codeq::Decode for Footer {
-    fn decode<R: Read>(mut r: R) -> Result<Self, Error> {
-        let block_index = Segment::decode(&mut r)?;
-        let meta = Segment::decode(&mut r)?;
-        let stat = Segment::decode(&mut r)?;
-
-        Ok(Self {
-            block_index_segment: block_index,
-            meta_segment: meta,
-            stat_segment: stat,
-        })
+    /// Decode a [`Version::V001`] footer.
+    fn decode<R: Read>(r: R) -> Result<Self, Error> {
+        Self::decode_version(Version::V001, r)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use codeq::testing::test_codec;
+    use codeq::Encode;
 
     use crate::v001::footer::Footer;
     use crate::v001::types::Segment;
+    use crate::version::Version;
 
     #[test]
     fn test_footer_codec() -> anyhow::Result<()> {
@@ -91,6 +143,28 @@ mod tests {
         ];
 
         test_codec(b.as_slice(), &f)?;
+        assert_eq!(Footer::encoded_size(Version::V001), b.len());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_footer_codec_v002() -> anyhow::Result<()> {
+        let f = Footer::new(
+            Segment::new(5, 10),
+            Segment::new(3, 4),
+            Segment::new(15, 20),
+        )
+        .with_sections(Segment::new(35, 40));
+        assert_eq!(Version::V002, f.version());
+
+        let mut b = Vec::new();
+        let n = f.encode(&mut b)?;
+        assert_eq!(n, b.len());
+        assert_eq!(Footer::encoded_size(Version::V002), n);
+
+        let got = Footer::decode_version(Version::V002, b.as_slice())?;
+        assert_eq!(f, got);
 
         Ok(())
     }
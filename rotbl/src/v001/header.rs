@@ -7,6 +7,7 @@ use codeq::config::CodeqConfig;
 use codeq::FixedSize;
 
 use crate::typ::Type;
+use crate::v001::error::RotblError;
 use crate::v001::types::Checksum;
 use crate::version::Version;
 
@@ -22,6 +23,35 @@ impl Header {
     pub fn new(typ: Type, version: Version) -> Self {
         Self { typ, version }
     }
+
+    pub fn typ(&self) -> Type {
+        self.typ
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    /// Check that the header is of type `typ` and of one of the `versions`.
+    ///
+    /// A decoder returns this error instead of panicking on the data of another type or version.
+    pub(crate) fn check(&self, typ: Type, versions: &[Version]) -> Result<(), RotblError> {
+        if self.typ != typ {
+            return Err(RotblError::WrongType {
+                expected: typ,
+                actual: self.typ,
+            });
+        }
+
+        if !versions.contains(&self.version) {
+            return Err(RotblError::UnsupportedVersion {
+                typ,
+                version: self.version,
+            });
+        }
+
+        Ok(())
+    }
 }
 
 impl fmt::Display for Header {
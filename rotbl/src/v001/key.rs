@@ -0,0 +1,80 @@
+//! Keys are arbitrary bytes, ordered lexicographically by bytes.
+//!
+//! A `String` key is stored as its UTF-8 bytes, which sort the same way as the `String`,
+//! so that a table with only UTF-8 keys is the same as one built from `String` keys.
+
+use std::fmt;
+use std::io;
+use std::str;
+
+/// Display a key as a string if it is valid UTF-8, otherwise as escaped bytes.
+pub(crate) struct KeyDisplay<'a>(pub(crate) &'a [u8]);
+
+impl fmt::Display for KeyDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match str::from_utf8(self.0) {
+            Ok(s) => write!(f, "{}", s),
+            Err(_) => write!(f, "{}", self.0.escape_ascii()),
+        }
+    }
+}
+
+/// Serialize a key as a string, for the JSON block index that only stores UTF-8 keys.
+pub(crate) mod utf8_serde {
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::Deserialize;
+    use serde::Deserializer;
+    use serde::Serializer;
+
+    pub(crate) fn serialize<S>(key: &[u8], serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        let s = std::str::from_utf8(key).map_err(|e| {
+            S::Error::custom(format!("non-UTF-8 key requires binary encoding: {}", e))
+        })?;
+        serializer.serialize_str(s)
+    }
+
+    pub(crate) fn deserialize<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
+    where D: Deserializer<'de> {
+        let s = String::deserialize(deserializer).map_err(D::Error::custom)?;
+        Ok(s.into_bytes())
+    }
+}
+
+/// Convert a key back to a `String`, for the APIs that return `String` keys.
+pub(crate) fn into_string(key: Vec<u8>) -> Result<String, io::Error> {
+    String::from_utf8(key).map_err(|e| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "key is not UTF-8: {}, use the bytes API instead",
+                KeyDisplay(e.as_bytes())
+            ),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::into_string;
+    use super::KeyDisplay;
+
+    #[test]
+    fn test_key_display() {
+        assert_eq!("abc", KeyDisplay(b"abc").to_string());
+        assert_eq!("é", KeyDisplay("é".as_bytes()).to_string());
+        assert_eq!("a\\xff\\x00", KeyDisplay(b"a\xff\x00").to_string());
+    }
+
+    #[test]
+    fn test_into_string() {
+        assert_eq!("abc", into_string(b"abc".to_vec()).unwrap());
+
+        let err = into_string(b"a\xff".to_vec()).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().contains("a\\xff"));
+    }
+}
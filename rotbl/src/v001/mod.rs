@@ -4,38 +4,58 @@ mod block_encoding_meta;
 mod block_id;
 mod block_index;
 mod block_stream;
+mod bloom;
 mod cache_stat;
+mod compression;
 mod config;
 mod db;
 mod footer;
 mod header;
+mod key;
+mod packed_entries;
+mod prefix_bloom;
+mod prefix_extractor;
+mod prefix_keys;
+mod properties;
 mod range;
 mod rotbl;
 mod rotbl_meta;
 pub mod rotbl_meta_payload;
+mod sections;
 pub(crate) mod testing;
+mod value;
 
 pub(crate) mod bincode_config;
+pub(crate) mod error;
 pub(crate) mod types;
 
 pub use block_id::BlockId;
 pub use block_index::BlockIndex;
 pub use block_index::BlockIndexEntry;
 pub use block_stream::BlockStream;
+pub use bloom::BloomFilter;
 pub use cache_stat::CacheStat;
+pub use compression::Compression;
 pub use config::BlockCacheConfig;
 pub use config::BlockConfig;
+pub use config::BlockIndexConfig;
+pub use config::BloomFilterConfig;
 pub use config::Config;
 pub use db::DB;
+pub use error::RotblError;
 pub use footer::Footer;
 pub use header::Header;
+pub use prefix_bloom::PrefixBloomFilter;
+pub use prefix_extractor::PrefixExtractor;
+pub use properties::Properties;
+pub use properties::PropertyCollector;
 pub use rotbl::builder::Builder;
 pub use rotbl::dump::Dump;
 pub use rotbl::stat;
 pub use rotbl::Rotbl;
 pub use rotbl_meta::RotblMeta;
+pub use sections::Sections;
 pub use seq_marked::Marked;
 pub use seq_marked::SeqMarked;
 pub use types::Segment;
-
-// TODO: introduce an Error for rotbl
+pub use value::Value;
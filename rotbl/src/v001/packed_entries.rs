@@ -0,0 +1,233 @@
+//! The packed layout of the data part of a block.
+//!
+//! Keys and bincode encoded values are stored in two [`RawVLArray`]s, each with an offset table,
+//! so that a key is binary searched in place, and only the values that are read are decoded.
+//!
+//! Layout:
+//!
+//! ```text
+//! | keys: RawVLArray | values: RawVLArray |
+//! ```
+
+use std::collections::BTreeMap;
+use std::io;
+use std::ops::Bound;
+use std::ops::Range;
+
+use bytes::Bytes;
+use codeq::Encode;
+
+use crate::v001::bincode_config::bincode_config;
+use crate::v001::value::Value;
+use crate::v00x::RawVLArray;
+use crate::v00x::RawVLArrayBuilder;
+
+/// Encode sorted key-values in the packed layout.
+pub(crate) fn encode<V: bincode::Encode>(
+    data: &BTreeMap<Vec<u8>, V>,
+) -> Result<Vec<u8>, io::Error> {
+    let values = data
+        .values()
+        .map(|v| bincode::encode_to_vec(v, bincode_config()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    let keys = RawVLArrayBuilder::new(Some(data.len())).build(data.keys());
+    let values = RawVLArrayBuilder::new(Some(data.len())).build(values);
+
+    let mut buf = Vec::new();
+    keys.encode(&mut buf)?;
+    values.encode(&mut buf)?;
+
+    Ok(buf)
+}
+
+/// The data part of a block in the packed layout, which is searched without decoding.
+///
+/// Keys and encoded values refer to the buffer they are decoded from, without copying.
+#[derive(Debug)]
+#[derive(Clone)]
+pub(crate) struct PackedEntries {
+    keys: RawVLArray,
+    values: RawVLArray,
+}
+
+impl PackedEntries {
+    /// Parse the data in the packed layout, checking the offset tables.
+    pub(crate) fn new(buf: Bytes) -> Result<Self, io::Error> {
+        let (keys, n) = RawVLArray::decode_bytes(&buf)?;
+        let (values, m) = RawVLArray::decode_bytes(&buf.slice(n..))?;
+
+        if keys.len() != values.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "invalid packed block data: {} keys but {} values",
+                    keys.len(),
+                    values.len()
+                ),
+            ));
+        }
+
+        if n + m != buf.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "invalid packed block data: {} bytes after entries",
+                    buf.len() - n - m
+                ),
+            ));
+        }
+
+        Ok(Self { keys, values })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// The key at index `i`, a slice of the block data.
+    pub(crate) fn key(&self, i: usize) -> &[u8] {
+        self.keys.get(i).unwrap()
+    }
+
+    /// Decode the value at index `i`.
+    pub(crate) fn value<V: Value>(&self, i: usize) -> Result<V, io::Error> {
+        let (value, _size) =
+            bincode::decode_from_slice(self.values.get(i).unwrap(), bincode_config())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(value)
+    }
+
+    /// Find the value of `key` by binary searching the keys in place.
+    pub(crate) fn get<V: Value>(&self, key: &[u8]) -> Result<Option<V>, io::Error> {
+        let i = self.partition_point(|k| k < key);
+
+        if i < self.len() && self.key(i) == key {
+            Ok(Some(self.value(i)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Return the indexes of the keys in the range.
+    pub(crate) fn range_indexes(&self, start: Bound<&[u8]>, end: Bound<&[u8]>) -> Range<usize> {
+        let left = match start {
+            Bound::Included(s) => self.partition_point(|k| k < s),
+            Bound::Excluded(s) => self.partition_point(|k| k <= s),
+            Bound::Unbounded => 0,
+        };
+
+        let right = match end {
+            Bound::Included(e) => self.partition_point(|k| k <= e),
+            Bound::Excluded(e) => self.partition_point(|k| k < e),
+            Bound::Unbounded => self.len(),
+        };
+
+        left..right.max(left)
+    }
+
+    /// Decode all the entries.
+    pub(crate) fn decode_all<V: Value>(&self) -> Result<BTreeMap<Vec<u8>, V>, io::Error> {
+        (0..self.len()).map(|i| Ok((self.key(i).to_vec(), self.value(i)?))).collect()
+    }
+
+    /// The number of keys for which `pred` is true, assuming it is true for a prefix of the keys.
+    fn partition_point(&self, pred: impl Fn(&[u8]) -> bool) -> usize {
+        let mut left = 0;
+        let mut right = self.len();
+
+        while left < right {
+            let mid = (left + right) / 2;
+            if pred(self.key(mid)) {
+                left = mid + 1;
+            } else {
+                right = mid;
+            }
+        }
+
+        left
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::ops::Bound;
+
+    use bytes::Bytes;
+
+    use crate::v001::packed_entries::encode;
+    use crate::v001::packed_entries::PackedEntries;
+    use crate::v001::testing::bb;
+    use crate::v001::SeqMarked;
+
+    fn data(n: usize) -> BTreeMap<Vec<u8>, SeqMarked> {
+        (0..n)
+            .map(|i| {
+                let k = bb(format!("k{:03}", i * 2));
+                (k, SeqMarked::new_normal(i as u64, bb(format!("v{}", i))))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_packed_entries() -> anyhow::Result<()> {
+        for n in [0, 1, 2, 7, 64] {
+            let d = data(n);
+            let buf = encode(&d)?;
+            let pe = PackedEntries::new(Bytes::from(buf))?;
+
+            assert_eq!(n, pe.len());
+            assert_eq!(d, pe.decode_all()?);
+
+            for (k, v) in d.iter() {
+                assert_eq!(Some(v.clone()), pe.get(k)?, "n={} key={:?}", n, k);
+
+                let absent = [k.as_slice(), b"-"].concat();
+                assert_eq!(None, pe.get::<SeqMarked>(&absent)?);
+            }
+
+            assert_eq!(None, pe.get::<SeqMarked>(b"")?);
+            assert_eq!(None, pe.get::<SeqMarked>(b"zzz")?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed_entries_range_indexes() -> anyhow::Result<()> {
+        // keys: k000, k002, k004, k006, k008
+        let pe = PackedEntries::new(Bytes::from(encode(&data(5))?))?;
+
+        let incl = |k: &'static [u8]| Bound::Included(k);
+        let excl = |k: &'static [u8]| Bound::Excluded(k);
+
+        assert_eq!(0..5, pe.range_indexes(Bound::Unbounded, Bound::Unbounded));
+        assert_eq!(1..3, pe.range_indexes(incl(b"k002"), excl(b"k006")));
+        assert_eq!(2..4, pe.range_indexes(excl(b"k002"), incl(b"k006")));
+        assert_eq!(1..2, pe.range_indexes(incl(b"k001"), excl(b"k003")));
+        assert_eq!(5..5, pe.range_indexes(incl(b"k009"), Bound::Unbounded));
+        assert_eq!(3..3, pe.range_indexes(incl(b"k006"), excl(b"k002")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_packed_entries_invalid() -> anyhow::Result<()> {
+        let buf = encode(&data(5))?;
+
+        // Truncated
+        for len in [0, 7, 30, buf.len() - 1] {
+            let res = PackedEntries::new(Bytes::copy_from_slice(&buf[..len]));
+            assert!(res.is_err(), "len={}", len);
+        }
+
+        // Trailing bytes
+        let mut b = buf.clone();
+        b.push(0);
+        assert!(PackedEntries::new(Bytes::from(b)).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+use byteorder::BigEndian;
+use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
+use codeq::config::CodeqConfig;
+
+use crate::typ::Type;
+use crate::v001::bloom::BloomFilter;
+use crate::v001::header::Header;
+use crate::v001::prefix_extractor::PrefixExtractor;
+use crate::v001::types::Checksum;
+use crate::version::Version;
+
+/// Bloom filters of the key prefixes in every block, one filter per block.
+///
+/// A scan over a prefix skips the blocks whose filter does not contain the prefix,
+/// and a table can be skipped if no block may contain it.
+///
+/// The extractor is stored along with the filters,
+/// so that the table is queried the same way it is built, regardless of the config to open it.
+///
+/// Encoded data layout:
+/// ```text
+/// | Header
+/// | PrefixExtractor
+/// | n: u64
+/// | BloomFilter * n
+/// | Checksum
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq, Eq)]
+pub struct PrefixBloomFilter {
+    header: Header,
+
+    extractor: PrefixExtractor,
+
+    /// The filter of the block `i` is at index `i`.
+    filters: Vec<BloomFilter>,
+}
+
+impl PrefixBloomFilter {
+    pub fn new(extractor: PrefixExtractor) -> Self {
+        Self {
+            header: Header::new(Type::PrefixBloomFilter, Version::V001),
+            extractor,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Build and append the filter of the next block, from the sorted keys in it.
+    pub fn push_block<'k>(
+        &mut self,
+        keys: impl IntoIterator<Item = &'k [u8]>,
+        bits_per_key: usize,
+    ) {
+        let mut hashes = Vec::new();
+        let mut prev = None;
+
+        for key in keys {
+            let Some(prefix) = self.extractor.extract(key) else {
+                continue;
+            };
+
+            // Keys are sorted, so the same prefixes are adjacent.
+            if prev != Some(prefix) {
+                hashes.push(BloomFilter::hash(prefix));
+                prev = Some(prefix);
+            }
+        }
+
+        self.filters.push(BloomFilter::build(&hashes, bits_per_key));
+    }
+
+    pub fn extractor(&self) -> &PrefixExtractor {
+        &self.extractor
+    }
+
+    /// Return `false` if the block definitely contains no key with `prefix`.
+    ///
+    /// A block without a filter may contain any prefix.
+    pub fn may_contain(&self, block_num: u32, prefix: &[u8]) -> bool {
+        match self.filters.get(block_num as usize) {
+            Some(f) => f.may_contain(prefix),
+            None => true,
+        }
+    }
+
+    /// Number of blocks with a filter.
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Size in bytes of the filter bits of all blocks.
+    pub fn size(&self) -> usize {
+        self.filters.iter().map(|f| f.size()).sum()
+    }
+}
+
+impl codeq::Encode for PrefixBloomFilter {
+    fn encode<W: Write>(&self, mut w: W) -> Result<usize, io::Error> {
+        let mut n = 0usize;
+
+        let mut cw = Checksum::new_writer(&mut w);
+
+        n += self.header.encode(&mut cw)?;
+        n += self.extractor.encode(&mut cw)?;
+
+        cw.write_u64::<BigEndian>(self.filters.len() as u64)?;
+        n += 8;
+
+        for f in self.filters.iter() {
+            n += f.encode(&mut cw)?;
+        }
+
+        n += cw.write_checksum()?;
+
+        Ok(n)
+    }
+}
+
+impl codeq::Decode for PrefixBloomFilter {
+    fn decode<R: Read>(r: R) -> Result<Self, io::Error> {
+        let mut cr = Checksum::new_reader(r);
+
+        let header = Header::decode(&mut cr)?;
+        header.check(Type::PrefixBloomFilter, &[Version::V001])?;
+
+        let extractor = PrefixExtractor::decode(&mut cr)?;
+
+        let n = cr.read_u64::<BigEndian>()?;
+
+        // Do not pre-allocate: `n` is not verified by the checksum yet.
+        let mut filters = Vec::new();
+        for _ in 0..n {
+            filters.push(BloomFilter::decode(&mut cr)?);
+        }
+
+        cr.verify_checksum(|| "PrefixBloomFilter::decode()")?;
+
+        Ok(Self {
+            header,
+            extractor,
+            filters,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codeq::testing::test_codec;
+    use codeq::Encode;
+
+    use crate::v001::prefix_bloom::PrefixBloomFilter;
+    use crate::v001::prefix_extractor::PrefixExtractor;
+
+    #[test]
+    fn test_prefix_bloom_filter() -> anyhow::Result<()> {
+        let mut f = PrefixBloomFilter::new(PrefixExtractor::Delimiter {
+            delimiter: '/',
+            count: 2,
+        });
+
+        f.push_block([&b"user/1/a"[..], b"user/1/b", b"user/2/a", b"x"], 10);
+        f.push_block([&b"user/3/a"[..], b"user/4/a"], 10);
+
+        assert_eq!(2, f.len());
+
+        assert!(f.may_contain(0, b"user/1/"));
+        assert!(f.may_contain(0, b"user/2/"));
+        assert!(f.may_contain(1, b"user/3/"));
+        assert!(f.may_contain(1, b"user/4/"));
+
+        let absent =
+            (5..1000).filter(|i| f.may_contain(1, format!("user/{}/", i).as_bytes())).count();
+        assert!(absent < 30, "false positives: {}", absent);
+
+        // No filter for block 2
+        assert!(f.may_contain(2, b"user/1/"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_bloom_filter_codec() -> anyhow::Result<()> {
+        let mut f = PrefixBloomFilter::new(PrefixExtractor::FixedLength(2));
+        f.push_block([&b"aa"[..], b"ab"], 10);
+        f.push_block([&b"ba"[..]], 10);
+
+        let mut b = Vec::new();
+        let n = f.encode(&mut b)?;
+        assert_eq!(n, b.len());
+        assert_eq!(n, 24 + 16 + 8 + (24 + 4 + 8 + 8 + 8) * 2 + 8);
+
+        test_codec(b.as_slice(), &f)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,302 @@
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::ops::Bound;
+use std::ops::RangeBounds;
+
+use byteorder::BigEndian;
+use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
+
+/// Extracts the prefix of a key, to build and query a prefix bloom filter.
+///
+/// A key without such a prefix, such as one shorter than the fixed length,
+/// is out of the domain of the extractor and is not added to the filter.
+///
+/// Encoded data layout:
+/// ```text
+/// | kind: u32 | delimiter: u32 | n: u64
+/// ```
+#[derive(Debug)]
+#[derive(Clone, Copy)]
+#[derive(PartialEq, Eq)]
+pub enum PrefixExtractor {
+    /// The first `n` bytes of a key.
+    FixedLength(usize),
+
+    /// A key up to and including the `count`-th `delimiter`.
+    ///
+    /// For example, the prefix of `user/42/profile` with delimiter `/` and count 2 is `user/42/`.
+    Delimiter { delimiter: char, count: usize },
+}
+
+impl fmt::Display for PrefixExtractor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PrefixExtractor::FixedLength(n) => write!(f, "FixedLength({})", n),
+            PrefixExtractor::Delimiter { delimiter, count } => {
+                write!(f, "Delimiter({:?} x {})", delimiter, count)
+            }
+        }
+    }
+}
+
+impl PrefixExtractor {
+    const FIXED_LENGTH: u32 = 1;
+    const DELIMITER: u32 = 2;
+
+    /// Return the prefix of `key`, or `None` if `key` is out of the domain.
+    ///
+    /// The delimiter is matched against its UTF-8 encoding in the key bytes.
+    pub fn extract<'k>(&self, key: &'k [u8]) -> Option<&'k [u8]> {
+        match self {
+            PrefixExtractor::FixedLength(n) => key.get(..*n),
+            PrefixExtractor::Delimiter { delimiter, count } => {
+                let mut buf = [0u8; 4];
+                let delimiter = delimiter.encode_utf8(&mut buf).as_bytes();
+
+                let mut seen = 0;
+                let mut i = 0;
+                while i + delimiter.len() <= key.len() {
+                    if key[i..].starts_with(delimiter) {
+                        seen += 1;
+                        i += delimiter.len();
+                        if seen == *count {
+                            return Some(&key[..i]);
+                        }
+                    } else {
+                        i += 1;
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Return the prefix shared by every key in `range`, if there is one.
+    ///
+    /// For example, with delimiter `/` and count 2,
+    /// the prefix of `"user/42/a".."user/42/b"` is `user/42/`,
+    /// while `"user/42/".."user/43/"` has no common prefix.
+    pub fn prefix_of_range<'r, R>(&self, range: &'r R) -> Option<&'r [u8]>
+    where R: RangeBounds<Vec<u8>> {
+        let start = match range.start_bound() {
+            Bound::Included(s) | Bound::Excluded(s) => s,
+            Bound::Unbounded => return None,
+        };
+
+        let prefix = self.extract(start)?;
+
+        let within = match range.end_bound() {
+            Bound::Included(e) => e.starts_with(prefix),
+            Bound::Excluded(e) => {
+                e.starts_with(prefix)
+                    || prefix_upper_bound(prefix).is_some_and(|u| e.as_slice() <= u.as_slice())
+            }
+            Bound::Unbounded => false,
+        };
+
+        within.then_some(prefix)
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), io::Error> {
+        let valid = match self {
+            PrefixExtractor::FixedLength(n) => *n > 0,
+            PrefixExtractor::Delimiter { count, .. } => *count > 0,
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("invalid prefix extractor: {}", self),
+            ))
+        }
+    }
+}
+
+/// The smallest key that is greater than every key starting with `prefix`.
+///
+/// Returns `None` if there is no such key, i.e., `prefix` consists of only `0xFF`.
+pub(crate) fn prefix_upper_bound(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut k = prefix.to_vec();
+
+    while let Some(b) = k.pop() {
+        if b < 0xFF {
+            k.push(b + 1);
+            return Some(k);
+        }
+    }
+
+    None
+}
+
+/// The range of all keys starting with `prefix`.
+pub(crate) fn prefix_range(prefix: &[u8]) -> (Bound<Vec<u8>>, Bound<Vec<u8>>) {
+    let end = match prefix_upper_bound(prefix) {
+        Some(u) => Bound::Excluded(u),
+        None => Bound::Unbounded,
+    };
+
+    (Bound::Included(prefix.to_vec()), end)
+}
+
+impl codeq::Encode for PrefixExtractor {
+    fn encode<W: Write>(&self, mut w: W) -> Result<usize, io::Error> {
+        let (kind, delimiter, n) = match self {
+            PrefixExtractor::FixedLength(n) => (Self::FIXED_LENGTH, 0, *n),
+            PrefixExtractor::Delimiter { delimiter, count } => {
+                (Self::DELIMITER, *delimiter as u32, *count)
+            }
+        };
+
+        w.write_u32::<BigEndian>(kind)?;
+        w.write_u32::<BigEndian>(delimiter)?;
+        w.write_u64::<BigEndian>(n as u64)?;
+
+        Ok(16)
+    }
+}
+
+impl codeq::Decode for PrefixExtractor {
+    fn decode<R: Read>(mut r: R) -> Result<Self, io::Error> {
+        let kind = r.read_u32::<BigEndian>()?;
+        let delimiter = r.read_u32::<BigEndian>()?;
+        let n = r.read_u64::<BigEndian>()? as usize;
+
+        match kind {
+            Self::FIXED_LENGTH => Ok(PrefixExtractor::FixedLength(n)),
+            Self::DELIMITER => {
+                let delimiter = char::from_u32(delimiter).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("invalid prefix extractor delimiter: {}", delimiter),
+                    )
+                })?;
+
+                Ok(PrefixExtractor::Delimiter {
+                    delimiter,
+                    count: n,
+                })
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid prefix extractor kind: {}", kind),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codeq::Decode;
+    use codeq::Encode;
+
+    use super::prefix_upper_bound;
+    use super::PrefixExtractor;
+    use crate::v001::testing::bb;
+
+    #[test]
+    fn test_extract() {
+        let fixed = PrefixExtractor::FixedLength(3);
+        assert_eq!(Some(&b"abc"[..]), fixed.extract(b"abcd"));
+        assert_eq!(Some(&b"abc"[..]), fixed.extract(b"abc"));
+        assert_eq!(None, fixed.extract(b"ab"));
+        assert_eq!(Some(&b"a\xff\x00"[..]), fixed.extract(b"a\xff\x00\x01"));
+
+        let delim = PrefixExtractor::Delimiter {
+            delimiter: '/',
+            count: 2,
+        };
+        assert_eq!(Some(&b"user/42/"[..]), delim.extract(b"user/42/profile"));
+        assert_eq!(Some(&b"user/42/"[..]), delim.extract(b"user/42/"));
+        assert_eq!(Some(&b"\xff/\x00/"[..]), delim.extract(b"\xff/\x00/a"));
+        assert_eq!(None, delim.extract(b"user/42"));
+        assert_eq!(None, delim.extract(b""));
+
+        // A multi-byte delimiter
+        let delim = PrefixExtractor::Delimiter {
+            delimiter: 'é',
+            count: 1,
+        };
+        assert_eq!(Some("aé".as_bytes()), delim.extract("aébé".as_bytes()));
+        assert_eq!(None, delim.extract(b"a\xc3"));
+    }
+
+    #[test]
+    fn test_prefix_upper_bound() {
+        assert_eq!(None, prefix_upper_bound(b""));
+        assert_eq!(Some(bb("user/420")), prefix_upper_bound(b"user/42/"));
+        assert_eq!(Some(bb("b")), prefix_upper_bound(b"a"));
+        assert_eq!(Some(bb("b")), prefix_upper_bound(b"a\xff\xff"));
+        assert_eq!(None, prefix_upper_bound(b"\xff"));
+    }
+
+    #[test]
+    fn test_prefix_of_range() {
+        let delim = PrefixExtractor::Delimiter {
+            delimiter: '/',
+            count: 2,
+        };
+
+        let p = Some(&b"user/42/"[..]);
+        assert_eq!(p, delim.prefix_of_range(&(bb("user/42/")..bb("user/420"))));
+        assert_eq!(
+            p,
+            delim.prefix_of_range(&(bb("user/42/a")..bb("user/42/b")))
+        );
+        assert_eq!(
+            p,
+            delim.prefix_of_range(&(bb("user/42/a")..=bb("user/42/b")))
+        );
+
+        assert_eq!(
+            None,
+            delim.prefix_of_range(&(bb("user/42/")..bb("user/421")))
+        );
+        assert_eq!(
+            None,
+            delim.prefix_of_range(&(bb("user/42/")..=bb("user/420")))
+        );
+        assert_eq!(None, delim.prefix_of_range(&(bb("user/42/")..)));
+        assert_eq!(None, delim.prefix_of_range(&(..bb("user/42/b"))));
+        assert_eq!(
+            None,
+            delim.prefix_of_range(&(bb("user/42")..bb("user/42/b")))
+        );
+    }
+
+    #[test]
+    fn test_prefix_extractor_codec() -> anyhow::Result<()> {
+        let fixed = PrefixExtractor::FixedLength(3);
+
+        let mut b = Vec::new();
+        let n = fixed.encode(&mut b)?;
+        assert_eq!(n, b.len());
+        assert_eq!(b, vec![0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 3]);
+        assert_eq!(fixed, PrefixExtractor::decode(b.as_slice())?);
+
+        let delim = PrefixExtractor::Delimiter {
+            delimiter: '/',
+            count: 2,
+        };
+
+        let mut b = Vec::new();
+        let n = delim.encode(&mut b)?;
+        assert_eq!(n, b.len());
+        assert_eq!(b, vec![0, 0, 0, 2, 0, 0, 0, 47, 0, 0, 0, 0, 0, 0, 0, 2]);
+        assert_eq!(delim, PrefixExtractor::decode(b.as_slice())?);
+
+        // Invalid kind
+        let b = vec![0, 0, 0, 3, 0, 0, 0, 47, 0, 0, 0, 0, 0, 0, 0, 2];
+        assert!(PrefixExtractor::decode(b.as_slice()).is_err());
+
+        // Invalid delimiter: a surrogate
+        let b = vec![0, 0, 0, 2, 0, 0, 0xD8, 0, 0, 0, 0, 0, 0, 0, 0, 2];
+        assert!(PrefixExtractor::decode(b.as_slice()).is_err());
+
+        Ok(())
+    }
+}
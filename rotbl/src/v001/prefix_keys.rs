@@ -0,0 +1,349 @@
+//! The prefix-keys layout of the data part of a block.
+//!
+//! Every key is stored as the length of the prefix it shares with the previous key,
+//! followed by the remaining suffix.
+//! Every `restart_interval` entries, a key is stored in full as a restart point,
+//! so that a lookup can binary search the restart points
+//! and then decode at most `restart_interval` entries.
+//!
+//! Layout:
+//!
+//! ```text
+//! | entry 0 | entry 1 | ... | restart offsets: u32 * n | restart_interval: u32 | n: u32 |
+//! ```
+//!
+//! An entry is a bincode encoded `(shared: u64, suffix: Vec<u8>, value: V)`.
+//! A `String` suffix is encoded the same way, so the blocks built from `String` keys are readable.
+//! Restart offsets, `restart_interval` and `n` are big-endian.
+
+use std::collections::BTreeMap;
+use std::io;
+
+use byteorder::BigEndian;
+use byteorder::ByteOrder;
+
+use crate::v001::bincode_config::bincode_config;
+use crate::v001::key::KeyDisplay;
+use crate::v001::value::Value;
+
+/// Encode sorted key-values in the prefix-keys layout.
+///
+/// It returns an [`io::ErrorKind::InvalidInput`] error if `restart_interval` is 0.
+pub(crate) fn encode<V: bincode::Encode>(
+    data: &BTreeMap<Vec<u8>, V>,
+    restart_interval: usize,
+) -> Result<Vec<u8>, io::Error> {
+    if restart_interval == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "restart_interval must be greater than 0",
+        ));
+    }
+
+    let mut buf = Vec::new();
+    let mut restarts = Vec::new();
+    let mut prev: &[u8] = &[];
+
+    for (i, (key, value)) in data.iter().enumerate() {
+        let shared = if i % restart_interval == 0 {
+            restarts.push(buf.len() as u32);
+            0
+        } else {
+            shared_prefix_len(prev, key)
+        };
+
+        let entry = (shared as u64, &key[shared..], value);
+        bincode::encode_into_std_write(entry, &mut buf, bincode_config())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        prev = key;
+    }
+
+    for offset in restarts.iter() {
+        buf.extend_from_slice(&offset.to_be_bytes());
+    }
+    buf.extend_from_slice(&(restart_interval as u32).to_be_bytes());
+    buf.extend_from_slice(&(restarts.len() as u32).to_be_bytes());
+
+    Ok(buf)
+}
+
+/// Length in bytes of the common prefix of `a` and `b`.
+fn shared_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// The data part of a block in the prefix-keys layout, which can be searched without decoding.
+#[derive(Debug)]
+#[derive(Clone)]
+pub(crate) struct PrefixKeys {
+    buf: Vec<u8>,
+
+    /// The end offset of the entries, where the restart offsets start.
+    entries_end: usize,
+
+    restart_interval: u32,
+
+    num_restarts: usize,
+}
+
+impl PrefixKeys {
+    /// Parse and check the trailer of the data in the prefix-keys layout.
+    pub(crate) fn new(buf: Vec<u8>) -> Result<Self, io::Error> {
+        let invalid = |reason: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid prefix-keys block data: {}", reason),
+            )
+        };
+
+        let len = buf.len();
+        if len < 8 {
+            return Err(invalid(format!("size {} is smaller than the trailer", len)));
+        }
+
+        let restart_interval = BigEndian::read_u32(&buf[len - 8..]);
+        let num_restarts = BigEndian::read_u32(&buf[len - 4..]) as usize;
+
+        if restart_interval == 0 {
+            return Err(invalid("restart_interval is 0".to_string()));
+        }
+
+        let Some(entries_end) = (len - 8).checked_sub(num_restarts * 4) else {
+            return Err(invalid(format!(
+                "{} restart points do not fit in {} bytes",
+                num_restarts, len
+            )));
+        };
+
+        let pk = Self {
+            buf,
+            entries_end,
+            restart_interval,
+            num_restarts,
+        };
+
+        let mut prev = None;
+        for i in 0..num_restarts {
+            let offset = pk.restart_offset(i);
+            if offset >= entries_end || prev.is_some_and(|p| offset <= p) || (i == 0 && offset != 0)
+            {
+                return Err(invalid(format!("invalid restart offset {}: {}", i, offset)));
+            }
+            prev = Some(offset);
+        }
+
+        if num_restarts == 0 && entries_end != 0 {
+            return Err(invalid("entries without restart point".to_string()));
+        }
+
+        Ok(pk)
+    }
+
+    pub(crate) fn restart_interval(&self) -> usize {
+        self.restart_interval as usize
+    }
+
+    fn restart_offset(&self, i: usize) -> usize {
+        let start = self.entries_end + i * 4;
+        BigEndian::read_u32(&self.buf[start..start + 4]) as usize
+    }
+
+    /// Decode the entry at `offset` whose key shares a prefix with `prev_key`.
+    ///
+    /// Returns the full key, the value and the offset of the next entry.
+    fn decode_entry<V: Value>(
+        &self,
+        offset: usize,
+        prev_key: &[u8],
+    ) -> Result<(Vec<u8>, V, usize), io::Error> {
+        let ((shared, suffix, value), n): ((u64, Vec<u8>, V), usize) =
+            bincode::decode_from_slice(&self.buf[offset..self.entries_end], bincode_config())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        let shared = shared as usize;
+        if shared > prev_key.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "invalid shared prefix length {} at offset {}, previous key: {}",
+                    shared,
+                    offset,
+                    KeyDisplay(prev_key)
+                ),
+            ));
+        }
+
+        let mut key = prev_key[..shared].to_vec();
+        key.extend_from_slice(&suffix);
+        Ok((key, value, offset + n))
+    }
+
+    /// Find the value of `key` by binary searching the restart points.
+    ///
+    /// At most one restart interval of entries is decoded.
+    pub(crate) fn get<V: Value>(&self, key: &[u8]) -> Result<Option<V>, io::Error> {
+        // Find the last restart point whose key is <= `key`.
+        let mut left = 0;
+        let mut right = self.num_restarts;
+
+        while left < right {
+            let mid = (left + right) / 2;
+            let (k, _, _) = self.decode_entry::<V>(self.restart_offset(mid), &[])?;
+            if k.as_slice() <= key {
+                left = mid + 1;
+            } else {
+                right = mid;
+            }
+        }
+
+        if left == 0 {
+            return Ok(None);
+        }
+
+        let restart = left - 1;
+        let end = if restart + 1 < self.num_restarts {
+            self.restart_offset(restart + 1)
+        } else {
+            self.entries_end
+        };
+
+        let mut offset = self.restart_offset(restart);
+        let mut prev = Vec::new();
+
+        while offset < end {
+            let (k, v, next) = self.decode_entry(offset, &prev)?;
+            if k.as_slice() == key {
+                return Ok(Some(v));
+            }
+            if k.as_slice() > key {
+                break;
+            }
+            prev = k;
+            offset = next;
+        }
+
+        Ok(None)
+    }
+
+    /// Decode all the entries.
+    pub(crate) fn decode_all<V: Value>(&self) -> Result<BTreeMap<Vec<u8>, V>, io::Error> {
+        let mut data = BTreeMap::new();
+
+        let mut offset = 0;
+        let mut prev = Vec::new();
+
+        while offset < self.entries_end {
+            let (k, v, next) = self.decode_entry(offset, &prev)?;
+            data.insert(k.clone(), v);
+            prev = k;
+            offset = next;
+        }
+
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::encode;
+    use super::shared_prefix_len;
+    use super::PrefixKeys;
+    use crate::v001::bincode_config::bincode_config;
+    use crate::v001::testing::bb;
+    use crate::v001::SeqMarked;
+
+    fn data(n: usize) -> BTreeMap<Vec<u8>, SeqMarked> {
+        (0..n)
+            .map(|i| {
+                let k = bb(format!("tenant/123/objects/{:04}", i));
+                (k, SeqMarked::new_normal(i as u64, bb(format!("v{}", i))))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_shared_prefix_len() {
+        assert_eq!(0, shared_prefix_len(b"", b"a"));
+        assert_eq!(2, shared_prefix_len(b"abc", b"abd"));
+        assert_eq!(3, shared_prefix_len(b"abc", b"abcd"));
+
+        // Keys are bytes: "é" is 0xC3 0xA9; "ê" is 0xC3 0xAA.
+        assert_eq!(2, shared_prefix_len("aé".as_bytes(), "aê".as_bytes()));
+    }
+
+    #[test]
+    fn test_prefix_keys_string_entry_compatible() -> anyhow::Result<()> {
+        // An entry written with a `String` suffix decodes as bytes.
+        let v = SeqMarked::new_normal(1, bb("v"));
+        let s = bincode::encode_to_vec((3u64, "bc", &v), bincode_config())?;
+        let b = bincode::encode_to_vec((3u64, b"bc".as_slice(), &v), bincode_config())?;
+        assert_eq!(s, b);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_keys_round_trip() -> anyhow::Result<()> {
+        for n in [0, 1, 3, 4, 5, 17] {
+            for restart_interval in [1, 2, 4, 16] {
+                let d = data(n);
+                let buf = encode(&d, restart_interval)?;
+                let pk = PrefixKeys::new(buf)?;
+
+                assert_eq!(restart_interval, pk.restart_interval());
+                assert_eq!(d, pk.decode_all()?);
+
+                for (k, v) in d.iter() {
+                    assert_eq!(Some(v.clone()), pk.get(k)?, "n={} key={:?}", n, k);
+
+                    let absent = [k.as_slice(), b"-"].concat();
+                    assert_eq!(None, pk.get::<SeqMarked>(&absent)?);
+                }
+
+                assert_eq!(None, pk.get::<SeqMarked>(b"")?);
+                assert_eq!(None, pk.get::<SeqMarked>(b"zzz")?);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_keys_smaller_than_full_keys() -> anyhow::Result<()> {
+        let d = data(64);
+        let full: usize = d.keys().map(|k| k.len()).sum();
+
+        let buf = encode(&d, 16)?;
+        assert!(buf.len() < full, "{} < {}", buf.len(), full);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix_keys_invalid_trailer() -> anyhow::Result<()> {
+        let buf = encode(&data(5), 2)?;
+        let len = buf.len();
+
+        assert!(PrefixKeys::new(buf[..4].to_vec()).is_err());
+
+        // Too many restart points
+        let mut b = buf.clone();
+        b[len - 1] = 100;
+        assert!(PrefixKeys::new(b).is_err());
+
+        // restart_interval is 0
+        let mut b = buf.clone();
+        b[len - 5] = 0;
+        assert!(PrefixKeys::new(b).is_err());
+
+        // The first restart point is not at 0
+        let mut b = buf.clone();
+        b[len - 8 - 3 * 4 + 3] = 1;
+        assert!(PrefixKeys::new(b).is_err());
+
+        Ok(())
+    }
+}
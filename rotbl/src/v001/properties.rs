@@ -0,0 +1,226 @@
+//! User-defined properties of a table, collected when it is built.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+use byteorder::BigEndian;
+use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
+use codeq::config::CodeqConfig;
+
+use crate::buf;
+use crate::typ::Type;
+use crate::v001::block_index::BlockIndexEntry;
+use crate::v001::header::Header;
+use crate::v001::key::KeyDisplay;
+use crate::v001::types::Checksum;
+use crate::v001::types::WithChecksum;
+use crate::v001::value::Value;
+use crate::v001::SeqMarked;
+use crate::version::Version;
+
+/// Collects properties of a table while it is built by a [`Builder`](crate::v001::Builder).
+///
+/// For example, the min and max of a field inside values, or the number of keys per tenant.
+/// A collector observes every key-value in key order and every block when it is written,
+/// and writes what it collected into the [`Properties`] of the table when it is committed.
+pub trait PropertyCollector<V = SeqMarked>
+where
+    Self: Send,
+    V: Value,
+{
+    /// Observe a key-value appended to the table.
+    fn add_kv(&mut self, key: &[u8], value: &V);
+
+    /// Observe a block after it is written, with all its key-values already added.
+    fn finish_block(&mut self, entry: &BlockIndexEntry) {
+        let _ = entry;
+    }
+
+    /// Write the collected properties.
+    ///
+    /// Properties written by collectors registered earlier are visible,
+    /// and one with the same name is replaced.
+    fn finish(&mut self, properties: &mut Properties);
+}
+
+/// Named byte values collected by [`PropertyCollector`]s, stored in the properties section.
+///
+/// Encoded data layout:
+/// ```text
+/// | Header
+/// | Data encoded size
+/// | n: u64
+/// | len: u32 | name | len: u32 | value
+/// | ... n entries
+/// | Checksum
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq, Eq)]
+pub struct Properties {
+    header: Header,
+
+    properties: BTreeMap<String, Vec<u8>>,
+}
+
+impl Default for Properties {
+    fn default() -> Self {
+        Self {
+            header: Header::new(Type::Properties, Version::V001),
+            properties: BTreeMap::new(),
+        }
+    }
+}
+
+impl fmt::Display for Properties {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (name, value)) in self.properties.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}: {}", name, KeyDisplay(value))?;
+        }
+        write!(f, "}}")
+    }
+}
+
+impl Properties {
+    pub fn insert(&mut self, name: impl ToString, value: impl Into<Vec<u8>>) {
+        self.properties.insert(name.to_string(), value.into());
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.properties.get(name).map(|v| v.as_slice())
+    }
+
+    pub fn len(&self) -> usize {
+        self.properties.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.properties.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Vec<u8>)> {
+        self.properties.iter()
+    }
+}
+
+impl codeq::Encode for Properties {
+    fn encode<W: Write>(&self, mut w: W) -> Result<usize, io::Error> {
+        let mut n = 0usize;
+
+        let mut data = Vec::new();
+        data.write_u64::<BigEndian>(self.properties.len() as u64)?;
+        for (name, value) in self.properties.iter() {
+            data.write_u32::<BigEndian>(name.len() as u32)?;
+            data.extend_from_slice(name.as_bytes());
+            data.write_u32::<BigEndian>(value.len() as u32)?;
+            data.extend_from_slice(value);
+        }
+
+        let mut cw = Checksum::new_writer(&mut w);
+
+        n += self.header.encode(&mut cw)?;
+        n += Checksum::wrap(data.len() as u64).encode(&mut cw)?;
+
+        cw.write_all(&data)?;
+        n += data.len();
+
+        n += cw.write_checksum()?;
+
+        Ok(n)
+    }
+}
+
+impl codeq::Decode for Properties {
+    fn decode<R: Read>(r: R) -> Result<Self, io::Error> {
+        let mut cr = Checksum::new_reader(r);
+
+        let header = Header::decode(&mut cr)?;
+        header.check(Type::Properties, &[Version::V001])?;
+
+        let data_size = WithChecksum::<u64>::decode(&mut cr)?.into_inner();
+
+        let mut data = buf::new_uninitialized(data_size as usize);
+        cr.read_exact(&mut data)?;
+
+        cr.verify_checksum(|| "Properties::decode()")?;
+
+        let mut r = data.as_slice();
+        let n = r.read_u64::<BigEndian>()?;
+
+        let mut properties = BTreeMap::new();
+        for _ in 0..n {
+            let name = read_bytes(&mut r)?;
+            let name = String::from_utf8(name)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            let value = read_bytes(&mut r)?;
+
+            properties.insert(name, value);
+        }
+
+        Ok(Self { header, properties })
+    }
+}
+
+/// Read a `u32` length followed by that many bytes.
+fn read_bytes(r: &mut &[u8]) -> Result<Vec<u8>, io::Error> {
+    let len = r.read_u32::<BigEndian>()? as usize;
+    if len > r.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("property length {} exceeds the data", len),
+        ));
+    }
+
+    let (b, rest) = r.split_at(len);
+    *r = rest;
+    Ok(b.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use codeq::testing::test_codec;
+
+    use crate::v001::properties::Properties;
+    use crate::v001::testing::vec_chain;
+
+    #[test]
+    fn test_properties_codec() -> anyhow::Result<()> {
+        let mut props = Properties::default();
+        props.insert("ab", b"xyz".to_vec());
+
+        let b = vec_chain([
+            vec![
+                112, 114, 111, 112, 115, 0, 0, 0, // header.type
+                0, 0, 0, 0, 0, 0, 0, 1, // header.version
+                0, 0, 0, 0, 217, 105, 183, 184, // header checksum
+                0, 0, 0, 0, 0, 0, 0, 21, // data size
+                0, 0, 0, 0, 8, 255, 59, 130, // data size checksum
+            ],
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 1, // number of properties
+                0, 0, 0, 2, 97, 98, // name: "ab"
+                0, 0, 0, 3, 120, 121, 122, // value: "xyz"
+            ],
+            vec![
+                0, 0, 0, 0, 195, 34, 48, 145, // checksum
+            ],
+        ]);
+
+        test_codec(b.as_slice(), &props)?;
+
+        assert_eq!(Some(b"xyz".as_slice()), props.get("ab"));
+        assert_eq!(None, props.get("a"));
+        assert_eq!("{ab: xyz}", props.to_string());
+
+        Ok(())
+    }
+}
@@ -12,17 +12,42 @@ pub struct AccessStat {
     read_block: AtomicU64,
     read_block_from_cache: AtomicU64,
     read_block_from_disk: AtomicU64,
+
+    /// Number of `get` the bloom filter says the key may be present.
+    #[serde(default)]
+    filter_positive: AtomicU64,
+
+    /// Number of `get` skipped because the bloom filter says the key is absent.
+    #[serde(default)]
+    filter_negative: AtomicU64,
+
+    /// Number of positive `get` whose key turns out to be absent.
+    #[serde(default)]
+    filter_false_positive: AtomicU64,
+
+    /// Number of blocks in a prefix scan the prefix filter says may contain the prefix.
+    #[serde(default)]
+    prefix_filter_positive: AtomicU64,
+
+    /// Number of blocks skipped in a prefix scan because the prefix filter rules them out.
+    #[serde(default)]
+    prefix_filter_negative: AtomicU64,
 }
 
 impl fmt::Display for AccessStat {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             f,
-            "read key: {}, read block: ({}, from cache: {}, from disk: {})",
+            "read key: {}, read block: ({}, from cache: {}, from disk: {}), filter: (positive: {}, negative: {}, false positive: {}), prefix filter: (positive: {}, negative: {})",
             format_num(self.read_key.load(Ordering::Relaxed)),
             format_num(self.read_block.load(Ordering::Relaxed)),
             format_num(self.read_block_from_cache.load(Ordering::Relaxed)),
             format_num(self.read_block_from_disk.load(Ordering::Relaxed)),
+            format_num(self.filter_positive.load(Ordering::Relaxed)),
+            format_num(self.filter_negative.load(Ordering::Relaxed)),
+            format_num(self.filter_false_positive.load(Ordering::Relaxed)),
+            format_num(self.prefix_filter_positive.load(Ordering::Relaxed)),
+            format_num(self.prefix_filter_negative.load(Ordering::Relaxed)),
         )
     }
 }
@@ -44,6 +69,26 @@ impl AccessStat {
         self.read_block_from_disk.load(Ordering::Relaxed)
     }
 
+    pub fn filter_positive(&self) -> u64 {
+        self.filter_positive.load(Ordering::Relaxed)
+    }
+
+    pub fn filter_negative(&self) -> u64 {
+        self.filter_negative.load(Ordering::Relaxed)
+    }
+
+    pub fn filter_false_positive(&self) -> u64 {
+        self.filter_false_positive.load(Ordering::Relaxed)
+    }
+
+    pub fn prefix_filter_positive(&self) -> u64 {
+        self.prefix_filter_positive.load(Ordering::Relaxed)
+    }
+
+    pub fn prefix_filter_negative(&self) -> u64 {
+        self.prefix_filter_negative.load(Ordering::Relaxed)
+    }
+
     pub fn hit_block(&self, from_cache: bool) {
         self.read_block.fetch_add(1, Ordering::Relaxed);
 
@@ -53,4 +98,24 @@ impl AccessStat {
             self.read_block_from_disk.fetch_add(1, Ordering::Relaxed);
         }
     }
+
+    pub fn hit_filter(&self, positive: bool) {
+        if positive {
+            self.filter_positive.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.filter_negative.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn hit_filter_false_positive(&self) {
+        self.filter_false_positive.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn hit_prefix_filter(&self, positive: bool) {
+        if positive {
+            self.prefix_filter_positive.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.prefix_filter_negative.fetch_add(1, Ordering::Relaxed);
+        }
+    }
 }
@@ -1,7 +1,5 @@
 use std::collections::BTreeMap;
 use std::io;
-use std::sync::Arc;
-use std::sync::Mutex;
 
 use codeq::config::CodeqConfig;
 use codeq::Encode;
@@ -11,11 +9,22 @@ use crate::storage::Storage;
 use crate::typ::Type;
 use crate::v001::block::Block;
 use crate::v001::block_index::BlockIndexEntry;
+use crate::v001::bloom::BloomFilter;
+use crate::v001::error::RotblError;
 use crate::v001::header::Header;
+use crate::v001::key::KeyDisplay;
+use crate::v001::prefix_bloom::PrefixBloomFilter;
+use crate::v001::properties::Properties;
+use crate::v001::properties::PropertyCollector;
 use crate::v001::rotbl::stat::RotblStat;
+use crate::v001::rotbl::TableReader;
+use crate::v001::sections::Sections;
 use crate::v001::types::Checksum;
 use crate::v001::types::Segment;
+use crate::v001::value::EncodedValue;
+use crate::v001::value::Value;
 use crate::v001::BlockIndex;
+use crate::v001::Compression;
 use crate::v001::Config;
 use crate::v001::Footer;
 use crate::v001::Rotbl;
@@ -24,8 +33,11 @@ use crate::v001::SeqMarked;
 use crate::v001::DB;
 use crate::version::Version;
 
-pub struct Builder<S>
-where S: Storage
+/// Build a table whose values are of type `V`, which is [`SeqMarked`] by default.
+pub struct Builder<S, V = SeqMarked>
+where
+    S: Storage,
+    V: Value,
 {
     config: Config,
 
@@ -33,16 +45,26 @@ where S: Storage
     header: Header,
     table_id: u32,
 
-    chunk_size: usize,
+    /// Cut a block at this many items, `None` for no limit.
+    chunk_size: Option<usize>,
+
+    /// Cut a block once the encoded size of its key-values reaches this many bytes.
+    target_size: Option<usize>,
 
     stat: RotblStat,
 
-    this_chunk: Vec<(String, SeqMarked)>,
+    this_chunk: Vec<(Vec<u8>, EncodedValue)>,
+
+    /// The encoded size of the key-values in `this_chunk`, if `target_size` is set.
+    this_chunk_size: usize,
 
     /// The previously added key.
     ///
     /// This is used to ensure that keys are added in strictly increasing order.
-    prev: Option<String>,
+    prev: Option<Vec<u8>>,
+
+    /// Whether all keys are UTF-8, which is required by the JSON block index.
+    utf8_keys: bool,
 
     /// The storage where the rotbl files are stored.
     ///
@@ -59,47 +81,140 @@ where S: Storage
 
     /// The block indexes accumulated during appending keys.
     index: Vec<BlockIndexEntry>,
+
+    /// The hashes of all keys to build a bloom filter, if it is enabled.
+    key_hashes: Option<Vec<u64>>,
+
+    /// The prefix filters of the blocks written so far, if it is enabled.
+    prefix_bloom_filter: Option<PrefixBloomFilter>,
+
+    /// The user-defined collectors of the table properties.
+    property_collectors: Vec<Box<dyn PropertyCollector<V>>>,
 }
 
-impl<S> Builder<S>
-where S: Storage
+impl<S, V> Builder<S, V>
+where
+    S: Storage,
+    V: Value,
 {
-    pub fn new(mut storage: S, config: Config, rel_path: &str) -> Result<Self, io::Error> {
+    pub fn new(mut storage: S, config: Config, rel_path: &str) -> Result<Self, RotblError> {
         // Table id is not supported yet in this version,
         // and is always 0.
         let table_id = 0;
 
         let f = storage.writer(rel_path)?;
 
-        let chunk_size = config.block_config.max_items();
-        if chunk_size == 0 {
-            return Err(io::Error::new(
-                io::ErrorKind::InvalidInput,
+        let chunk_size = config.block_config.item_limit();
+        if chunk_size == Some(0) {
+            return Err(RotblError::invalid_argument(
                 "BlockConfig.max_items must be greater than 0",
             ));
         }
 
-        let mut builder = Self {
+        let target_size = config.block_config.target_size;
+        if target_size == Some(0) {
+            return Err(RotblError::invalid_argument(
+                "BlockConfig.target_size must be greater than 0",
+            ));
+        }
+
+        if config.block_config.restart_interval == Some(0) {
+            return Err(RotblError::invalid_argument(
+                "BlockConfig.restart_interval must be greater than 0",
+            ));
+        }
+
+        if config.block_config.packed() && config.block_config.restart_interval.is_some() {
+            return Err(RotblError::invalid_argument(
+                "BlockConfig.packed can not be used with BlockConfig.restart_interval",
+            ));
+        }
+
+        if config.block_index.partition_size == Some(0) {
+            return Err(RotblError::invalid_argument(
+                "BlockIndexConfig.partition_size must be greater than 0",
+            ));
+        }
+
+        if config.bloom_filter.bits_per_key == Some(0) {
+            return Err(RotblError::invalid_argument(
+                "BloomFilterConfig.bits_per_key must be greater than 0",
+            ));
+        }
+
+        if let Some(extractor) = &config.bloom_filter.prefix_extractor {
+            extractor.validate()?;
+        }
+
+        let key_hashes = config.bloom_filter.bits_per_key.map(|_| Vec::new());
+        let prefix_bloom_filter = config.bloom_filter.prefix_extractor.map(PrefixBloomFilter::new);
+
+        // A table with optional sections has a footer referencing them,
+        // which can only be read by a V002 reader.
+        let version = if key_hashes.is_some() || prefix_bloom_filter.is_some() {
+            Version::V002
+        } else {
+            Version::V001
+        };
+
+        let builder = Self {
             config,
             offset: 0,
-            header: Header::new(Type::Rotbl, Version::V001),
+            header: Header::new(Type::Rotbl, version),
             table_id,
             chunk_size,
+            target_size,
             stat: RotblStat::default(),
-            this_chunk: Vec::with_capacity(chunk_size),
+            this_chunk: Vec::with_capacity(chunk_size.unwrap_or_default()),
+            this_chunk_size: 0,
             prev: None,
+            utf8_keys: true,
             storage,
             rel_path: rel_path.to_string(),
             writer: f,
             index: Vec::new(),
+            key_hashes,
+            prefix_bloom_filter,
+            property_collectors: Vec::new(),
         };
 
-        builder.offset += builder.header.encode(&mut builder.writer)?;
+        Ok(builder)
+    }
+
+    /// Register a collector of the table properties.
+    ///
+    /// Collectors must be registered before any key-value is appended.
+    /// The collected [`Properties`] are read with [`Rotbl::properties()`].
+    pub fn add_property_collector(
+        &mut self,
+        collector: impl PropertyCollector<V> + 'static,
+    ) -> Result<(), RotblError> {
+        if self.stat.key_num > 0 {
+            return Err(RotblError::invalid_argument(
+                "property collectors must be added before appending key-values",
+            ));
+        }
 
-        let tid = Checksum::wrap(builder.table_id);
-        builder.offset += tid.encode(&mut builder.writer)?;
+        // The properties are stored in an optional section.
+        self.header = Header::new(Type::Rotbl, Version::V002);
+        self.property_collectors.push(Box::new(collector));
+        Ok(())
+    }
 
-        Ok(builder)
+    /// Write the header and the table id, before the first block.
+    ///
+    /// It is deferred until the version is decided by the registered property collectors.
+    fn write_header(&mut self) -> Result<(), io::Error> {
+        if self.offset > 0 {
+            return Ok(());
+        }
+
+        self.offset += self.header.encode(&mut self.writer)?;
+
+        let tid = Checksum::wrap(self.table_id);
+        self.offset += tid.encode(&mut self.writer)?;
+
+        Ok(())
     }
 
     pub fn rel_path(&self) -> &str {
@@ -114,26 +229,63 @@ where S: Storage
         &self.storage
     }
 
-    pub fn append_kv(&mut self, k: impl ToString, v: SeqMarked) -> Result<(), io::Error> {
-        let k = k.to_string();
+    /// Append a key-value. Keys are bytes and must be appended in strictly increasing order.
+    ///
+    /// With [`Config::debug_check()`] enabled, a key out of order is an
+    /// [`RotblError::InvalidArgument`] error.
+    pub fn append_kv(&mut self, k: impl AsRef<[u8]>, v: V) -> Result<(), RotblError> {
+        let k = k.as_ref().to_vec();
 
         if self.config.debug_check() {
-            assert!(
-                Some(&k) > self.prev.as_ref(),
-                "this key {:?} must be greater than prev {:?}",
-                k,
-                self.prev
-            );
+            if Some(&k) <= self.prev.as_ref() {
+                return Err(RotblError::invalid_argument(format!(
+                    "this key {} must be greater than prev {:?}",
+                    KeyDisplay(&k),
+                    self.prev.as_ref().map(|p| KeyDisplay(p).to_string())
+                )));
+            }
 
             self.prev = Some(k.clone());
         }
 
+        if self.utf8_keys && std::str::from_utf8(&k).is_err() {
+            self.utf8_keys = false;
+        }
+
+        if let Some(hashes) = &mut self.key_hashes {
+            hashes.push(BloomFilter::hash(&k));
+        }
+
+        for c in self.property_collectors.iter_mut() {
+            c.add_kv(&k, &v);
+        }
+
+        let value =
+            EncodedValue::new(&v).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let value_size = value.len();
+
+        if self.target_size.is_some() {
+            self.this_chunk_size += k.len() + value_size;
+        }
+
+        self.stat.add_kv_size(k.len() as u64, value_size as u64);
+
+        if let Some((seq, tombstone)) = v.seq_stat() {
+            self.stat.add_seq(seq, tombstone);
+        }
+
         self.stat.key_num += 1;
-        self.this_chunk.push((k, v));
+        self.this_chunk.push((k, value));
 
-        if self.this_chunk.len() == self.chunk_size {
-            let chunk =
-                std::mem::replace(&mut self.this_chunk, Vec::with_capacity(self.chunk_size));
+        let full = Some(self.this_chunk.len()) == self.chunk_size
+            || self.target_size.is_some_and(|t| self.this_chunk_size >= t);
+
+        if full {
+            let chunk = std::mem::replace(
+                &mut self.this_chunk,
+                Vec::with_capacity(self.chunk_size.unwrap_or_default()),
+            );
+            self.this_chunk_size = 0;
 
             self.write_chunk(chunk)?;
         }
@@ -143,20 +295,40 @@ where S: Storage
 
     fn write_chunk(
         &mut self,
-        chunk: impl IntoIterator<Item = (String, SeqMarked)>,
+        chunk: impl IntoIterator<Item = (Vec<u8>, EncodedValue)>,
     ) -> Result<(), io::Error> {
+        self.write_header()?;
+
         let bt: BTreeMap<_, _> = chunk.into_iter().collect();
 
-        let first_key: String = bt.first_key_value().unwrap().0.clone();
-        let last_key: String = bt.last_key_value().unwrap().0.clone();
+        if let Some(f) = &mut self.prefix_bloom_filter {
+            f.push_block(
+                bt.keys().map(|k| k.as_slice()),
+                self.config.bloom_filter.bits_per_key(),
+            );
+        }
 
-        let block = Block::new(self.stat.block_num, bt);
+        let first_key = bt.first_key_value().unwrap().0.clone();
+        let last_key = bt.last_key_value().unwrap().0.clone();
+
+        let compression = self.config.block_config.compression();
+        let mut block = Block::new(self.stat.block_num, bt).with_compression(compression);
+        if let Some(restart_interval) = self.config.block_config.restart_interval {
+            block = block.with_restart_interval(restart_interval);
+        }
+        if self.config.block_config.packed() {
+            block = block.with_packed();
+        }
 
         let block_offset = self.offset as u64;
-        let block_size = block.encode(&mut self.writer)?;
+        let (block_size, block_meta) = block.encode_new_with_meta(&mut self.writer)?;
         self.offset += block_size;
         self.stat.data_size += block_size as u64;
 
+        if compression != Compression::None {
+            *self.stat.raw_data_size.get_or_insert(0) += block_meta.raw_size();
+        }
+
         let index_entry = BlockIndexEntry {
             block_num: self.stat.block_num,
             offset: block_offset,
@@ -165,25 +337,80 @@ where S: Storage
             last_key,
         };
 
+        for c in self.property_collectors.iter_mut() {
+            c.finish_block(&index_entry);
+        }
+
         self.index.push(index_entry);
         self.stat.block_num += 1;
 
         Ok(())
     }
 
-    pub fn commit(mut self, rotbl_meta: RotblMeta) -> Result<Rotbl, io::Error> {
+    /// Write the block index entries in partitions of `partition_size` entries,
+    /// and return the top-level index of the partitions.
+    fn write_index_partitions(&mut self, partition_size: usize) -> Result<BlockIndex, io::Error> {
+        let index = std::mem::take(&mut self.index);
+        let mut partitions = Vec::new();
+
+        for entries in index.chunks(partition_size) {
+            let first = &entries[0];
+            let last = &entries[entries.len() - 1];
+
+            let partition = BlockIndex::new(entries.to_vec()).with_binary_encoding();
+            let size = partition.encode(&mut self.writer)?;
+
+            partitions.push(BlockIndexEntry {
+                block_num: first.block_num,
+                offset: self.offset as u64,
+                size: size as u64,
+                first_key: first.first_key.clone(),
+                last_key: last.last_key.clone(),
+            });
+
+            self.offset += size;
+            self.stat.index_size += size as u64;
+        }
+
+        Ok(BlockIndex::new(partitions).with_partitions())
+    }
+
+    /// Discard the table being built and remove all data written so far.
+    ///
+    /// Dropping a builder without committing has the same effect,
+    /// but errors are ignored.
+    pub fn abort(mut self) -> Result<(), RotblError> {
+        self.writer.abort()?;
+        Ok(())
+    }
+
+    pub fn commit(mut self, rotbl_meta: RotblMeta) -> Result<Rotbl<V>, RotblError> {
         if !self.this_chunk.is_empty() {
             let chunk = std::mem::take(&mut self.this_chunk);
             self.write_chunk(chunk)?;
         }
 
+        // An empty table has no block
+        self.write_header()?;
+
         // Write block index
 
-        let block_index = BlockIndex::new(self.index);
-        self.stat.index_size = block_index.encode(&mut self.writer)? as u64;
+        let block_index = if let Some(partition_size) = self.config.block_index.partition_size {
+            self.write_index_partitions(partition_size)?
+        } else {
+            // The JSON block index can not store a non-UTF-8 key.
+            let mut block_index = BlockIndex::new(std::mem::take(&mut self.index));
+            if self.config.block_index.binary() || !self.utf8_keys {
+                block_index = block_index.with_binary_encoding();
+            }
+            block_index
+        };
+
+        let block_index_size = block_index.encode(&mut self.writer)? as u64;
+        self.stat.index_size += block_index_size;
 
-        let blog_index_seg = Segment::new(self.offset as u64, self.stat.index_size);
-        self.offset += self.stat.index_size as usize;
+        let blog_index_seg = Segment::new(self.offset as u64, block_index_size);
+        self.offset += block_index_size as usize;
 
         // Write Meta
 
@@ -197,20 +424,78 @@ where S: Storage
         let stat_seg = Segment::new(self.offset as u64, stat_size as u64);
         self.offset += stat_size;
 
+        // Write optional sections
+
+        let mut sections = Sections::default();
+
+        let bloom_filter = if let Some(hashes) = self.key_hashes.take() {
+            let filter = BloomFilter::build(&hashes, self.config.bloom_filter.bits_per_key());
+
+            let filter_size = filter.encode(&mut self.writer)?;
+            sections.insert(
+                Sections::BLOOM_FILTER,
+                Segment::new(self.offset as u64, filter_size as u64),
+            );
+            self.offset += filter_size;
+
+            Some(filter)
+        } else {
+            None
+        };
+
+        let prefix_bloom_filter = if let Some(filter) = self.prefix_bloom_filter.take() {
+            let filter_size = filter.encode(&mut self.writer)?;
+            sections.insert(
+                Sections::PREFIX_BLOOM_FILTER,
+                Segment::new(self.offset as u64, filter_size as u64),
+            );
+            self.offset += filter_size;
+
+            Some(filter)
+        } else {
+            None
+        };
+
+        let properties = if !self.property_collectors.is_empty() {
+            let mut properties = Properties::default();
+            for c in self.property_collectors.iter_mut() {
+                c.finish(&mut properties);
+            }
+
+            let properties_size = properties.encode(&mut self.writer)?;
+            sections.insert(
+                Sections::PROPERTIES,
+                Segment::new(self.offset as u64, properties_size as u64),
+            );
+            self.offset += properties_size;
+
+            Some(properties)
+        } else {
+            None
+        };
+
+        let mut footer = Footer::new(blog_index_seg, meta_seg, stat_seg);
+
+        if self.header.version() != Version::V001 {
+            let sections_size = sections.encode(&mut self.writer)?;
+            footer = footer.with_sections(Segment::new(self.offset as u64, sections_size as u64));
+            self.offset += sections_size;
+        }
+
         // Write footer
 
-        let footer = Footer::new(blog_index_seg, meta_seg, stat_seg);
         self.offset += footer.encode(&mut self.writer)?;
 
         self.writer.commit()?;
 
-        let reader = self.storage.reader(&self.rel_path)?;
+        let reader = self.storage.positional_reader(&self.rel_path)?;
 
         let block_cache = DB::new_cache(self.config.clone());
 
         let r = Rotbl {
             block_cache,
-            file: Arc::new(Mutex::new(reader)),
+            rel_path: self.rel_path,
+            file: TableReader::Sync(reader),
             file_size: self.offset as u64,
             header: self.header,
             table_id: self.table_id,
@@ -219,6 +504,10 @@ where S: Storage
             stat: self.stat,
             access_stat: Default::default(),
             footer,
+            sections,
+            bloom_filter,
+            prefix_bloom_filter,
+            properties,
         };
 
         Ok(r)
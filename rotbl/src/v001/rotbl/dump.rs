@@ -1,23 +1,24 @@
 //! Export rotbl data
 
-use std::io;
 use std::ops::Coroutine;
 use std::ops::CoroutineState;
 use std::pin::Pin;
 use std::sync::Arc;
 
+use crate::v001::key::KeyDisplay;
 use crate::v001::Rotbl;
+use crate::v001::RotblError;
 
 pub struct Dump {
     rotbl: Arc<Rotbl>,
 }
 
 pub struct DumpIter {
-    coro: Pin<Box<dyn Coroutine<Yield = String, Return = Result<(), io::Error>>>>,
+    coro: Pin<Box<dyn Coroutine<Yield = String, Return = Result<(), RotblError>>>>,
 }
 
 impl Iterator for DumpIter {
-    type Item = Result<String, io::Error>;
+    type Item = Result<String, RotblError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self.coro.as_mut().resume(()) {
@@ -36,13 +37,13 @@ impl Dump {
     }
 
     /// Dump rotbl information to human readable lines in an iterator.
-    pub fn dump(self) -> impl Iterator<Item = Result<String, io::Error>> {
+    pub fn dump(self) -> impl Iterator<Item = Result<String, RotblError>> {
         let c = self.dump_coro();
         DumpIter { coro: Box::pin(c) }
     }
 
     /// Dump rotbl information to human readable lines. Return a coroutine.
-    pub fn dump_coro(self) -> impl Coroutine<Yield = String, Return = Result<(), io::Error>> {
+    pub fn dump_coro(self) -> impl Coroutine<Yield = String, Return = Result<(), RotblError>> {
         #[coroutine]
         move || {
             yield "Rotbl:".to_string();
@@ -50,11 +51,20 @@ impl Dump {
             yield format!("    file_size: {}", self.rotbl.file_size());
             yield format!("    meta: {}", self.rotbl.meta());
             yield format!("    stat: {}", self.rotbl.stat());
+            if let Some(h) = &self.rotbl.stat().key_size_histogram {
+                yield format!("    key_size_histogram: {}", h);
+            }
+            if let Some(h) = &self.rotbl.stat().value_size_histogram {
+                yield format!("    value_size_histogram: {}", h);
+            }
             yield format!("    access_stat: {:?}", self.rotbl.access_stat());
+            if let Some(properties) = self.rotbl.properties() {
+                yield format!("    properties: {}", properties);
+            }
 
             // Block index
 
-            let bi = self.rotbl.block_index.iter_index_entries().cloned().collect::<Vec<_>>();
+            let bi = self.rotbl.index_entries()?;
             yield format!("BlockIndex: n: {}", bi.len());
             for ent in bi.into_iter() {
                 yield format!("    index: {}", ent);
@@ -65,12 +75,17 @@ impl Dump {
             for block_num in 0..self.rotbl.stat.block_num {
                 let block = self.rotbl.load_block(block_num)?;
                 let kvs = block
-                    .range::<String, _>(..)
+                    .range::<Vec<u8>, _>(..)?
                     .map(|(k, v)| (k.clone(), v.clone()))
                     .collect::<Vec<_>>();
 
                 for (k, v) in kvs {
-                    yield format!("Block-{:>04}: {}: {}", block_num, k, v.display_with_debug());
+                    yield format!(
+                        "Block-{:>04}: {}: {}",
+                        block_num,
+                        KeyDisplay(&k),
+                        v.display_with_debug()
+                    );
                 }
             }
             Ok(())
@@ -4,37 +4,127 @@ pub mod dump;
 pub mod stat;
 
 use std::io;
-use std::io::Read;
-use std::io::Seek;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+use bytes::Bytes;
 use codeq::Decode;
 use codeq::FixedSize;
 use futures::stream::BoxStream;
+use futures::StreamExt;
 use log::debug;
 
-use crate::buf::new_uninitialized;
 use crate::io_util;
-use crate::storage::BoxReader;
+use crate::storage::ArcReadAt;
+use crate::storage::AsyncStorage;
+use crate::storage::BoxAsyncReader;
 use crate::storage::Storage;
 use crate::typ::Type;
 use crate::v001::block::Block;
 use crate::v001::block_cache::BlockCache;
+use crate::v001::block_cache::CachedBlock;
 use crate::v001::block_id::BlockId;
 use crate::v001::block_index::BlockIndex;
+use crate::v001::block_index::BlockIndexEntry;
+use crate::v001::bloom::BloomFilter;
 use crate::v001::db::DB;
+use crate::v001::error::Location;
+use crate::v001::error::RotblError;
 use crate::v001::footer::Footer;
 use crate::v001::header::Header;
+use crate::v001::key;
+use crate::v001::prefix_bloom::PrefixBloomFilter;
+use crate::v001::prefix_extractor::prefix_range;
+use crate::v001::properties::Properties;
 use crate::v001::range::RangeArg;
 use crate::v001::rotbl::access_stat::AccessStat;
 use crate::v001::rotbl_meta::RotblMeta;
+use crate::v001::sections::Sections;
+use crate::v001::types::Segment;
 use crate::v001::types::WithChecksum;
+use crate::v001::value::Value;
 use crate::v001::CacheStat;
 use crate::v001::Config;
 use crate::v001::SeqMarked;
 use crate::version::Version;
 
+/// Decode a section of a table, reporting a decode error as a corruption at `loc`.
+fn decode_section<T: Decode>(loc: &Location, buf: &[u8]) -> Result<T, RotblError> {
+    T::decode(buf).map_err(|e| loc.decode_error(e))
+}
+
+/// The underlying reader of a [`Rotbl`], opened with either a sync or an async storage.
+#[derive(Debug)]
+pub(crate) enum TableReader {
+    /// A positional reader opened with [`Storage`].
+    ///
+    /// It does not need a lock, thus blocks can be loaded concurrently.
+    Sync(ArcReadAt),
+
+    /// A reader opened with [`AsyncStorage`].
+    Async(BoxAsyncReader),
+}
+
+impl TableReader {
+    /// Read the bytes of a section at `loc`, which must be within `file_size`.
+    ///
+    /// A sync reader reads in place, the returned future is ready when it is first polled.
+    async fn read_segment(&self, loc: &Location<'_>, file_size: u64) -> Result<Bytes, RotblError> {
+        match self {
+            Self::Sync(f) => io_util::read_segment(f.as_ref(), loc, file_size),
+            Self::Async(f) => io_util::read_segment_async(f.as_ref(), loc, file_size).await,
+        }
+    }
+
+    /// Read the bytes of a section at `loc` without blocking the runtime thread.
+    ///
+    /// A sync reader reads in a blocking thread.
+    async fn read_segment_nonblocking(
+        &self,
+        loc: &Location<'_>,
+        file_size: u64,
+    ) -> Result<Bytes, RotblError> {
+        match self {
+            Self::Sync(f) => io_util::read_segment_blocking(f.clone(), loc, file_size).await,
+            Self::Async(f) => io_util::read_segment_async(f.as_ref(), loc, file_size).await,
+        }
+    }
+
+    async fn size(&self) -> Result<u64, RotblError> {
+        let size = match self {
+            Self::Sync(f) => f.size()?,
+            Self::Async(f) => f.size().await?,
+        };
+        Ok(size)
+    }
+
+    /// Read and decode a section at `loc`.
+    async fn load_section<T: Decode>(
+        &self,
+        loc: &Location<'_>,
+        file_size: u64,
+    ) -> Result<T, RotblError> {
+        let buf = self.read_segment(loc, file_size).await?;
+        decode_section(loc, &buf)
+    }
+
+    /// Read and decode the optional section `name`, or return `None` if the table does not have it.
+    async fn load_optional_section<T: Decode>(
+        &self,
+        rel_path: &str,
+        file_size: u64,
+        sections: &Sections,
+        name: &str,
+    ) -> Result<Option<T>, RotblError> {
+        let Some(seg) = sections.get(name) else {
+            return Ok(None);
+        };
+
+        let loc = Location::new(rel_path, name, seg);
+        Ok(Some(self.load_section(&loc, file_size).await?))
+    }
+}
+
 /// A readonly table.
 ///
 /// The table is organized as follows, and every part has its own checksum embedded:
@@ -42,20 +132,36 @@ use crate::version::Version;
 /// ```text
 /// | Header
 /// | TableId with checksum
-/// | Meta
 /// | Block 0
 /// | Block 1
 /// | ...
+/// | BlockIndex partitions     // Only with a partitioned index
 /// | BlockIndex
+/// | Meta
 /// | Stat
+/// | BloomFilter               // Optional sections, V002 only
+/// | PrefixBloomFilter
+/// | Properties
+/// | Sections                  // V002 only, locating the optional sections
 /// | Footer
 /// ```
+///
+/// A V001 table has none of the optional sections, and its footer locates only the
+/// block index, meta and stat. A V002 footer also locates the [`Sections`] table.
+///
+/// Values are of type `V`, which is [`SeqMarked`] by default.
+/// A table must be opened with the same value type as it is built with.
 #[derive(Debug)]
-pub struct Rotbl {
+pub struct Rotbl<V = SeqMarked>
+where V: Value
+{
     /// The db this table belongs
-    block_cache: Arc<Mutex<BlockCache>>,
+    block_cache: Arc<Mutex<BlockCache<V>>>,
+
+    /// The key of the table file in the storage, to locate a corruption.
+    rel_path: String,
 
-    file: Arc<Mutex<BoxReader>>,
+    file: TableReader,
 
     /// On disk file size in bytes
     file_size: u64,
@@ -75,17 +181,33 @@ pub struct Rotbl {
 
     #[allow(dead_code)]
     footer: Footer,
+
+    /// The optional sections, empty for a V001 table.
+    sections: Sections,
+
+    /// The filter of all keys, if the table is built with one.
+    bloom_filter: Option<BloomFilter>,
+
+    /// The filters of key prefixes in every block, if the table is built with them.
+    prefix_bloom_filter: Option<PrefixBloomFilter>,
+
+    /// The user-defined properties, if the table is built with property collectors.
+    properties: Option<Properties>,
 }
 
-impl Rotbl {
+impl<V> Rotbl<V>
+where V: Value
+{
     /// Create a new table from a series of key-value pairs
-    pub fn create_table<S: Storage>(
+    ///
+    /// Keys can be `String`, `Vec<u8>` or any other bytes, in strictly increasing order.
+    pub fn create_table<S: Storage, K: AsRef<[u8]>>(
         storage: S,
         config: Config,
         path: &str,
         meta: RotblMeta,
-        kvs: impl IntoIterator<Item = (String, SeqMarked)>,
-    ) -> Result<Rotbl, io::Error> {
+        kvs: impl IntoIterator<Item = (K, V)>,
+    ) -> Result<Self, RotblError> {
         let mut builder = builder::Builder::new(storage, config, path)?;
         for (k, v) in kvs {
             builder.append_kv(k, v)?;
@@ -99,53 +221,131 @@ impl Rotbl {
         mut storage: S,
         config: Config,
         rel_path: &str,
-    ) -> Result<Self, io::Error> {
-        let mut f = storage.reader(rel_path)?;
+    ) -> Result<Self, RotblError> {
+        let f = storage.positional_reader(rel_path)?;
 
-        let header = {
-            let header = Header::decode(&mut f)?;
-            assert_eq!(header, Header::new(Type::Rotbl, Version::V001));
-            header
-        };
+        // Reading from a sync reader never waits, thus it does not block on anything else.
+        futures::executor::block_on(Self::open_reader(TableReader::Sync(f), config, rel_path))
+    }
+
+    /// Open a table with an [`AsyncStorage`].
+    ///
+    /// Unlike [`Rotbl::open()`], all the IO are done asynchronously,
+    /// and blocks are loaded without blocking the runtime thread.
+    pub async fn open_async<S: AsyncStorage>(
+        mut storage: S,
+        config: Config,
+        rel_path: &str,
+    ) -> Result<Self, RotblError> {
+        let f = storage.async_reader(rel_path).await?;
+        Self::open_reader(TableReader::Async(f), config, rel_path).await
+    }
+
+    /// Load the parts of a table that are kept in memory, from either a sync or an async reader.
+    async fn open_reader(
+        file: TableReader,
+        config: Config,
+        rel_path: &str,
+    ) -> Result<Self, RotblError> {
+        let file_size = file.size().await?;
+
+        let (header, table_id) = {
+            let size = Header::encoded_size() + WithChecksum::<u32>::encoded_size();
+            let loc = Location::new(rel_path, "header", Segment::new(0, size as u64));
+            let buf = file.read_segment(&loc, file_size).await?;
+            let mut r = buf.as_ref();
 
-        let table_id = WithChecksum::<u32>::decode(&mut f)?.into_inner();
+            let header = Header::decode(&mut r).map_err(|e| loc.decode_error(e))?;
+            header.check(Type::Rotbl, &[Version::V001, Version::V002])?;
 
-        let footer_offset = f.seek(io::SeekFrom::End(-(Footer::encoded_size() as i64)))?;
-        let footer = Footer::decode(&mut f)?;
+            let table_id =
+                WithChecksum::<u32>::decode(&mut r).map_err(|e| loc.decode_error(e))?.into_inner();
+            (header, table_id)
+        };
+
+        let footer = {
+            let size = Footer::encoded_size(header.version()) as u64;
+            let offset = file_size.checked_sub(size).ok_or_else(|| {
+                let loc = Location::new(rel_path, "footer", Segment::new(0, file_size));
+                loc.corruption(format!(
+                    "file size {} is smaller than footer size {}",
+                    file_size, size
+                ))
+            })?;
+            let loc = Location::new(rel_path, "footer", Segment::new(offset, size));
+            let buf = file.read_segment(&loc, file_size).await?;
+            Footer::decode_version(header.version(), buf.as_ref())
+                .map_err(|e| loc.decode_error(e))?
+        };
 
         let block_index = {
-            let buf = io_util::read_segment(&mut f, footer.block_index_segment)?;
-            BlockIndex::decode(&mut buf.as_slice())?
+            let loc = Location::new(rel_path, "block_index", footer.block_index_segment);
+            file.load_section(&loc, file_size).await?
         };
 
         let meta = {
-            let buf = io_util::read_segment(&mut f, footer.meta_segment)?;
-            RotblMeta::decode(&mut buf.as_slice())?
+            let loc = Location::new(rel_path, "meta", footer.meta_segment);
+            file.load_section(&loc, file_size).await?
         };
 
         let stat = {
-            let buf = io_util::read_segment(&mut f, footer.stat_segment)?;
-            stat::RotblStat::decode(&mut buf.as_slice())?
+            let loc = Location::new(rel_path, "stat", footer.stat_segment);
+            file.load_section(&loc, file_size).await?
         };
 
+        let sections = match footer.sections_segment {
+            Some(seg) => {
+                let loc = Location::new(rel_path, "sections", seg);
+                file.load_section(&loc, file_size).await?
+            }
+            None => Sections::default(),
+        };
+
+        let bloom_filter = file
+            .load_optional_section(rel_path, file_size, &sections, Sections::BLOOM_FILTER)
+            .await?;
+
+        let prefix_bloom_filter = file
+            .load_optional_section(
+                rel_path,
+                file_size,
+                &sections,
+                Sections::PREFIX_BLOOM_FILTER,
+            )
+            .await?;
+
+        let properties = file
+            .load_optional_section(rel_path, file_size, &sections, Sections::PROPERTIES)
+            .await?;
+
         let cache = DB::new_cache(config.clone());
 
         let r = Self {
             block_cache: cache,
+            rel_path: rel_path.to_string(),
             table_id,
             header,
-            file: Arc::new(Mutex::new(f)),
-            file_size: footer_offset + Footer::encoded_size() as u64,
+            file,
+            file_size,
             meta,
             block_index,
             stat,
             access_stat: Default::default(),
             footer,
+            sections,
+            bloom_filter,
+            prefix_bloom_filter,
+            properties,
         };
 
         Ok(r)
     }
 
+    /// The key of the table file in the storage.
+    pub fn rel_path(&self) -> &str {
+        &self.rel_path
+    }
+
     pub fn header(&self) -> &Header {
         &self.header
     }
@@ -162,6 +362,10 @@ impl Rotbl {
         &self.meta
     }
 
+    /// The block index loaded when opening the table.
+    ///
+    /// For a partitioned index, it is the top-level index of the partitions,
+    /// and the partitions are loaded through the block cache when accessed.
     pub fn block_index(&self) -> &BlockIndex {
         &self.block_index
     }
@@ -174,6 +378,54 @@ impl Rotbl {
         &self.footer
     }
 
+    pub fn sections(&self) -> &Sections {
+        &self.sections
+    }
+
+    pub fn bloom_filter(&self) -> Option<&BloomFilter> {
+        self.bloom_filter.as_ref()
+    }
+
+    pub fn prefix_bloom_filter(&self) -> Option<&PrefixBloomFilter> {
+        self.prefix_bloom_filter.as_ref()
+    }
+
+    /// Return `false` if the table definitely contains no key starting with `prefix`.
+    ///
+    /// It is answered without reading any block, so that a prefix scan over many tables
+    /// can skip those without the prefix.
+    /// Without a prefix filter, or if `prefix` is not a whole prefix
+    /// produced by the extractor the table is built with, it returns `true`.
+    pub fn may_contain_prefix(&self, prefix: impl AsRef<[u8]>) -> bool {
+        let prefix = prefix.as_ref();
+
+        let Some(filter) = &self.prefix_bloom_filter else {
+            return true;
+        };
+
+        if filter.extractor().extract(prefix) != Some(prefix) {
+            return true;
+        }
+
+        let range = prefix_range(prefix);
+
+        if self.block_index.is_partitioned() {
+            // Without loading partitions, check every block in the partitions of the range.
+            let positions = self.block_index.lookup_range_positions(range);
+            let mut block_nums = self.partition_block_nums(positions);
+            return block_nums.any(|block_num| filter.may_contain(block_num, prefix));
+        }
+
+        let block_metas = self.block_index.lookup_range(range);
+        block_metas.iter().any(|m| filter.may_contain(m.block_num, prefix))
+    }
+
+    /// The properties collected by the [`PropertyCollector`](crate::v001::PropertyCollector)s
+    /// registered when building the table, or `None` if there is none.
+    pub fn properties(&self) -> Option<&Properties> {
+        self.properties.as_ref()
+    }
+
     pub fn access_stat(&self) -> &AccessStat {
         &self.access_stat
     }
@@ -184,12 +436,15 @@ impl Rotbl {
     }
 
     /// Return the block if it is in the cache.
-    pub fn get_block(&self, block_num: u32) -> Option<Arc<Block>> {
+    pub fn get_block(&self, block_num: u32) -> Option<Arc<Block<V>>> {
         let block_id = BlockId::new(self.table_id, block_num);
 
         let b = {
             let mut c = self.block_cache.lock().unwrap();
-            c.get(&block_id).cloned()
+            match c.get(&block_id) {
+                Some(CachedBlock::Data(b)) => Some(b.clone()),
+                _ => None,
+            }
         };
 
         if b.is_some() {
@@ -203,48 +458,266 @@ impl Rotbl {
     ///
     /// If the block is already in the cache, it will be returned immediately.
     ///
-    /// If hold a lock to the cache while loading the block.
-    pub fn load_block(&self, block_num: u32) -> Result<Arc<Block>, io::Error> {
+    /// The cache is not locked while loading the block,
+    /// so that different blocks can be loaded concurrently.
+    /// Concurrent misses on the same block may load it more than once.
+    ///
+    /// A table opened with [`Rotbl::open_async()`] does not support loading block synchronously,
+    /// use [`Rotbl::load_block_async()`] instead.
+    pub fn load_block(&self, block_num: u32) -> Result<Arc<Block<V>>, RotblError> {
         debug!("load_block start: {}", block_num);
-        let block_id = BlockId::new(self.table_id, block_num);
-
-        // Hold the lock until the block is loaded.
-        let mut cache = self.block_cache.lock().unwrap();
-        if let Some(b) = cache.get(&block_id).cloned() {
-            self.access_stat.hit_block(true);
+        if let Some(b) = self.get_block(block_num) {
             return Ok(b);
         }
 
         let block = self.load_block_nocache(block_num)?;
 
-        cache.insert(block_id, block.clone());
+        {
+            let block_id = BlockId::new(self.table_id, block_num);
+            let mut cache = self.block_cache.lock().unwrap();
+            cache.insert(block_id, CachedBlock::Data(block.clone()));
+        }
 
         debug!("load_block   end: {}", block_num);
 
         Ok(block)
     }
 
-    pub async fn load_block_async(&self, block_num: u32) -> Result<Arc<Block>, io::Error> {
+    /// Load a block and fill it into the cache, without blocking the runtime thread.
+    ///
+    /// If the table is opened with a sync [`Storage`],
+    /// the block is read with [`tokio::task::spawn_blocking`],
+    /// which works on either a current-thread or a multi-thread runtime.
+    pub async fn load_block_async(&self, block_num: u32) -> Result<Arc<Block<V>>, RotblError> {
         debug!("load_block_async start: {}", block_num);
-        let join_handle = tokio::task::block_in_place(move || self.load_block(block_num));
-        let block = join_handle?;
+
+        if let Some(b) = self.get_block(block_num) {
+            return Ok(b);
+        }
+
+        let block_meta = self.index_entry_by_num_async(block_num).await?;
+        let loc = Location::block(&self.rel_path, block_num, block_meta.segment());
+        let buf = self.file.read_segment_nonblocking(&loc, self.file_size).await?;
+
+        let block = self.decode_block(&loc, &buf)?;
+
+        {
+            let block_id = BlockId::new(self.table_id, block_num);
+            let mut cache = self.block_cache.lock().unwrap();
+            cache.insert(block_id, CachedBlock::Data(block.clone()));
+        }
+
         debug!("load_block_async   end: {}", block_num);
         Ok(block)
     }
 
     /// Load block from disk without accessing cache.
-    pub(crate) fn load_block_nocache(&self, block_num: u32) -> Result<Arc<Block>, io::Error> {
-        let block_meta = self.block_index.get_index_entry_by_num(block_num).unwrap();
+    pub(crate) fn load_block_nocache(&self, block_num: u32) -> Result<Arc<Block<V>>, RotblError> {
+        let TableReader::Sync(file) = &self.file else {
+            return Err(RotblError::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "can not load block synchronously from an async storage, use load_block_async()",
+            )));
+        };
 
-        let mut buf = new_uninitialized(block_meta.size as usize);
+        let block_meta = self.index_entry_by_num(block_num)?;
 
-        {
-            let mut f = self.file.lock().unwrap();
-            f.seek(io::SeekFrom::Start(block_meta.offset))?;
-            f.read_exact(&mut buf)?;
+        let loc = Location::block(&self.rel_path, block_num, block_meta.segment());
+        let buf = io_util::read_segment(file.as_ref(), &loc, self.file_size)?;
+
+        self.decode_block(&loc, &buf)
+    }
+
+    /// Return the index partition if it is in the cache.
+    fn get_index_partition(&self, partition_num: usize) -> Option<Arc<BlockIndex>> {
+        let block_id = BlockId::new_index_partition(self.table_id, partition_num as u32);
+
+        let mut c = self.block_cache.lock().unwrap();
+        match c.get(&block_id) {
+            Some(CachedBlock::IndexPartition(p)) => Some(p.clone()),
+            _ => None,
+        }
+    }
+
+    fn insert_index_partition(&self, partition_num: usize, partition: Arc<BlockIndex>) {
+        let block_id = BlockId::new_index_partition(self.table_id, partition_num as u32);
+
+        let mut c = self.block_cache.lock().unwrap();
+        c.insert(block_id, CachedBlock::IndexPartition(partition));
+    }
+
+    /// Load an index partition of a partitioned index through the block cache.
+    ///
+    /// `partition_num` is the position of its entry in the top-level index.
+    fn load_index_partition(&self, partition_num: usize) -> Result<Arc<BlockIndex>, RotblError> {
+        if let Some(p) = self.get_index_partition(partition_num) {
+            return Ok(p);
+        }
+
+        let TableReader::Sync(file) = &self.file else {
+            return Err(RotblError::Io(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "can not load index partition synchronously from an async storage",
+            )));
+        };
+
+        let ent = &self.block_index.data[partition_num];
+        let loc = Location::new(&self.rel_path, "index_partition", ent.segment());
+        let buf = io_util::read_segment(file.as_ref(), &loc, self.file_size)?;
+        let partition: Arc<BlockIndex> = Arc::new(decode_section(&loc, &buf)?);
+
+        self.insert_index_partition(partition_num, partition.clone());
+        Ok(partition)
+    }
+
+    /// Load an index partition through the block cache, without blocking the runtime thread.
+    async fn load_index_partition_async(
+        &self,
+        partition_num: usize,
+    ) -> Result<Arc<BlockIndex>, RotblError> {
+        if let Some(p) = self.get_index_partition(partition_num) {
+            return Ok(p);
+        }
+
+        let ent = &self.block_index.data[partition_num];
+        let loc = Location::new(&self.rel_path, "index_partition", ent.segment());
+        let buf = self.file.read_segment_nonblocking(&loc, self.file_size).await?;
+        let partition: Arc<BlockIndex> = Arc::new(decode_section(&loc, &buf)?);
+
+        self.insert_index_partition(partition_num, partition.clone());
+        Ok(partition)
+    }
+
+    /// The numbers of all blocks in the partitions at `positions` of the top-level index.
+    fn partition_block_nums(&self, positions: std::ops::Range<usize>) -> std::ops::Range<u32> {
+        let data = &self.block_index.data;
+        if positions.is_empty() {
+            return 0..0;
+        }
+
+        let start = data[positions.start].block_num;
+        let end = match data.get(positions.end) {
+            Some(ent) => ent.block_num,
+            None => self.stat.block_num,
+        };
+        start..end
+    }
+
+    fn block_not_found(block_num: u32) -> RotblError {
+        RotblError::invalid_argument(format!(
+            "block {} is not found in the block index",
+            block_num
+        ))
+    }
+
+    /// A block that is in the table but not in the index means the index is corrupted.
+    fn block_not_indexed(&self, block_num: u32, partition_num: Option<usize>) -> RotblError {
+        let loc = match partition_num {
+            Some(p) => {
+                let seg = self.block_index.data[p].segment();
+                Location::new(&self.rel_path, "index_partition", seg)
+            }
+            None => Location::new(
+                &self.rel_path,
+                "block_index",
+                self.footer.block_index_segment,
+            ),
+        };
+
+        loc.corruption(format!(
+            "block {} of {} blocks is not in the index",
+            block_num, self.stat.block_num
+        ))
+    }
+
+    /// Return the index entry of a block, loading the index partition if it is partitioned.
+    fn index_entry_by_num(&self, block_num: u32) -> Result<BlockIndexEntry, RotblError> {
+        if block_num >= self.stat.block_num {
+            return Err(Self::block_not_found(block_num));
+        }
+
+        if !self.block_index.is_partitioned() {
+            let entry = self.block_index.get_index_entry_by_num(block_num).cloned();
+            return entry.ok_or_else(|| self.block_not_indexed(block_num, None));
+        }
+
+        let Some(p) = self.block_index.partition_position_by_num(block_num) else {
+            return Err(self.block_not_indexed(block_num, None));
+        };
+
+        let entry = self.load_index_partition(p)?.get_index_entry_by_num(block_num).cloned();
+        entry.ok_or_else(|| self.block_not_indexed(block_num, Some(p)))
+    }
+
+    async fn index_entry_by_num_async(
+        &self,
+        block_num: u32,
+    ) -> Result<BlockIndexEntry, RotblError> {
+        if block_num >= self.stat.block_num {
+            return Err(Self::block_not_found(block_num));
+        }
+
+        if !self.block_index.is_partitioned() {
+            let entry = self.block_index.get_index_entry_by_num(block_num).cloned();
+            return entry.ok_or_else(|| self.block_not_indexed(block_num, None));
+        }
+
+        let Some(p) = self.block_index.partition_position_by_num(block_num) else {
+            return Err(self.block_not_indexed(block_num, None));
+        };
+
+        let partition = self.load_index_partition_async(p).await?;
+        let entry = partition.get_index_entry_by_num(block_num).cloned();
+        entry.ok_or_else(|| self.block_not_indexed(block_num, Some(p)))
+    }
+
+    /// Return the number of the block that may contain the key.
+    async fn lookup_block_async(&self, key: &[u8]) -> Result<Option<u32>, RotblError> {
+        if !self.block_index.is_partitioned() {
+            return Ok(self.block_index.lookup(key).map(|x| x.block_num));
         }
 
-        let block = Block::decode(&mut buf.as_slice())?;
+        let Some(p) = self.block_index.lookup_position(key) else {
+            return Ok(None);
+        };
+
+        let partition = self.load_index_partition_async(p).await?;
+        Ok(partition.lookup(key).map(|x| x.block_num))
+    }
+
+    /// Return the index entries of the blocks that overlap with the range.
+    async fn lookup_range_async(
+        &self,
+        range: impl RangeArg<Vec<u8>>,
+    ) -> Result<Vec<BlockIndexEntry>, RotblError> {
+        if !self.block_index.is_partitioned() {
+            return Ok(self.block_index.lookup_range(range).to_vec());
+        }
+
+        let mut entries = Vec::new();
+        for p in self.block_index.lookup_range_positions(range.clone()) {
+            let partition = self.load_index_partition_async(p).await?;
+            entries.extend_from_slice(partition.lookup_range(range.clone()));
+        }
+        Ok(entries)
+    }
+
+    /// Return the index entries of all blocks, loading all partitions if it is partitioned.
+    pub(crate) fn index_entries(&self) -> Result<Vec<BlockIndexEntry>, RotblError> {
+        if !self.block_index.is_partitioned() {
+            return Ok(self.block_index.data.clone());
+        }
+
+        let mut entries = Vec::new();
+        for p in 0..self.block_index.data.len() {
+            entries.extend_from_slice(&self.load_index_partition(p)?.data);
+        }
+        Ok(entries)
+    }
+
+    /// Decode a block at `loc` from the raw bytes read from storage.
+    fn decode_block(&self, loc: &Location, buf: &[u8]) -> Result<Arc<Block<V>>, RotblError> {
+        let block = Block::decode(buf).map_err(|e| loc.decode_error(e))?;
         let block = Arc::new(block);
 
         self.access_stat.hit_block(false);
@@ -252,42 +725,133 @@ impl Rotbl {
         Ok(block)
     }
 
-    /// Dump the table to human-readable lines in an iterator.
-    pub fn dump(self: &Arc<Self>) -> impl Iterator<Item = Result<String, io::Error>> {
-        dump::Dump::new(self.clone()).dump()
+    /// Build an error from a failure to decode an entry of a loaded block.
+    async fn block_entry_error(&self, block_num: u32, e: io::Error) -> RotblError {
+        match self.index_entry_by_num_async(block_num).await {
+            Ok(ent) => Location::block(&self.rel_path, block_num, ent.segment()).decode_error(e),
+            Err(_) => RotblError::from(e),
+        }
     }
 
     /// Return the value of the specified key.
-    pub async fn get(&self, key: &str) -> Result<Option<SeqMarked>, io::Error> {
-        let block_num = self.block_index.lookup(key).map(|x| x.block_num);
+    pub async fn get(&self, key: impl AsRef<[u8]>) -> Result<Option<V>, RotblError> {
+        let key = key.as_ref();
 
-        let Some(block_num) = block_num else {
-            return Ok(None);
+        // Check the filter first: with a partitioned index, the lookup may read a partition.
+        if let Some(filter) = &self.bloom_filter {
+            let positive = filter.may_contain(key);
+            self.access_stat.hit_filter(positive);
+
+            if !positive {
+                return Ok(None);
+            }
+        }
+
+        let block_num = self.lookup_block_async(key).await?;
+
+        let v = match block_num {
+            None => None,
+            Some(block_num) => {
+                let block = self.load_block_async(block_num).await?;
+                match block.get(key) {
+                    Ok(v) => v,
+                    Err(e) => return Err(self.block_entry_error(block_num, e).await),
+                }
+            }
         };
 
-        let block = self.load_block_async(block_num).await?;
-        let v = block.get(key).cloned();
+        if self.bloom_filter.is_some() && v.is_none() {
+            self.access_stat.hit_filter_false_positive();
+        }
+
         Ok(v)
     }
 
-    /// Return a `'static` `Stream` that iterating kvs in the specified range.
+    /// Return a `'static` `Stream` that iterating kvs in the specified range of `String` keys.
+    ///
+    /// It returns an error when it meets a key that is not UTF-8,
+    /// use [`Rotbl::range_bytes()`] for a table with such keys.
     pub fn range(
         self: &Arc<Self>,
         range: impl RangeArg,
-    ) -> BoxStream<'static, Result<(String, SeqMarked), io::Error>> {
+    ) -> BoxStream<'static, Result<(String, V), RotblError>> {
+        let range = (
+            range.start_bound().map(|k| k.clone().into_bytes()),
+            range.end_bound().map(|k| k.clone().into_bytes()),
+        );
+
+        self.range_bytes(range)
+            .map(|res| res.and_then(|(k, v)| Ok((key::into_string(k)?, v))))
+            .boxed()
+    }
+
+    /// Return a `'static` `Stream` that iterating kvs in the specified range of byte keys.
+    pub fn range_bytes(
+        self: &Arc<Self>,
+        range: impl RangeArg<Vec<u8>>,
+    ) -> BoxStream<'static, Result<(Vec<u8>, V), RotblError>> {
         self.clone().do_range(range)
     }
 
-    #[futures_async_stream::try_stream(boxed, ok = (String, SeqMarked), error = io::Error)]
-    async fn do_range(self: Arc<Self>, range: impl RangeArg) {
-        let block_metas = self.block_index.lookup_range(range.clone()).to_vec();
+    /// Return a `'static` `Stream` that iterating kvs whose key starts with `prefix`.
+    ///
+    /// It is the same as [`Rotbl::range()`] over all keys with `prefix`.
+    pub fn range_prefix(
+        self: &Arc<Self>,
+        prefix: &str,
+    ) -> BoxStream<'static, Result<(String, V), RotblError>> {
+        self.range_prefix_bytes(prefix)
+            .map(|res| res.and_then(|(k, v)| Ok((key::into_string(k)?, v))))
+            .boxed()
+    }
+
+    /// Return a `'static` `Stream` that iterating kvs whose key starts with `prefix`.
+    ///
+    /// It is the same as [`Rotbl::range_bytes()`] over all keys with `prefix`.
+    pub fn range_prefix_bytes(
+        self: &Arc<Self>,
+        prefix: impl AsRef<[u8]>,
+    ) -> BoxStream<'static, Result<(Vec<u8>, V), RotblError>> {
+        let range = prefix_range(prefix.as_ref());
+        self.range_bytes(range)
+    }
+
+    #[futures_async_stream::try_stream(boxed, ok = (Vec<u8>, V), error = RotblError)]
+    async fn do_range(self: Arc<Self>, range: impl RangeArg<Vec<u8>>) {
+        let block_metas = self.lookup_range_async(range.clone()).await?;
+
+        // If every key in the range shares a prefix,
+        // skip the blocks whose prefix filter does not contain it.
+        let prefix_filter = self.prefix_bloom_filter.as_ref().and_then(|f| {
+            let prefix = f.extractor().prefix_of_range(&range)?;
+            Some((f, prefix.to_vec()))
+        });
 
         for m in block_metas {
+            if let Some((filter, prefix)) = &prefix_filter {
+                let positive = filter.may_contain(m.block_num, prefix);
+                self.access_stat.hit_prefix_filter(positive);
+
+                if !positive {
+                    continue;
+                }
+            }
+
+            let loc = Location::block(&self.rel_path, m.block_num, m.segment());
+
             let block = self.load_block_async(m.block_num).await?;
-            let it = block.range(range.clone());
-            for (k, v) in it {
-                yield (k.clone(), v.clone());
+            let it = block.scan(range.clone()).map_err(|e| loc.decode_error(e))?;
+            for res in it {
+                let (k, v) = res.map_err(|e| loc.decode_error(e))?;
+                yield (k.to_vec(), v);
             }
         }
     }
 }
+
+impl Rotbl {
+    /// Dump the table to human-readable lines in an iterator.
+    pub fn dump(self: &Arc<Self>) -> impl Iterator<Item = Result<String, RotblError>> {
+        dump::Dump::new(self.clone()).dump()
+    }
+}
@@ -31,6 +31,97 @@ pub struct RotblStat {
 
     /// Size of serialized block index in bytes.
     pub index_size: u64,
+
+    /// Size of the data part of all blocks before compression, in bytes.
+    ///
+    /// It is `None` if blocks are not compressed.
+    /// `data_size` is always the size on disk, including block headers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_data_size: Option<u64>,
+
+    /// The smallest seq of all values, including tombstones.
+    ///
+    /// This and the following fields are `None` if the table is built before they are introduced.
+    /// Seq and tombstone stats are also `None` if values are not [`SeqMarked`].
+    ///
+    /// [`SeqMarked`]: `crate::v001::SeqMarked`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_seq: Option<u64>,
+
+    /// The largest seq of all values, including tombstones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_seq: Option<u64>,
+
+    /// Number of tombstones. The rest of the keys have normal values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tombstone_num: Option<u64>,
+
+    /// Total size of all keys in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_size: Option<u64>,
+
+    /// Total size of all encoded values in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_size: Option<u64>,
+
+    /// Histogram of key sizes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_size_histogram: Option<SizeHistogram>,
+
+    /// Histogram of encoded value sizes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_size_histogram: Option<SizeHistogram>,
+}
+
+/// Number of sizes in power-of-two buckets.
+///
+/// Bucket 0 counts size 0, and bucket `i > 0` counts sizes in `[2^(i-1), 2^i)`.
+#[derive(Debug, Clone)]
+#[derive(Default)]
+#[derive(PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
+pub struct SizeHistogram {
+    buckets: Vec<u64>,
+}
+
+impl SizeHistogram {
+    pub fn add(&mut self, size: u64) {
+        let i = (u64::BITS - size.leading_zeros()) as usize;
+        if self.buckets.len() <= i {
+            self.buckets.resize(i + 1, 0);
+        }
+        self.buckets[i] += 1;
+    }
+
+    /// The counts of all buckets, up to the last non-empty one.
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// The size range `[start, end)` counted by bucket `i`.
+    pub fn bucket_range(i: usize) -> (u64, u64) {
+        if i == 0 {
+            (0, 1)
+        } else {
+            (1 << (i - 1), 1 << i)
+        }
+    }
+}
+
+impl fmt::Display for SizeHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        let non_empty = self.buckets.iter().enumerate().filter(|(_, n)| **n > 0);
+        for (k, (i, n)) in non_empty.enumerate() {
+            if k > 0 {
+                write!(f, ", ")?;
+            }
+            let (start, end) = Self::bucket_range(i);
+            write!(f, "[{}, {}): {}", start, end, format_num(*n))?;
+        }
+        write!(f, "}}")
+    }
 }
 
 impl RotblStat {
@@ -50,6 +141,37 @@ impl RotblStat {
         self.index_size
     }
 
+    /// The ratio of the size on disk to the raw size of all blocks.
+    pub fn compression_ratio(&self) -> Option<f64> {
+        let raw = self.raw_data_size?;
+        if raw == 0 {
+            return None;
+        }
+        Some(self.data_size as f64 / raw as f64)
+    }
+
+    /// Number of keys with normal values, if tombstones are counted.
+    ///
+    /// It is `None` for an invalid stat with more tombstones than keys.
+    pub fn normal_num(&self) -> Option<u64> {
+        self.key_num.checked_sub(self.tombstone_num?)
+    }
+
+    /// Record the size of a key and its encoded value.
+    pub(crate) fn add_kv_size(&mut self, key_size: u64, value_size: u64) {
+        *self.key_size.get_or_insert(0) += key_size;
+        *self.value_size.get_or_insert(0) += value_size;
+        self.key_size_histogram.get_or_insert_default().add(key_size);
+        self.value_size_histogram.get_or_insert_default().add(value_size);
+    }
+
+    /// Record the seq of a value and whether it is a tombstone.
+    pub(crate) fn add_seq(&mut self, seq: u64, tombstone: bool) {
+        self.min_seq = Some(self.min_seq.map_or(seq, |x| x.min(seq)));
+        self.max_seq = Some(self.max_seq.map_or(seq, |x| x.max(seq)));
+        *self.tombstone_num.get_or_insert(0) += tombstone as u64;
+    }
+
     /// Average size in bytes of a block.
     fn block_avg_size(&self) -> u64 {
         if self.block_num == 0 {
@@ -70,7 +192,25 @@ impl fmt::Display for RotblStat {
             format_num(self.data_size()),
             format_num(self.index_size()),
             format_num(self.block_avg_size()),
-        )
+        )?;
+
+        if let Some(raw) = self.raw_data_size {
+            write!(f, ", raw data({} B)", format_num(raw))?;
+        }
+
+        if let (Some(min), Some(max)) = (self.min_seq, self.max_seq) {
+            write!(f, ", seq[{}, {}]", min, max)?;
+        }
+
+        if let Some(n) = self.tombstone_num {
+            write!(f, ", tombstones({})", format_num(n))?;
+        }
+
+        if let (Some(k), Some(v)) = (self.key_size, self.value_size) {
+            write!(f, ", key({} B), value({} B)", format_num(k), format_num(v))?;
+        }
+
+        Ok(())
     }
 }
 
@@ -124,8 +264,11 @@ impl codeq::Decode for RotblStat {
 #[cfg(test)]
 mod tests {
     use codeq::testing::test_codec;
+    use codeq::Decode;
+    use codeq::Encode;
 
     use crate::v001::rotbl::stat::RotblStat;
+    use crate::v001::rotbl::stat::SizeHistogram;
     use crate::v001::testing::bbs;
     use crate::v001::testing::vec_chain;
 
@@ -136,6 +279,8 @@ mod tests {
             key_num: 10,
             data_size: 100,
             index_size: 200,
+            raw_data_size: None,
+            ..Default::default()
         };
         println!("{}", serde_json::to_string(&stat)?);
 
@@ -152,6 +297,82 @@ mod tests {
 
         test_codec(b.as_slice(), &stat)?;
 
+        let stat = RotblStat {
+            raw_data_size: Some(400),
+            ..stat
+        };
+        let json = serde_json::to_string(&stat)?;
+        assert_eq!(
+            r#"{"block_num":5,"key_num":10,"data_size":100,"index_size":200,"raw_data_size":400}"#,
+            json
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotbl_stat_codec_entry_stats() -> anyhow::Result<()> {
+        let mut stat = RotblStat {
+            key_num: 3,
+            ..Default::default()
+        };
+        stat.add_kv_size(1, 0);
+        stat.add_kv_size(2, 5);
+        stat.add_kv_size(3, 8);
+        stat.add_seq(7, false);
+        stat.add_seq(3, true);
+        stat.add_seq(5, false);
+
+        let json = serde_json::to_string(&stat)?;
+        assert_eq!(
+            concat!(
+                r#"{"block_num":0,"key_num":3,"data_size":0,"index_size":0,"#,
+                r#""min_seq":3,"max_seq":7,"tombstone_num":1,"key_size":6,"value_size":13,"#,
+                r#""key_size_histogram":[0,1,2],"value_size_histogram":[1,0,0,1,1]}"#
+            ),
+            json
+        );
+
+        let mut b = Vec::new();
+        stat.encode(&mut b)?;
+        assert_eq!(stat, RotblStat::decode(b.as_slice())?);
+
+        assert_eq!(Some(2), stat.normal_num());
+
+        // Fields missing from an old stat are `None`.
+        let old: RotblStat = serde_json::from_str(
+            r#"{"block_num":5,"key_num":10,"data_size":100,"index_size":200}"#,
+        )?;
+        assert_eq!(None, old.min_seq);
+        assert_eq!(None, old.tombstone_num);
+        assert_eq!(None, old.normal_num());
+        assert_eq!(None, old.key_size_histogram);
+
+        // A corrupted stat with more tombstones than keys does not panic.
+        let bad: RotblStat = serde_json::from_str(
+            r#"{"block_num":1,"key_num":1,"data_size":0,"index_size":0,"tombstone_num":2}"#,
+        )?;
+        assert_eq!(None, bad.normal_num());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_size_histogram() -> anyhow::Result<()> {
+        let mut h = SizeHistogram::default();
+        assert_eq!("{}", h.to_string());
+
+        for size in [0, 1, 2, 3, 4, 7, 8, 1000] {
+            h.add(size);
+        }
+
+        assert_eq!(&[1, 1, 2, 2, 1, 0, 0, 0, 0, 0, 1], h.buckets());
+        assert_eq!((512, 1024), SizeHistogram::bucket_range(10));
+        assert_eq!(
+            "{[0, 1): 1, [1, 2): 1, [2, 4): 2, [4, 8): 2, [8, 16): 1, [512, 1024): 1}",
+            h.to_string()
+        );
+
         Ok(())
     }
 
@@ -162,6 +383,8 @@ mod tests {
             key_num: 10,
             data_size: 100,
             index_size: 200,
+            raw_data_size: None,
+            ..Default::default()
         };
 
         assert_eq!(stat.block_num(), 5);
@@ -180,6 +403,8 @@ mod tests {
             key_num: 10,
             data_size: 100,
             index_size: 200,
+            raw_data_size: None,
+            ..Default::default()
         };
 
         assert_eq!(
@@ -187,6 +412,32 @@ mod tests {
             stat.to_string()
         );
 
+        let stat = RotblStat {
+            raw_data_size: Some(400),
+            ..stat
+        };
+
+        assert_eq!(
+            "10 keys in 5_000 blocks: data(100 B), index(200 B), avg block size(0 B), raw data(400 B)",
+            stat.to_string()
+        );
+        assert_eq!(stat.compression_ratio(), Some(0.25));
+
+        let stat = RotblStat {
+            raw_data_size: None,
+            min_seq: Some(1),
+            max_seq: Some(2),
+            tombstone_num: Some(1),
+            key_size: Some(4),
+            value_size: Some(5_000),
+            ..stat
+        };
+
+        assert_eq!(
+            "10 keys in 5_000 blocks: data(100 B), index(200 B), avg block size(0 B), seq[1, 2], tombstones(1), key(4 B), value(5_000 B)",
+            stat.to_string()
+        );
+
         Ok(())
     }
 }
@@ -75,7 +75,7 @@ impl codeq::Decode for RotblMeta {
         let mut cr = Checksum::new_reader(r);
 
         let header = Header::decode(&mut cr)?;
-        assert_eq!(header, Header::new(Type::RotblMeta, Version::V001));
+        header.check(Type::RotblMeta, &[Version::V001])?;
 
         let payload_size = WithChecksum::<u64>::decode(&mut cr)?.into_inner();
 
@@ -0,0 +1,189 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+
+use byteorder::BigEndian;
+use byteorder::ReadBytesExt;
+use byteorder::WriteBytesExt;
+use codeq::config::CodeqConfig;
+use codeq::Span;
+
+use crate::buf;
+use crate::typ::Type;
+use crate::v001::header::Header;
+use crate::v001::types::Checksum;
+use crate::v001::types::Segment;
+use crate::v001::types::WithChecksum;
+use crate::version::Version;
+
+/// A table of optional sections in a rotbl, by name.
+///
+/// It is referenced by the footer of a [`Version::V002`] rotbl,
+/// so that a new kind of section can be added without changing the footer again.
+/// A reader ignores the sections it does not know.
+///
+/// Encoded data layout:
+/// ```text
+/// | Header
+/// | Data encoded size
+/// | n: u64
+/// | len: u32 | name | offset: u64 | size: u64
+/// | ... n entries
+/// | Checksum
+/// ```
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq, Eq)]
+pub struct Sections {
+    header: Header,
+
+    sections: BTreeMap<String, Segment>,
+}
+
+impl Default for Sections {
+    fn default() -> Self {
+        Self {
+            header: Header::new(Type::Sections, Version::V001),
+            sections: BTreeMap::new(),
+        }
+    }
+}
+
+impl Sections {
+    /// The name of the [`BloomFilter`](`crate::v001::BloomFilter`) of all keys.
+    pub const BLOOM_FILTER: &'static str = "bloom_filter";
+
+    /// The name of the [`PrefixBloomFilter`](`crate::v001::PrefixBloomFilter`).
+    pub const PREFIX_BLOOM_FILTER: &'static str = "prefix_bloom_filter";
+
+    /// The name of the user-defined [`Properties`](`crate::v001::Properties`).
+    pub const PROPERTIES: &'static str = "properties";
+
+    pub fn insert(&mut self, name: impl ToString, segment: Segment) {
+        self.sections.insert(name.to_string(), segment);
+    }
+
+    pub fn get(&self, name: &str) -> Option<Segment> {
+        self.sections.get(name).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Segment)> {
+        self.sections.iter()
+    }
+}
+
+impl codeq::Encode for Sections {
+    fn encode<W: Write>(&self, mut w: W) -> Result<usize, io::Error> {
+        let mut n = 0usize;
+
+        let mut data = Vec::new();
+        data.write_u64::<BigEndian>(self.sections.len() as u64)?;
+        for (name, seg) in self.sections.iter() {
+            data.write_u32::<BigEndian>(name.len() as u32)?;
+            data.extend_from_slice(name.as_bytes());
+            data.write_u64::<BigEndian>(*seg.offset())?;
+            data.write_u64::<BigEndian>(*seg.size())?;
+        }
+
+        let mut cw = Checksum::new_writer(&mut w);
+
+        n += self.header.encode(&mut cw)?;
+        n += Checksum::wrap(data.len() as u64).encode(&mut cw)?;
+
+        cw.write_all(&data)?;
+        n += data.len();
+
+        n += cw.write_checksum()?;
+
+        Ok(n)
+    }
+}
+
+impl codeq::Decode for Sections {
+    fn decode<R: Read>(r: R) -> Result<Self, io::Error> {
+        let mut cr = Checksum::new_reader(r);
+
+        let header = Header::decode(&mut cr)?;
+        header.check(Type::Sections, &[Version::V001])?;
+
+        let data_size = WithChecksum::<u64>::decode(&mut cr)?.into_inner();
+
+        let mut data = buf::new_uninitialized(data_size as usize);
+        cr.read_exact(&mut data)?;
+
+        cr.verify_checksum(|| "Sections::decode()")?;
+
+        let mut r = data.as_slice();
+        let n = r.read_u64::<BigEndian>()?;
+
+        let mut sections = BTreeMap::new();
+        for _ in 0..n {
+            let len = r.read_u32::<BigEndian>()? as usize;
+            if len > r.len() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("section name length {} exceeds the data", len),
+                ));
+            }
+
+            let (name, rest) = r.split_at(len);
+            r = rest;
+
+            let name = String::from_utf8(name.to_vec())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+            let offset = r.read_u64::<BigEndian>()?;
+            let size = r.read_u64::<BigEndian>()?;
+
+            sections.insert(name, Segment::new(offset, size));
+        }
+
+        Ok(Self { header, sections })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use codeq::testing::test_codec;
+
+    use crate::v001::sections::Sections;
+    use crate::v001::testing::vec_chain;
+    use crate::v001::types::Segment;
+
+    #[test]
+    fn test_sections_codec() -> anyhow::Result<()> {
+        let mut sections = Sections::default();
+        sections.insert("ab", Segment::new(5, 10));
+
+        let b = vec_chain([
+            vec![
+                115, 101, 99, 116, 105, 111, 110, 115, // header.type
+                0, 0, 0, 0, 0, 0, 0, 1, // header.version
+                0, 0, 0, 0, 230, 141, 196, 20, // header checksum
+                0, 0, 0, 0, 0, 0, 0, 30, // data size
+                0, 0, 0, 0, 159, 45, 226, 10, // data size checksum
+            ],
+            vec![
+                0, 0, 0, 0, 0, 0, 0, 1, // number of sections
+                0, 0, 0, 2, 97, 98, // name: "ab"
+                0, 0, 0, 0, 0, 0, 0, 5, // offset
+                0, 0, 0, 0, 0, 0, 0, 10, // size
+            ],
+            vec![
+                0, 0, 0, 0, 54, 21, 123, 140, // checksum
+            ],
+        ]);
+
+        test_codec(b.as_slice(), &sections)?;
+
+        assert_eq!(Some(Segment::new(5, 10)), sections.get("ab"));
+        assert_eq!(None, sections.get("a"));
+
+        Ok(())
+    }
+}
@@ -18,6 +18,11 @@ pub(crate) fn bb(x: impl ToString) -> Vec<u8> {
     x.to_string().into_bytes()
 }
 
+/// Create a vector of byte vectors from multiple strings
+pub(crate) fn bb_vec(x: impl IntoIterator<Item = impl ToString>) -> Vec<Vec<u8>> {
+    x.into_iter().map(bb).collect()
+}
+
 /// Create a byte vector from multiple strings
 pub(crate) fn bbs(x: impl IntoIterator<Item = impl ToString>) -> Vec<u8> {
     let r = x.into_iter().map(|x| x.to_string().into_bytes());
@@ -0,0 +1,96 @@
+//! The type of values stored in a table.
+
+use std::fmt;
+
+use bincode::enc::write::Writer;
+use bincode::enc::Encoder;
+use bincode::error::EncodeError;
+
+use crate::v001::bincode_config::bincode_config;
+use crate::v001::SeqMarked;
+
+/// A value that can be stored in a [`Rotbl`](crate::v001::Rotbl).
+///
+/// Values are encoded with bincode in blocks, so any type that implements bincode
+/// `Encode` and `Decode` can be used as a value:
+///
+/// - [`SeqMarked`](crate::v001::SeqMarked), the default, carries a sequence number and a tombstone
+///   flag along with the bytes.
+/// - `Vec<u8>` stores raw bytes, without the cost of the sequence number and the tombstone.
+/// - A user-defined structured type is stored without being serialized twice.
+///
+/// The value type is not recorded in the table: a table must be read with the same value type
+/// that it is built with.
+///
+/// A user-defined type opts in with an empty impl, or overrides the hooks below:
+///
+/// ```
+/// #[derive(Debug, Clone, PartialEq, bincode::Encode, bincode::Decode)]
+/// struct User {
+///     id: u64,
+/// }
+///
+/// impl rotbl::v001::Value for User {}
+/// ```
+pub trait Value
+where Self: bincode::Encode
+        + bincode::Decode<()>
+        + fmt::Debug
+        + Clone
+        + PartialEq
+        + Send
+        + Sync
+        + 'static
+{
+    /// The sequence number of this value and whether it is a tombstone,
+    /// recorded in [`RotblStat`](crate::v001::stat::RotblStat) when a table is built.
+    ///
+    /// A value type that carries no sequence number returns `None`.
+    fn seq_stat(&self) -> Option<(u64, bool)> {
+        None
+    }
+}
+
+impl<D> Value for SeqMarked<D>
+where D: bincode::Encode
+        + bincode::Decode<()>
+        + fmt::Debug
+        + Clone
+        + PartialEq
+        + Send
+        + Sync
+        + 'static
+{
+    fn seq_stat(&self) -> Option<(u64, bool)> {
+        let (seq, _) = self.order_key().into_parts();
+        Some((seq, self.is_tombstone()))
+    }
+}
+
+impl Value for Vec<u8> {}
+
+impl Value for String {}
+
+/// A value already encoded with bincode, which is written as is when encoded again.
+///
+/// A [`Builder`](crate::v001::Builder) encodes a value once when it is appended,
+/// to measure it and to write it into a block.
+#[derive(Debug)]
+#[derive(Clone)]
+pub(crate) struct EncodedValue(Vec<u8>);
+
+impl EncodedValue {
+    pub(crate) fn new<V: Value>(v: &V) -> Result<Self, EncodeError> {
+        bincode::encode_to_vec(v, bincode_config()).map(Self)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl bincode::Encode for EncodedValue {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        encoder.writer().write(&self.0)
+    }
+}
@@ -3,12 +3,17 @@ use std::io::Read;
 use std::io::Write;
 use std::sync::Arc;
 
+use bytes::Bytes;
+use codeq::Decode;
+use codeq::FixedSize;
+
 use crate::typ::Type;
 use crate::v00x::var_len_array::payload::RawVLArrayPayload;
 use crate::version::Version;
 
 /// A list of packed entries of variable length raw bytes.
 #[derive(Debug)]
+#[derive(Clone)]
 #[derive(PartialEq, Eq)]
 pub struct RawVLArray {
     /// The version of this data.
@@ -31,23 +36,25 @@ impl RawVLArray {
     pub fn len(&self) -> usize {
         self.payload.len()
     }
-}
 
-impl codeq::Encode for RawVLArray {
-    fn encode<W: Write>(&self, mut w: W) -> Result<usize, Error> {
-        let mut n = 0;
+    /// Decode an array at the start of `buf`, referring to the entries in `buf` without copying.
+    ///
+    /// Returns the array and the number of bytes it takes in `buf`.
+    pub fn decode_bytes(buf: &Bytes) -> Result<(Self, usize), Error> {
+        let mut r = buf.as_ref();
+        let version = Self::decode_type_version(&mut r)?;
 
-        let t = Type::VLArray;
-        n += t.encode(&mut w)?;
-        n += self.version.encode(&mut w)?;
-        n += self.payload.encode(&mut w)?;
+        let n = Type::encoded_size() + Version::encoded_size();
+        let (payload, size) = RawVLArrayPayload::decode_bytes(buf.slice(n..))?;
 
-        Ok(n)
+        let arr = Self {
+            version,
+            payload: Arc::new(payload),
+        };
+        Ok((arr, n + size))
     }
-}
 
-impl codeq::Decode for RawVLArray {
-    fn decode<R: Read>(mut r: R) -> Result<Self, Error> {
+    fn decode_type_version<R: Read>(mut r: R) -> Result<Version, Error> {
         let t = Type::decode(&mut r)?;
         if t != Type::VLArray {
             return Err(Error::new(
@@ -64,6 +71,27 @@ impl codeq::Decode for RawVLArray {
             ));
         }
 
+        Ok(version)
+    }
+}
+
+impl codeq::Encode for RawVLArray {
+    fn encode<W: Write>(&self, mut w: W) -> Result<usize, Error> {
+        let mut n = 0;
+
+        let t = Type::VLArray;
+        n += t.encode(&mut w)?;
+        n += self.version.encode(&mut w)?;
+        n += self.payload.encode(&mut w)?;
+
+        Ok(n)
+    }
+}
+
+impl Decode for RawVLArray {
+    fn decode<R: Read>(mut r: R) -> Result<Self, Error> {
+        let version = Self::decode_type_version(&mut r)?;
+
         let payload = RawVLArrayPayload::decode(&mut r)?;
 
         Ok(Self {
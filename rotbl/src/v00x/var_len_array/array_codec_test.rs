@@ -38,3 +38,31 @@ fn test_build() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_decode_bytes() -> anyhow::Result<()> {
+    let vla = RawVLArrayBuilder::new(None).build(vec!["hello", "world", "foo", "bar"]);
+
+    let mut buf = Vec::new();
+    let n = vla.encode(&mut buf)?;
+    buf.extend_from_slice(b"trailing");
+
+    let buf = bytes::Bytes::from(buf);
+    let (got, size) = RawVLArray::decode_bytes(&buf)?;
+
+    assert_eq!(n, size);
+    assert_eq!(vla, got);
+
+    // Entries refer to `buf` without copying.
+    let entry = got.get(1).unwrap();
+    assert_eq!(b"world", entry);
+    assert!(buf.as_ptr_range().contains(&entry.as_ptr()));
+
+    // Truncated
+    for len in [0, 16, 24, 40, n - 1] {
+        let res = RawVLArray::decode_bytes(&buf.slice(..len));
+        assert!(res.is_err(), "len={}", len);
+    }
+
+    Ok(())
+}
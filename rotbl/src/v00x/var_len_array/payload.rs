@@ -1,6 +1,7 @@
 use std::io;
 
 use byteorder::BigEndian;
+use byteorder::ByteOrder;
 use byteorder::ReadBytesExt;
 use byteorder::WriteBytesExt;
 
@@ -29,6 +30,56 @@ impl RawVLArrayPayload {
     pub fn len(&self) -> usize {
         self.offsets.len() - 1
     }
+
+    /// Decode a payload at the start of `buf`, referring to the entries in `buf` without copying.
+    ///
+    /// The offsets are checked so that every entry is in `buf`.
+    /// Returns the payload and the number of bytes it takes in `buf`.
+    pub(crate) fn decode_bytes(buf: bytes::Bytes) -> Result<(Self, usize), io::Error> {
+        let invalid = |reason: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid RawVLArray payload: {}", reason),
+            )
+        };
+
+        if buf.len() < 8 {
+            return Err(invalid(format!("size {} is smaller than len", buf.len())));
+        }
+
+        let len = BigEndian::read_u64(&buf[..8]);
+
+        let offsets_end = (len as usize)
+            .checked_add(1)
+            .and_then(|n| n.checked_mul(4))
+            .and_then(|n| n.checked_add(8))
+            .filter(|n| *n <= buf.len())
+            .ok_or_else(|| invalid(format!("{} offsets do not fit in {} bytes", len, buf.len())))?;
+
+        let offsets =
+            buf[8..offsets_end].chunks_exact(4).map(BigEndian::read_u32).collect::<Vec<_>>();
+
+        if offsets[0] != 0 {
+            return Err(invalid(format!("the first offset is {}", offsets[0])));
+        }
+
+        if offsets.windows(2).any(|w| w[0] > w[1]) {
+            return Err(invalid("offsets are not sorted".to_string()));
+        }
+
+        let size = offsets[offsets.len() - 1] as usize;
+        if offsets_end + size > buf.len() {
+            return Err(invalid(format!(
+                "entries of size {} do not fit in {} bytes",
+                size,
+                buf.len() - offsets_end
+            )));
+        }
+
+        let entries = buf.slice(offsets_end..offsets_end + size);
+
+        Ok((Self { offsets, entries }, offsets_end + size))
+    }
 }
 
 impl codeq::Encode for RawVLArrayPayload {
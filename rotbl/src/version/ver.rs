@@ -11,6 +11,9 @@ use codeq::FixedSize;
 #[derive(PartialEq, Eq)]
 pub enum Version {
     V001,
+    V002,
+    V003,
+    V004,
 }
 
 impl fmt::Display for Version {
@@ -23,12 +26,18 @@ impl Version {
     pub fn as_u64(&self) -> u64 {
         match self {
             Version::V001 => 1,
+            Version::V002 => 2,
+            Version::V003 => 3,
+            Version::V004 => 4,
         }
     }
 
     pub fn from_u64(v: u64) -> Result<Self, u64> {
         match v {
             1 => Ok(Version::V001),
+            2 => Ok(Version::V002),
+            3 => Ok(Version::V003),
+            4 => Ok(Version::V004),
             _ => Err(v),
         }
     }
@@ -62,16 +71,26 @@ impl codeq::Decode for Version {
 #[cfg(test)]
 mod tests {
     use codeq::testing::test_codec;
+    use codeq::Decode;
     use codeq::Encode;
 
     use crate::version::Version;
 
     #[test]
     fn test_version_codec() -> anyhow::Result<()> {
-        let v = Version::V001;
+        for v in [Version::V001, Version::V002, Version::V003, Version::V004] {
+            let mut b = Vec::new();
+            let n = v.encode(&mut b)?;
+            assert_eq!(n, b.len());
+
+            let decoded = Version::decode(b.as_slice())?;
+            assert_eq!(v, decoded);
+        }
+
+        // Version has no checksum; only the latest version is invalid after changing a byte.
+        let v = Version::V004;
         let mut b = Vec::new();
-        let n = v.encode(&mut b)?;
-        assert_eq!(n, b.len());
+        v.encode(&mut b)?;
 
         test_codec(&b, &v)?;
 
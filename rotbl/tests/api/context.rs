@@ -1,10 +1,16 @@
-use std::io;
 use std::path::Path;
 use std::sync::Arc;
 
+use rotbl::storage::impls::encrypted::EncryptedStorage;
+use rotbl::storage::impls::encrypted::StaticKeyProvider;
 use rotbl::storage::impls::fs::FsStorage;
+use rotbl::storage::impls::fs::ReadMode;
+use rotbl::storage::impls::mem::MemStorage;
+use rotbl::storage::impls::object_store::MemObjectStore;
+use rotbl::storage::impls::object_store::ObjectStoreStorage;
 use rotbl::storage::Storage;
 use rotbl::v001::Config;
+use rotbl::v001::RotblError;
 use rotbl::v001::DB;
 use tempfile::TempDir;
 
@@ -14,7 +20,8 @@ where S: Storage
     #[allow(dead_code)]
     config: Config,
 
-    temp_dir: TempDir,
+    /// The temp dir of the storage, if the storage is backed by a file system.
+    temp_dir: Option<TempDir>,
     storage: S,
 }
 
@@ -31,13 +38,52 @@ impl TestContext<FsStorage> {
     }
 
     pub fn new_fs(config: Config) -> anyhow::Result<Self> {
+        Self::new_fs_with_read_mode(config, ReadMode::Buffered)
+    }
+
+    pub fn new_fs_with_read_mode(config: Config, read_mode: ReadMode) -> anyhow::Result<Self> {
         let temp_dir = tempfile::tempdir()?;
 
-        let storage = FsStorage::new(temp_dir.path().to_path_buf());
+        let storage = FsStorage::new(temp_dir.path().to_path_buf()).with_read_mode(read_mode);
+
+        Ok(TestContext {
+            config,
+            temp_dir: Some(temp_dir),
+            storage,
+        })
+    }
+}
+
+impl TestContext<MemStorage> {
+    pub fn new_mem(config: Config) -> anyhow::Result<Self> {
+        Ok(TestContext {
+            config,
+            temp_dir: None,
+            storage: MemStorage::new(),
+        })
+    }
+}
+
+impl TestContext<ObjectStoreStorage<MemObjectStore>> {
+    pub fn new_object_store(config: Config) -> anyhow::Result<Self> {
+        Ok(TestContext {
+            config,
+            temp_dir: None,
+            storage: ObjectStoreStorage::new(MemObjectStore::new()),
+        })
+    }
+}
+
+impl TestContext<EncryptedStorage<MemStorage, StaticKeyProvider>> {
+    pub fn new_encrypted(config: Config) -> anyhow::Result<Self> {
+        let keys = StaticKeyProvider::new(1, [7; 32]);
+
+        // A small chunk size to let a block span several chunks.
+        let storage = EncryptedStorage::new(MemStorage::new(), keys).with_chunk_size(64)?;
 
         Ok(TestContext {
             config,
-            temp_dir,
+            temp_dir: None,
             storage,
         })
     }
@@ -46,7 +92,7 @@ impl TestContext<FsStorage> {
 impl<S> TestContext<S>
 where S: Storage
 {
-    pub fn new_db(&self) -> Result<Arc<DB>, io::Error> {
+    pub fn new_db(&self) -> Result<Arc<DB>, RotblError> {
         DB::open(self.config.clone())
     }
 
@@ -58,8 +104,8 @@ where S: Storage
         &mut self.config
     }
 
-    pub fn base_dir(&self) -> &Path {
-        self.temp_dir.path()
+    pub fn base_dir(&self) -> Option<&Path> {
+        self.temp_dir.as_ref().map(|d| d.path())
     }
 
     pub fn storage(&self) -> S {
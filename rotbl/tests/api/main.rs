@@ -1,5 +1,6 @@
 use libtest_mimic::Arguments;
 use libtest_mimic::Trial;
+use rotbl::storage::impls::fs::ReadMode;
 use rotbl::storage::Storage;
 use rotbl::v001::Config;
 
@@ -13,9 +14,22 @@ pub mod utils;
 
 pub mod test_create_open;
 pub mod test_dump;
+pub mod test_rotbl_async_storage;
 pub mod test_rotbl_block;
+pub mod test_rotbl_block_size;
+pub mod test_rotbl_bloom_filter;
+pub mod test_rotbl_byte_keys;
 pub mod test_rotbl_cache_stat;
+pub mod test_rotbl_compression;
+pub mod test_rotbl_error;
+pub mod test_rotbl_fault;
+pub mod test_rotbl_packed;
+pub mod test_rotbl_partitioned_index;
+pub mod test_rotbl_prefix_bloom_filter;
+pub mod test_rotbl_prefix_keys;
+pub mod test_rotbl_properties;
 pub mod test_rotbl_read;
+pub mod test_rotbl_value_type;
 
 fn main() -> anyhow::Result<()> {
     let args = Arguments::from_args();
@@ -27,9 +41,41 @@ fn main() -> anyhow::Result<()> {
         TestContext::new_fs(config)
     };
 
+    let new_fs_mmap_ctx = || {
+        let mut config = Config::default();
+        config.block_config.max_items = Some(3);
+
+        TestContext::new_fs_with_read_mode(config, ReadMode::Mmap)
+    };
+
+    let new_mem_ctx = || {
+        let mut config = Config::default();
+        config.block_config.max_items = Some(3);
+
+        TestContext::new_mem(config)
+    };
+
+    let new_object_store_ctx = || {
+        let mut config = Config::default();
+        config.block_config.max_items = Some(3);
+
+        TestContext::new_object_store(config)
+    };
+
+    let new_encrypted_ctx = || {
+        let mut config = Config::default();
+        config.block_config.max_items = Some(3);
+
+        TestContext::new_encrypted(config)
+    };
+
     let mut tests = Vec::new();
 
     collect_trials(&mut tests, "fs", new_fs_ctx);
+    collect_trials(&mut tests, "fs-mmap", new_fs_mmap_ctx);
+    collect_trials(&mut tests, "mem", new_mem_ctx);
+    collect_trials(&mut tests, "object-store", new_object_store_ctx);
+    collect_trials(&mut tests, "encrypted", new_encrypted_ctx);
 
     // Don't init logging while building operator which may break cargo
     // nextest output
@@ -52,7 +98,20 @@ where S: Storage {
 
     test_create_open::tests(new_ctx.clone(), tests);
     test_dump::tests(new_ctx.clone(), tests);
+    test_rotbl_async_storage::tests(new_ctx.clone(), tests);
     test_rotbl_block::tests(new_ctx.clone(), tests);
+    test_rotbl_block_size::tests(new_ctx.clone(), tests);
+    test_rotbl_bloom_filter::tests(new_ctx.clone(), tests);
+    test_rotbl_byte_keys::tests(new_ctx.clone(), tests);
     test_rotbl_cache_stat::tests(new_ctx.clone(), tests);
+    test_rotbl_compression::tests(new_ctx.clone(), tests);
+    test_rotbl_error::tests(new_ctx.clone(), tests);
+    test_rotbl_prefix_bloom_filter::tests(new_ctx.clone(), tests);
+    test_rotbl_prefix_keys::tests(new_ctx.clone(), tests);
+    test_rotbl_fault::tests(new_ctx.clone(), tests);
+    test_rotbl_packed::tests(new_ctx.clone(), tests);
+    test_rotbl_partitioned_index::tests(new_ctx.clone(), tests);
+    test_rotbl_properties::tests(new_ctx.clone(), tests);
     test_rotbl_read::tests(new_ctx.clone(), tests);
+    test_rotbl_value_type::tests(new_ctx.clone(), tests);
 }
@@ -40,8 +40,8 @@ where
     let t = Rotbl::create_table(storage, db.config(), path, rotbl_meta, kvs)?;
 
     let index_data = vec![
-        BlockIndexEntry::new(0, Segment::new(36, 73), ss("a"), ss("c")),
-        BlockIndexEntry::new(1, Segment::new(109, 63), ss("d"), ss("d")),
+        BlockIndexEntry::new(0, Segment::new(36, 73), bb("a"), bb("c")),
+        BlockIndexEntry::new(1, Segment::new(109, 63), bb("d"), bb("d")),
     ];
 
     Ok((t, index_data))
@@ -2,21 +2,32 @@ use libtest_mimic::Trial;
 use rotbl::storage::Storage;
 use rotbl::typ::Type;
 use rotbl::v001::stat::RotblStat;
+use rotbl::v001::stat::SizeHistogram;
 use rotbl::v001::BlockIndex;
+use rotbl::v001::Builder;
 use rotbl::v001::Footer;
 use rotbl::v001::Header;
 use rotbl::v001::Rotbl;
 use rotbl::v001::Segment;
+use rotbl::v001::SeqMarked;
+use rotbl::v001::DB;
 use rotbl::version::Version;
 use temp_table::create_tmp_table;
 
 use crate::context::TestContext;
 use crate::temp_table;
 use crate::trials;
+use crate::utils::bb_vec;
 use crate::utils::NewContext;
 
 pub(crate) fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
-    trials.extend(trials!(new_ctx, test_create_table, test_open_table));
+    trials.extend(trials!(
+        new_ctx,
+        test_create_table,
+        test_open_table,
+        test_open_table_binary_index,
+        test_builder_abort
+    ));
 }
 
 fn test_create_table<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
@@ -36,6 +47,14 @@ fn test_create_table<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
         key_num: 4,
         data_size: 136,
         index_size: 188,
+        raw_data_size: None,
+        min_seq: Some(1),
+        max_seq: Some(2),
+        tombstone_num: Some(1),
+        key_size: Some(4),
+        value_size: Some(14),
+        key_size_histogram: Some(size_histogram([1, 1, 1, 1])),
+        value_size_histogram: Some(size_histogram([2, 4, 4, 4])),
     });
 
     assert_eq!(
@@ -43,11 +62,11 @@ fn test_create_table<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
         &Footer::new(
             Segment::new(172, 188),
             Segment::new(360, 77),
-            Segment::new(437, 84)
+            Segment::new(437, 215)
         )
     );
 
-    assert_eq!(593, t.file_size());
+    assert_eq!(724, t.file_size());
 
     Ok(())
 }
@@ -58,7 +77,7 @@ fn test_open_table<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
     let _ = t;
     // println!("{:?}", t);
 
-    let t = Rotbl::open(ctx.storage(), ctx.config(), "foo.rot")?;
+    let t: Rotbl = Rotbl::open(ctx.storage(), ctx.config(), "foo.rot")?;
 
     assert_eq!(t.header(), &Header::new(Type::Rotbl, Version::V001));
     // assert_eq!(t.table_id, 12);
@@ -75,6 +94,14 @@ fn test_open_table<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
         key_num: 4,
         data_size: 136,
         index_size: 188,
+        raw_data_size: None,
+        min_seq: Some(1),
+        max_seq: Some(2),
+        tombstone_num: Some(1),
+        key_size: Some(4),
+        value_size: Some(14),
+        key_size_histogram: Some(size_histogram([1, 1, 1, 1])),
+        value_size_histogram: Some(size_histogram([2, 4, 4, 4])),
     });
 
     assert_eq!(
@@ -82,10 +109,72 @@ fn test_open_table<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
         &Footer::new(
             Segment::new(172, 188),
             Segment::new(360, 77),
-            Segment::new(437, 84)
+            Segment::new(437, 215)
         )
     );
-    assert_eq!(593, t.file_size());
+    assert_eq!(724, t.file_size());
 
     Ok(())
 }
+
+fn test_open_table_binary_index<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut config = ctx.config();
+    config.block_index.binary = Some(true);
+
+    let db = DB::open(config.clone())?;
+    let (t, index_data) = create_tmp_table(ctx.storage(), db.as_ref(), "foo.rot")?;
+
+    // The JSON index of the same table is 188 bytes.
+    assert_eq!(t.stat().index_size, 116);
+
+    let t: Rotbl = Rotbl::open(ctx.storage(), config, "foo.rot")?;
+
+    assert_eq!(
+        t.block_index(),
+        &BlockIndex::new(index_data.clone()).with_binary_encoding().with_encoded_size(68)
+    );
+
+    let b = t.load_block(1)?;
+    let keys = b.range::<Vec<u8>, _>(..)?.map(|(k, _)| k.clone()).collect::<Vec<_>>();
+    assert_eq!(keys, bb_vec(["d"]));
+
+    Ok(())
+}
+
+fn test_builder_abort<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let files_in_base_dir = || -> anyhow::Result<usize> {
+        let Some(base_dir) = ctx.base_dir() else {
+            return Ok(0);
+        };
+        Ok(std::fs::read_dir(base_dir)?.count())
+    };
+
+    // Abort explicitly
+    {
+        let mut b = Builder::new(ctx.storage(), ctx.config(), "foo.rot")?;
+        b.append_kv("a", SeqMarked::new_normal(1, b"A".to_vec()))?;
+        b.abort()?;
+    }
+
+    assert!(ctx.storage().list("")?.is_empty());
+    assert_eq!(files_in_base_dir()?, 0);
+
+    // Drop without commit
+    {
+        let mut b = Builder::new(ctx.storage(), ctx.config(), "foo.rot")?;
+        b.append_kv("a", SeqMarked::new_normal(1, b"A".to_vec()))?;
+    }
+
+    assert!(ctx.storage().list("")?.is_empty());
+    assert_eq!(files_in_base_dir()?, 0);
+
+    Ok(())
+}
+
+fn size_histogram(sizes: impl IntoIterator<Item = u64>) -> SizeHistogram {
+    let mut h = SizeHistogram::default();
+    for size in sizes {
+        h.add(size);
+    }
+    h
+}
@@ -1,9 +1,9 @@
-use std::io;
 use std::sync::Arc;
 
 use libtest_mimic::Trial;
 use rotbl::storage::Storage;
 use rotbl::v001::Dump;
+use rotbl::v001::RotblError;
 
 use crate::context::TestContext;
 use crate::temp_table::create_tmp_table;
@@ -19,15 +19,17 @@ fn test_dump<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
     let _ = index_data;
 
     let d = Dump::new(Arc::new(t));
-    let got = d.dump().collect::<Result<Vec<_>, io::Error>>()?;
+    let got = d.dump().collect::<Result<Vec<_>, RotblError>>()?;
 
     let want = vec![
         r#"Rotbl:"#,
         r#"    header: {typ: Rotbl, version: V001}"#,
-        r#"    file_size: 593"#,
+        r#"    file_size: 724"#,
         r#"    meta: {header: {typ: RotblMeta, version: V001}, payload: {seq: 5, user_data: hello}}"#,
-        r#"    stat: 4 keys in 2 blocks: data(136 B), index(188 B), avg block size(68 B)"#,
-        r#"    access_stat: AccessStat { read_key: 0, read_block: 0, read_block_from_cache: 0, read_block_from_disk: 0 }"#,
+        r#"    stat: 4 keys in 2 blocks: data(136 B), index(188 B), avg block size(68 B), seq[1, 2], tombstones(1), key(4 B), value(14 B)"#,
+        r#"    key_size_histogram: {[1, 2): 4}"#,
+        r#"    value_size_histogram: {[2, 4): 1, [4, 8): 3}"#,
+        r#"    access_stat: AccessStat { read_key: 0, read_block: 0, read_block_from_cache: 0, read_block_from_disk: 0, filter_positive: 0, filter_negative: 0, filter_false_positive: 0, prefix_filter_positive: 0, prefix_filter_negative: 0 }"#,
         r#"BlockIndex: n: 2"#,
         r#"    index: { block_num: 0000, position: 36+73, key_range: ["a", "c"] }"#,
         r#"    index: { block_num: 0001, position: 109+63, key_range: ["d", "d"] }"#,
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use libtest_mimic::Trial;
+use rotbl::storage::impls::blocking::BlockingAdapter;
+use rotbl::storage::Storage;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::context::TestContext;
+use crate::temp_table::create_tmp_table;
+use crate::trials;
+use crate::utils::bb;
+use crate::utils::ss;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(trials!(
+        new_ctx,
+        test_rotbl_open_async_current_thread,
+        test_rotbl_sync_storage_current_thread
+    ));
+}
+
+/// Async storage does not rely on `block_in_place`, thus it works on a current-thread runtime.
+fn test_rotbl_open_async_current_thread<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let (t, index_data) = create_tmp_table(ctx.storage(), ctx.new_db()?.as_ref(), "foo.rot")?;
+
+    let rt = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+
+    rt.block_on(async move {
+        let storage = BlockingAdapter::new(ctx.storage());
+        let got = Rotbl::open_async(storage, ctx.config(), "foo.rot").await?;
+
+        assert_eq!(got.header(), t.header());
+        assert_eq!(got.file_size(), t.file_size());
+        assert_eq!(got.meta(), t.meta());
+        assert_eq!(got.stat(), t.stat());
+        assert_eq!(got.footer(), t.footer());
+        assert_eq!(
+            got.block_index().iter_index_entries().cloned().collect::<Vec<_>>(),
+            index_data
+        );
+
+        // Get
+
+        assert_eq!(None, got.get("a1").await?);
+        assert_eq!(Some(SeqMarked::new_tombstone(1)), got.get("a").await?);
+        assert_eq!(Some(SeqMarked::new_normal(2, bb("D"))), got.get("d").await?);
+
+        // Block 0 is loaded by "a1", then "a" is read from cache.
+        assert_eq!(got.access_stat().read_block_from_disk(), 2);
+        assert_eq!(got.access_stat().read_block_from_cache(), 1);
+
+        assert_eq!(Some(SeqMarked::new_normal(2, bb("B"))), got.get("b").await?);
+        assert_eq!(got.access_stat().read_block_from_cache(), 2);
+
+        // Range
+
+        let got = Arc::new(got);
+        let keys = got.range(..).map_ok(|(k, _v)| k).try_collect::<Vec<_>>().await?;
+        assert_eq!(vec![ss("a"), ss("b"), ss("c"), ss("d")], keys);
+
+        // Loading an uncached block with sync API is not supported on async storage
+        let storage = BlockingAdapter::new(ctx.storage());
+        let got: Rotbl = Rotbl::open_async(storage, ctx.config(), "foo.rot").await?;
+        let res = got.load_block(0);
+        assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::Unsupported);
+
+        Ok::<(), anyhow::Error>(())
+    })?;
+
+    Ok(())
+}
+
+/// A table opened with a sync storage loads blocks and index partitions in a blocking thread,
+/// thus the async API works on a current-thread runtime too.
+fn test_rotbl_sync_storage_current_thread<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    create_tmp_table(ctx.storage(), ctx.new_db()?.as_ref(), "foo.rot")?;
+
+    let mut config = ctx.config();
+    config.block_index.partition_size = Some(1);
+    let kvs = (0..10).map(|i| (format!("k{:02}", i), SeqMarked::new_normal(i, bb(i))));
+    Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "part.rot",
+        RotblMeta::new(1, "hello"),
+        kvs.clone(),
+    )?;
+
+    let rt = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+
+    rt.block_on(async move {
+        let t: Rotbl = Rotbl::open(ctx.storage(), ctx.config(), "foo.rot")?;
+
+        assert_eq!(Some(SeqMarked::new_normal(2, bb("D"))), t.get("d").await?);
+        assert_eq!(Some(SeqMarked::new_normal(2, bb("B"))), t.get("b").await?);
+        assert_eq!(t.access_stat().read_block_from_disk(), 2);
+
+        let t = Arc::new(t);
+        let keys = t.range(..).map_ok(|(k, _v)| k).try_collect::<Vec<_>>().await?;
+        assert_eq!(vec![ss("a"), ss("b"), ss("c"), ss("d")], keys);
+
+        let t: Rotbl = Rotbl::open(ctx.storage(), config, "part.rot")?;
+        assert!(t.block_index().is_partitioned());
+
+        for (k, v) in kvs {
+            assert_eq!(Some(v), t.get(&k).await?, "key: {}", k);
+        }
+
+        Ok::<(), anyhow::Error>(())
+    })?;
+
+    Ok(())
+}
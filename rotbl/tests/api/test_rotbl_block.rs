@@ -1,16 +1,19 @@
 use libtest_mimic::Trial;
 use rotbl::storage::Storage;
+use rotbl::v001::Rotbl;
 
 use crate::context::TestContext;
 use crate::temp_table;
 use crate::trials;
+use crate::utils::bb_vec;
 use crate::utils::NewContext;
 
 pub(crate) fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
     trials.extend(trials!(
         new_ctx,
         test_rotbl_get_block,
-        test_rotbl_load_block
+        test_rotbl_load_block,
+        test_rotbl_load_block_concurrently
     ));
 }
 
@@ -24,8 +27,8 @@ fn test_rotbl_get_block<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
     {
         // Block is filled into the cache.
         let b = t.get_block(0).unwrap();
-        let keys = b.range::<String, _>(..).map(|(k, _)| k.clone()).collect::<Vec<_>>();
-        assert_eq!(keys, vec!["a", "b", "c"]);
+        let keys = b.range::<Vec<u8>, _>(..)?.map(|(k, _)| k.clone()).collect::<Vec<_>>();
+        assert_eq!(keys, bb_vec(["a", "b", "c"]));
     }
 
     Ok(())
@@ -37,15 +40,51 @@ fn test_rotbl_load_block<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()>
 
     {
         let b = t.load_block(0)?;
-        let keys = b.range::<String, _>(..).map(|(k, _)| k.clone()).collect::<Vec<_>>();
-        assert_eq!(keys, vec!["a", "b", "c"]);
+        let keys = b.range::<Vec<u8>, _>(..)?.map(|(k, _)| k.clone()).collect::<Vec<_>>();
+        assert_eq!(keys, bb_vec(["a", "b", "c"]));
     }
 
     {
         let b = t.load_block(1)?;
-        let keys = b.range::<String, _>(..).map(|(k, _)| k.clone()).collect::<Vec<_>>();
-        assert_eq!(keys, vec!["d"]);
+        let keys = b.range::<Vec<u8>, _>(..)?.map(|(k, _)| k.clone()).collect::<Vec<_>>();
+        assert_eq!(keys, bb_vec(["d"]));
     }
 
     Ok(())
 }
+
+fn test_rotbl_load_block_concurrently<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    temp_table::create_tmp_table(ctx.storage(), ctx.new_db()?.as_ref(), "foo.rot")?;
+
+    let t: Rotbl = Rotbl::open(ctx.storage(), ctx.config(), "foo.rot")?;
+
+    std::thread::scope(|s| {
+        let handles = (0..8)
+            .map(|i| {
+                let t = &t;
+                s.spawn(move || t.load_block(i % 2))
+            })
+            .collect::<Vec<_>>();
+
+        for (i, h) in handles.into_iter().enumerate() {
+            let b = h.join().unwrap()?;
+            let keys = b.range::<Vec<u8>, _>(..)?.map(|(k, _)| k.clone()).collect::<Vec<_>>();
+            if i % 2 == 0 {
+                assert_eq!(keys, bb_vec(["a", "b", "c"]));
+            } else {
+                assert_eq!(keys, bb_vec(["d"]));
+            }
+        }
+
+        Ok::<(), anyhow::Error>(())
+    })?;
+
+    // Concurrent misses on the same block may load it more than once.
+    let read_from_disk = t.access_stat().read_block_from_disk();
+    assert!((2..=8).contains(&read_from_disk));
+
+    assert!(t.get_block(0).is_some());
+    assert!(t.get_block(1).is_some());
+
+    Ok(())
+}
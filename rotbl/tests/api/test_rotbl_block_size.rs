@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(
+        new_ctx,
+        test_rotbl_block_target_size,
+        test_rotbl_block_target_size_and_max_items
+    ));
+}
+
+/// Values of very different sizes.
+fn kvs() -> Vec<(String, SeqMarked)> {
+    (0..100)
+        .map(|i| {
+            let size = [10, 3000, 200, 50, 1500][i % 5];
+            (
+                format!("k{:03}", i),
+                SeqMarked::new_normal(i as u64, vec![b'v'; size]),
+            )
+        })
+        .collect()
+}
+
+async fn test_rotbl_block_target_size<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let target_size = 4096;
+
+    let mut config = ctx.config();
+    config.block_config.max_items = None;
+    config.block_config.target_size = Some(target_size);
+
+    Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "size.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+
+    let t = Arc::new(Rotbl::open(ctx.storage(), config, "size.rot")?);
+
+    let got = t.range(..).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs(), got);
+
+    // Every block but the last is cut right after it reaches the target size:
+    // it is at least the target size and at most one entry larger.
+    let block_num = t.stat().block_num;
+    assert!(block_num > 1);
+
+    let max_entry = 3000 + 64;
+    for i in 0..block_num - 1 {
+        let size = t.load_block(i)?.data_raw_size() as usize;
+        assert!(size >= target_size, "block {}: {}", i, size);
+        assert!(size < target_size + max_entry, "block {}: {}", i, size);
+    }
+
+    Ok(())
+}
+
+async fn test_rotbl_block_target_size_and_max_items<S: Storage>(
+    ctx: TestContext<S>,
+) -> anyhow::Result<()> {
+    let mut config = ctx.config();
+    config.block_config.max_items = Some(3);
+    config.block_config.target_size = Some(1024 * 1024);
+
+    let t = Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "items.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+
+    // A block is cut at the item limit before reaching the target size.
+    assert_eq!(34, t.stat().block_num);
+
+    config.block_config.target_size = Some(0);
+    let res = Rotbl::create_table(
+        ctx.storage(),
+        config,
+        "zero.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    );
+    assert_eq!(std::io::ErrorKind::InvalidInput, res.unwrap_err().kind());
+
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use libtest_mimic::Trial;
+use rotbl::storage::impls::blocking::BlockingAdapter;
+use rotbl::storage::Storage;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+use rotbl::version::Version;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(new_ctx, test_rotbl_bloom_filter));
+}
+
+async fn test_rotbl_bloom_filter<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    // Only even keys are present, so that absent odd keys fall inside a block.
+    let kvs = || {
+        (0..100).step_by(2).map(|i| {
+            (
+                format!("k{:03}", i),
+                SeqMarked::new_normal(i as u64, format!("v{}", i).into_bytes()),
+            )
+        })
+    };
+
+    let mut config = ctx.config();
+    config.block_config.max_items = Some(3);
+    config.disable_cache();
+
+    let without = Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "without.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+    assert_eq!(without.header().version(), Version::V001);
+    assert!(without.bloom_filter().is_none());
+    assert!(without.footer().sections_segment().is_none());
+
+    config.bloom_filter.bits_per_key = Some(10);
+
+    let created = Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "with.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+    assert_eq!(created.header().version(), Version::V002);
+    assert!(created.footer().sections_segment().is_some());
+
+    let t = Rotbl::open(ctx.storage(), config.clone(), "with.rot")?;
+    assert_eq!(t.footer(), created.footer());
+    assert_eq!(t.sections(), created.sections());
+    assert_eq!(t.bloom_filter(), created.bloom_filter());
+    assert_eq!(t.stat(), created.stat());
+
+    // Present keys are always read from disk.
+
+    for (k, v) in kvs() {
+        assert_eq!(Some(v), t.get(&k).await?, "{}", k);
+    }
+
+    let stat = t.access_stat();
+    assert_eq!(stat.filter_positive(), 50);
+    assert_eq!(stat.filter_negative(), 0);
+    assert_eq!(stat.read_block_from_disk(), 50);
+
+    // Most absent keys are answered without reading a block.
+
+    for i in (1..99).step_by(2) {
+        assert_eq!(None, t.get(&format!("k{:03}", i)).await?);
+    }
+
+    let stat = t.access_stat();
+    // Every absent key is checked by the filter before looking up the block index.
+    // A key between two blocks that passes the filter is rejected by the index without a read.
+    assert_eq!(stat.filter_positive() + stat.filter_negative(), 50 + 49);
+    assert!(stat.filter_negative() >= 35, "{}", stat);
+    assert_eq!(stat.filter_false_positive(), stat.filter_positive() - 50);
+    assert!(
+        stat.read_block_from_disk() <= stat.filter_positive(),
+        "{}",
+        stat
+    );
+
+    // The filter is loaded by the async open too.
+
+    let got = Rotbl::open_async(BlockingAdapter::new(ctx.storage()), config, "with.rot").await?;
+    assert_eq!(got.bloom_filter(), created.bloom_filter());
+    assert_eq!(None, got.get("k001").await?);
+    assert_eq!(
+        Some(SeqMarked::new_normal(2, "v2".to_string().into_bytes())),
+        got.get("k002").await?
+    );
+
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::io;
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::ss;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(new_ctx, test_rotbl_byte_keys));
+}
+
+async fn test_rotbl_byte_keys<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    // Keys are ordered by bytes, including the ones that are not UTF-8.
+    let keys: Vec<Vec<u8>> = vec![
+        vec![],
+        vec![0x00],
+        vec![0x00, 0x00],
+        vec![0x01, 0xff],
+        b"a".to_vec(),
+        vec![0x80],
+        vec![0xff],
+        vec![0xff, 0x00],
+        vec![0xff, 0xff],
+    ];
+
+    let kvs = keys
+        .iter()
+        .enumerate()
+        .map(|(i, k)| (k.clone(), SeqMarked::new_normal(i as u64, k.clone())))
+        .collect::<Vec<_>>();
+
+    Rotbl::create_table(
+        ctx.storage(),
+        ctx.config(),
+        "bytes.rot",
+        RotblMeta::new(1, "hello"),
+        kvs.clone(),
+    )?;
+
+    // The JSON block index can not store these keys, the binary one is used instead.
+    let t = Arc::new(Rotbl::open(ctx.storage(), ctx.config(), "bytes.rot")?);
+    assert_eq!(t.stat().key_num, keys.len() as u64);
+
+    for (k, v) in kvs.iter() {
+        assert_eq!(Some(v.clone()), t.get(k).await?);
+    }
+    assert_eq!(None, t.get([0x00, 0x01]).await?);
+    assert_eq!(None, t.get([0xfe]).await?);
+
+    let got = t.range_bytes(..).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs, got);
+
+    let got = t.range_bytes(vec![0x01]..vec![0xff, 0x00]).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs[3..7].to_vec(), got);
+
+    let got = t.range_prefix_bytes([0xff]).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs[6..].to_vec(), got);
+
+    let got = t.range_prefix_bytes([0x00]).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs[1..3].to_vec(), got);
+
+    // The `String` API works as long as only UTF-8 keys are returned.
+
+    let got = t.range(..ss("b")).try_collect::<Vec<_>>().await;
+    assert_eq!(io::ErrorKind::InvalidData, got.unwrap_err().kind());
+
+    let got = t.range_prefix("a").try_collect::<Vec<_>>().await?;
+    assert_eq!(vec![(ss("a"), kvs[4].1.clone())], got);
+
+    Ok(())
+}
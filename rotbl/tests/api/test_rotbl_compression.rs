@@ -0,0 +1,79 @@
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::Compression;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(new_ctx, test_rotbl_compression));
+}
+
+async fn test_rotbl_compression<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let value = |i: usize| format!(r#"{{"id":{},"name":"user","tags":["a","b","c"]}}"#, i);
+
+    let kvs = || {
+        (0..20).map(move |i| {
+            (
+                format!("k{:03}", i),
+                SeqMarked::new_normal(i as u64, value(i).into_bytes()),
+            )
+        })
+    };
+
+    // Small blocks have too little redundancy to compress.
+    let mut config = ctx.config();
+    config.block_config.max_items = Some(20);
+
+    let uncompressed = Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "none.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+    assert_eq!(uncompressed.stat().raw_data_size, None);
+
+    for compression in [Compression::Lz4, Compression::Zstd, Compression::Snappy] {
+        let mut config = config.clone();
+        config.block_config.compression = Some(compression);
+
+        let path = format!("{}.rot", compression);
+        let t = Rotbl::create_table(
+            ctx.storage(),
+            config.clone(),
+            &path,
+            RotblMeta::new(1, "hello"),
+            kvs(),
+        )?;
+
+        let stat = t.stat();
+        assert_eq!(stat.key_num, 20);
+        assert!(
+            stat.raw_data_size.unwrap() > stat.data_size,
+            "{}: {}",
+            compression,
+            stat
+        );
+        assert!(
+            stat.data_size < uncompressed.stat().data_size,
+            "{}: {}",
+            compression,
+            stat
+        );
+
+        let t = Rotbl::open(ctx.storage(), config, &path)?;
+        assert_eq!(t.stat(), stat);
+
+        for (k, v) in kvs() {
+            let got = t.get(&k).await?;
+            assert_eq!(Some(v), got, "{}: {}", compression, k);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use std::io::Write;
+
+use codeq::Encode;
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::typ::Type;
+use rotbl::v001::Builder;
+use rotbl::v001::Footer;
+use rotbl::v001::Header;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblError;
+use rotbl::v001::Segment;
+use rotbl::v001::SeqMarked;
+use rotbl::version::Version;
+
+use crate::context::TestContext;
+use crate::temp_table::create_tmp_table;
+use crate::trials;
+use crate::utils::NewContext;
+
+pub(crate) fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(trials!(
+        new_ctx,
+        test_rotbl_error_wrong_type,
+        test_rotbl_error_unsupported_version,
+        test_rotbl_error_corruption,
+        test_rotbl_error_truncated,
+        test_rotbl_error_segment_beyond_file,
+        test_rotbl_error_keys_out_of_order
+    ));
+}
+
+fn write_file<S: Storage>(mut storage: S, path: &str, data: &[u8]) -> anyhow::Result<()> {
+    let mut w = storage.writer(path)?;
+    w.write_all(data)?;
+    w.commit()?;
+    Ok(())
+}
+
+fn read_file<S: Storage>(mut storage: S, path: &str) -> anyhow::Result<Vec<u8>> {
+    let r = storage.positional_reader(path)?;
+    Ok(r.read_at(0, r.size()?)?.to_vec())
+}
+
+/// A file of another type is an error, not a panic.
+fn test_rotbl_error_wrong_type<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut data = Vec::new();
+    Header::new(Type::BlockIndex, Version::V001).encode(&mut data)?;
+    data.extend_from_slice(&[0; 64]);
+    write_file(ctx.storage(), "wrong.rot", &data)?;
+
+    let res = Rotbl::<SeqMarked>::open(ctx.storage(), ctx.config(), "wrong.rot");
+    let err = res.unwrap_err();
+    assert!(
+        matches!(err, RotblError::WrongType {
+            expected: Type::Rotbl,
+            actual: Type::BlockIndex
+        }),
+        "{}",
+        err
+    );
+
+    // Not a rotbl at all
+    write_file(ctx.storage(), "text.rot", &[b'x'; 128])?;
+    let res = Rotbl::<SeqMarked>::open(ctx.storage(), ctx.config(), "text.rot");
+    let err = res.unwrap_err();
+    assert!(
+        matches!(&err, RotblError::Corruption { section, offset: 0, .. } if section == "header"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+fn test_rotbl_error_unsupported_version<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut data = Vec::new();
+    Header::new(Type::Rotbl, Version::V004).encode(&mut data)?;
+    data.extend_from_slice(&[0; 64]);
+    write_file(ctx.storage(), "v4.rot", &data)?;
+
+    let res = Rotbl::<SeqMarked>::open(ctx.storage(), ctx.config(), "v4.rot");
+    let err = res.unwrap_err();
+    assert!(
+        matches!(err, RotblError::UnsupportedVersion {
+            typ: Type::Rotbl,
+            version: Version::V004
+        }),
+        "{}",
+        err
+    );
+    assert_eq!(std::io::ErrorKind::Unsupported, err.kind());
+
+    Ok(())
+}
+
+fn test_rotbl_error_corruption<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    create_tmp_table(ctx.storage(), ctx.new_db()?.as_ref(), "foo.rot")?;
+    let data = read_file(ctx.storage(), "foo.rot")?;
+
+    // Meta is at 360, see `test_create_table`.
+    let mut b = data.clone();
+    b[360 + 30] ^= 0xff;
+    write_file(ctx.storage(), "bad-meta.rot", &b)?;
+
+    let res = Rotbl::<SeqMarked>::open(ctx.storage(), ctx.config(), "bad-meta.rot");
+    let err = res.unwrap_err();
+    assert!(
+        matches!(&err, RotblError::Corruption {
+            table,
+            section,
+            block_num: None,
+            offset: 360,
+            size: 77,
+            ..
+        } if table == "bad-meta.rot" && section == "meta"),
+        "{}",
+        err
+    );
+
+    // Block 0 is at 36; it is not read until it is loaded.
+    let mut b = data.clone();
+    b[36 + 40] ^= 0xff;
+    write_file(ctx.storage(), "bad-block.rot", &b)?;
+
+    let t: Rotbl = Rotbl::open(ctx.storage(), ctx.config(), "bad-block.rot")?;
+    t.load_block(1)?;
+
+    let err = t.load_block(0).unwrap_err();
+    assert!(
+        matches!(&err, RotblError::Corruption {
+            table,
+            section,
+            block_num: Some(0),
+            offset: 36,
+            size: 73,
+            ..
+        } if table == "bad-block.rot" && section == "block"),
+        "{}",
+        err
+    );
+    assert_eq!(std::io::ErrorKind::InvalidData, err.kind());
+    assert!(
+        err.to_string()
+            .starts_with("corrupted block 0 of table 'bad-block.rot' at bytes [36, 109): "),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+/// A file cut short is reported as a corruption of the section that can not be read in full.
+fn test_rotbl_error_truncated<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    create_tmp_table(ctx.storage(), ctx.new_db()?.as_ref(), "foo.rot")?;
+    let data = read_file(ctx.storage(), "foo.rot")?;
+
+    // Only the header is left.
+    write_file(ctx.storage(), "short.rot", &data[..40])?;
+
+    let res = Rotbl::<SeqMarked>::open(ctx.storage(), ctx.config(), "short.rot");
+    let err = res.unwrap_err();
+    assert!(
+        matches!(&err, RotblError::Corruption { table, section, .. }
+            if table == "short.rot" && section == "footer"),
+        "{}",
+        err
+    );
+
+    Ok(())
+}
+
+/// A segment in the footer beyond the end of the file is not read.
+fn test_rotbl_error_segment_beyond_file<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    create_tmp_table(ctx.storage(), ctx.new_db()?.as_ref(), "foo.rot")?;
+    let data = read_file(ctx.storage(), "foo.rot")?;
+
+    // Segments of index, meta and stat, see `test_create_table`.
+    let footer_size = Footer::encoded_size(Version::V001);
+    let index = Segment::new(172, 188);
+    let stat = Segment::new(437, 215);
+
+    for meta in [
+        Segment::new(360, u64::MAX - 360),
+        Segment::new(360, u64::MAX),
+    ] {
+        let mut b = data[..data.len() - footer_size].to_vec();
+        Footer::new(index, meta, stat).encode(&mut b)?;
+        write_file(ctx.storage(), "huge.rot", &b)?;
+
+        let res = Rotbl::<SeqMarked>::open(ctx.storage(), ctx.config(), "huge.rot");
+        let err = res.unwrap_err();
+        assert!(
+            matches!(&err, RotblError::Corruption { section, offset: 360, .. } if section == "meta"),
+            "{}",
+            err
+        );
+        assert!(
+            err.to_string().contains("beyond the file size 724"),
+            "{}",
+            err
+        );
+    }
+
+    Ok(())
+}
+
+/// Appending keys out of order is an error, not a panic.
+fn test_rotbl_error_keys_out_of_order<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut b = Builder::new(ctx.storage(), ctx.config(), "order.rot")?;
+    b.append_kv("b", SeqMarked::new_normal(1, b"v".to_vec()))?;
+
+    for k in ["a", "b"] {
+        let err = b.append_kv(k, SeqMarked::new_normal(1, b"v".to_vec())).unwrap_err();
+        assert!(matches!(err, RotblError::InvalidArgument(_)), "{}", err);
+        assert_eq!(std::io::ErrorKind::InvalidInput, err.kind());
+    }
+
+    b.abort()?;
+
+    Ok(())
+}
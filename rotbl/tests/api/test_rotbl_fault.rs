@@ -0,0 +1,114 @@
+use libtest_mimic::Trial;
+use rotbl::storage::impls::fault::FaultConfig;
+use rotbl::storage::impls::fault::FaultStorage;
+use rotbl::storage::Storage;
+use rotbl::v001::Config;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblError;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::context::TestContext;
+use crate::temp_table::create_tmp_table;
+use crate::trials;
+use crate::utils::bb;
+use crate::utils::ss;
+use crate::utils::NewContext;
+
+pub(crate) fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(trials!(
+        new_ctx,
+        test_rotbl_fault_read,
+        test_rotbl_fault_write
+    ));
+}
+
+/// Load all key-values of a table block by block.
+fn load_all(t: &Rotbl) -> Result<Vec<(Vec<u8>, SeqMarked)>, RotblError> {
+    let mut kvs = Vec::new();
+    for block_num in 0..t.stat().block_num {
+        let b = t.load_block(block_num)?;
+        kvs.extend(b.range::<Vec<u8>, _>(..)?.map(|(k, v)| (k.clone(), v.clone())));
+    }
+    Ok(kvs)
+}
+
+/// Corrupted reads must result in an error, never a panic or wrong data.
+fn test_rotbl_fault_read<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    create_tmp_table(ctx.storage(), ctx.new_db()?.as_ref(), "foo.rot")?;
+
+    let want = load_all(&Rotbl::open(ctx.storage(), ctx.config(), "foo.rot")?)?;
+
+    let mut n_err = 0;
+    let mut n_fault = 0;
+
+    for seed in 0..64 {
+        let config = FaultConfig {
+            read_error: 0.05,
+            short_read: 0.05,
+            bit_flip: 0.1,
+            ..FaultConfig::new(seed)
+        };
+        let storage = FaultStorage::new(ctx.storage(), config);
+
+        let res = Rotbl::open(storage.clone(), ctx.config(), "foo.rot").and_then(|t| load_all(&t));
+
+        match res {
+            Ok(got) => assert_eq!(want, got, "seed: {}, faults: {}", seed, storage.stat()),
+            Err(_e) => n_err += 1,
+        }
+        n_fault += storage.stat().total();
+    }
+
+    assert!(n_fault > 0);
+    assert!(n_err > 0);
+
+    Ok(())
+}
+
+/// A table built with faulty writes must either fail to build, fail to open, or contain
+/// exactly the written data.
+fn test_rotbl_fault_write<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut config = Config::default();
+    config.block_config.max_items = Some(3);
+
+    let kvs = (0..20)
+        .map(|i| {
+            (
+                bb(format!("k{:03}", i)),
+                SeqMarked::new_normal(i, ss(i).into_bytes()),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut n_err = 0;
+    let mut n_fault = 0;
+
+    for seed in 0..64 {
+        let fault_config = FaultConfig {
+            write_error: 0.005,
+            torn_write: 0.01,
+            lost_commit: 0.2,
+            ..FaultConfig::new(seed)
+        };
+        let storage = FaultStorage::new(ctx.storage(), fault_config);
+
+        let path = format!("t-{}.rot", seed);
+        let meta = RotblMeta::new(1, "hello");
+
+        let res = Rotbl::create_table(storage.clone(), config.clone(), &path, meta, kvs.clone())
+            .and_then(|_t| Rotbl::open(ctx.storage(), config.clone(), &path))
+            .and_then(|t| load_all(&t));
+
+        match res {
+            Ok(got) => assert_eq!(kvs, got, "seed: {}, faults: {}", seed, storage.stat()),
+            Err(_e) => n_err += 1,
+        }
+        n_fault += storage.stat().total();
+    }
+
+    assert!(n_fault > 0);
+    assert!(n_err > 0);
+
+    Ok(())
+}
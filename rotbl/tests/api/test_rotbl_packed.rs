@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::Compression;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(
+        new_ctx,
+        test_rotbl_packed,
+        test_rotbl_packed_invalid_config
+    ));
+}
+
+async fn test_rotbl_packed<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let kvs = (0..30)
+        .map(|i| {
+            let v = if i % 7 == 0 {
+                SeqMarked::new_tombstone(i)
+            } else {
+                SeqMarked::new_normal(i, format!("v{}", i).into_bytes())
+            };
+            (format!("k{:03}", i), v)
+        })
+        .collect::<std::collections::BTreeMap<_, _>>();
+
+    for compression in [Compression::None, Compression::Lz4] {
+        let mut config = ctx.config();
+        config.block_config.max_items = Some(10);
+        config.block_config.packed = Some(true);
+        config.block_config.compression = Some(compression);
+
+        let path = format!("{}.rot", compression);
+        Rotbl::create_table(
+            ctx.storage(),
+            config.clone(),
+            &path,
+            RotblMeta::new(1, "hello"),
+            kvs.clone(),
+        )?;
+
+        let t = Arc::new(Rotbl::open(ctx.storage(), config, &path)?);
+
+        for (k, v) in kvs.iter() {
+            assert_eq!(Some(v.clone()), t.get(k).await?, "{}", k);
+        }
+        assert_eq!(None, t.get("k0011").await?);
+        assert_eq!(None, t.get("k100").await?);
+
+        let got = t.range(..).try_collect::<Vec<_>>().await?;
+        assert_eq!(kvs.clone().into_iter().collect::<Vec<_>>(), got);
+
+        let got = t
+            .range("k005".to_string()..="k012".to_string())
+            .map_ok(|(k, _v)| k)
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(
+            (5..=12).map(|i| format!("k{:03}", i)).collect::<Vec<_>>(),
+            got
+        );
+
+        // Scanning a block does not decode all of it.
+        let block = t.load_block(1)?;
+        let got = block
+            .scan(b"k012".to_vec()..b"k014".to_vec())?
+            .map(|res| res.map(|(k, v)| (k.to_vec(), v)))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(
+            vec![
+                (b"k012".to_vec(), kvs["k012"].clone()),
+                (b"k013".to_vec(), kvs["k013"].clone()),
+            ],
+            got
+        );
+    }
+
+    Ok(())
+}
+
+async fn test_rotbl_packed_invalid_config<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut config = ctx.config();
+    config.block_config.packed = Some(true);
+    config.block_config.restart_interval = Some(4);
+
+    let res = Rotbl::create_table(
+        ctx.storage(),
+        config,
+        "invalid.rot",
+        RotblMeta::new(1, "hello"),
+        [("a", SeqMarked::new_normal(1, b"A".to_vec()))],
+    );
+    assert_eq!(std::io::ErrorKind::InvalidInput, res.unwrap_err().kind());
+
+    Ok(())
+}
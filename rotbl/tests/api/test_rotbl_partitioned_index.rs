@@ -0,0 +1,154 @@
+use std::io;
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(
+        new_ctx,
+        test_rotbl_partitioned_index,
+        test_rotbl_partitioned_index_bloom_filter,
+        test_rotbl_partitioned_index_zero_size
+    ));
+}
+
+fn kvs() -> Vec<(String, SeqMarked)> {
+    (0..30)
+        .map(|i| {
+            let k = format!("{}/{:03}", ["a", "b", "c"][i % 3], i);
+            (
+                k,
+                SeqMarked::new_normal(i as u64, format!("v{}", i).into_bytes()),
+            )
+        })
+        .collect::<std::collections::BTreeMap<_, _>>()
+        .into_iter()
+        .collect()
+}
+
+async fn test_rotbl_partitioned_index<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut config = ctx.config();
+    config.block_index.partition_size = Some(2);
+
+    Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "part.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+
+    let t: Rotbl = Rotbl::open(ctx.storage(), config, "part.rot")?;
+    let t = Arc::new(t);
+
+    // 30 keys in blocks of 3 items, in partitions of 2 blocks.
+    assert_eq!(10, t.stat().block_num);
+    assert!(t.block_index().is_partitioned());
+    assert_eq!(5, t.block_index().iter_index_entries().count());
+
+    // Nothing is loaded when opening.
+    assert_eq!(0, t.cache_stat().item_cnt());
+
+    // A get loads one partition and one block.
+    assert_eq!(
+        Some(SeqMarked::new_normal(16, b"v16".to_vec())),
+        t.get("b/016").await?
+    );
+    assert_eq!(2, t.cache_stat().item_cnt());
+
+    assert_eq!(None, t.get("b/017").await?);
+    assert_eq!(None, t.get("0").await?);
+    assert_eq!(None, t.get("z").await?);
+
+    for (k, v) in kvs() {
+        assert_eq!(Some(v), t.get(&k).await?, "key: {}", k);
+    }
+
+    // All partitions and blocks are cached
+    assert_eq!(15, t.cache_stat().item_cnt());
+
+    let got = t.range(..).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs(), got);
+
+    let got = t.range("a/010".to_string().."b/007".to_string()).try_collect::<Vec<_>>().await?;
+    let want = kvs()
+        .into_iter()
+        .filter(|(k, _)| k.as_str() >= "a/010" && k.as_str() < "b/007")
+        .collect::<Vec<_>>();
+    assert_eq!(want, got);
+
+    let got = t.range_prefix("c/").try_collect::<Vec<_>>().await?;
+    let want = kvs().into_iter().filter(|(k, _)| k.starts_with("c/")).collect::<Vec<_>>();
+    assert_eq!(10, got.len());
+    assert_eq!(want, got);
+
+    for i in 0..t.stat().block_num {
+        t.load_block(i)?;
+    }
+    assert!(t.load_block(t.stat().block_num).is_err());
+
+    Ok(())
+}
+
+/// A key rejected by the bloom filter does not load an index partition.
+async fn test_rotbl_partitioned_index_bloom_filter<S: Storage>(
+    ctx: TestContext<S>,
+) -> anyhow::Result<()> {
+    let mut config = ctx.config();
+    config.block_index.partition_size = Some(2);
+    config.bloom_filter.bits_per_key = Some(10);
+
+    Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "part-bloom.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+
+    let t: Rotbl = Rotbl::open(ctx.storage(), config, "part-bloom.rot")?;
+
+    for i in 0..30 {
+        assert_eq!(None, t.get(format!("b/{:03}-absent", i)).await?);
+    }
+
+    // Only a false positive loads a partition and a block.
+    let stat = t.access_stat();
+    let cached = t.cache_stat().item_cnt();
+    assert!(stat.filter_negative() > 0, "{}", stat);
+    assert!(
+        cached <= 2 * stat.filter_positive(),
+        "cached: {}, {}",
+        cached,
+        stat
+    );
+
+    Ok(())
+}
+
+async fn test_rotbl_partitioned_index_zero_size<S: Storage>(
+    ctx: TestContext<S>,
+) -> anyhow::Result<()> {
+    let mut config = ctx.config();
+    config.block_index.partition_size = Some(0);
+
+    let res = Rotbl::create_table(
+        ctx.storage(),
+        config,
+        "zero.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    );
+    assert_eq!(io::ErrorKind::InvalidInput, res.unwrap_err().kind());
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::PrefixExtractor;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(new_ctx, test_rotbl_prefix_bloom_filter));
+}
+
+async fn test_rotbl_prefix_bloom_filter<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    // Only even users are present, 3 keys each.
+    let kvs = || {
+        (10..30).step_by(2).flat_map(|u| {
+            ["a", "b", "c"].into_iter().map(move |k| {
+                (
+                    format!("user/{}/{}", u, k),
+                    SeqMarked::new_normal(u, k.to_string().into_bytes()),
+                )
+            })
+        })
+    };
+
+    let keys_of = |u: u64| {
+        kvs().filter(move |(k, _v)| k.starts_with(&format!("user/{}/", u))).collect::<Vec<_>>()
+    };
+
+    // Blocks span users, so that the block index alone can not rule out an absent user.
+    let mut config = ctx.config();
+    config.block_config.max_items = Some(4);
+    config.bloom_filter.prefix_extractor = Some(PrefixExtractor::Delimiter {
+        delimiter: '/',
+        count: 2,
+    });
+
+    let created = Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "prefix.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+
+    let t = Arc::new(Rotbl::open(ctx.storage(), config, "prefix.rot")?);
+    assert_eq!(t.prefix_bloom_filter(), created.prefix_bloom_filter());
+    assert_eq!(t.prefix_bloom_filter().unwrap().len(), 8);
+    assert!(t.bloom_filter().is_none());
+
+    // Present prefixes
+
+    for u in (10..30).step_by(2) {
+        let prefix = format!("user/{}/", u);
+        assert!(t.may_contain_prefix(&prefix), "{}", prefix);
+
+        let got = t.range_prefix(&prefix).try_collect::<Vec<_>>().await?;
+        assert_eq!(keys_of(u), got, "{}", prefix);
+    }
+
+    let stat = t.access_stat();
+    assert_eq!(stat.prefix_filter_negative(), 0);
+    let positive = stat.prefix_filter_positive();
+
+    // Absent prefixes are mostly ruled out without reading a block.
+
+    let read_before = stat.read_block();
+    let mut may_contain = 0;
+
+    for u in (11..29).step_by(2) {
+        let prefix = format!("user/{}/", u);
+        if t.may_contain_prefix(&prefix) {
+            may_contain += 1;
+        }
+
+        let got = t.range_prefix(&prefix).try_collect::<Vec<_>>().await?;
+        assert!(got.is_empty(), "{}", prefix);
+    }
+
+    assert!(may_contain <= 2, "false positives: {}", may_contain);
+    assert!(stat.prefix_filter_negative() >= 7, "{}", stat);
+    assert_eq!(
+        stat.read_block() - read_before,
+        stat.prefix_filter_positive() - positive
+    );
+
+    // A range within a prefix is pruned too, while other ranges are not.
+
+    let got = t
+        .range("user/12/b".to_string().."user/12/c".to_string())
+        .map_ok(|(k, _v)| k)
+        .try_collect::<Vec<_>>()
+        .await?;
+    assert_eq!(vec!["user/12/b".to_string()], got);
+
+    let negative = stat.prefix_filter_negative();
+    let got = t.range("user/11/".to_string()..).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs().skip(3).collect::<Vec<_>>(), got);
+    assert_eq!(stat.prefix_filter_negative(), negative);
+
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::Compression;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(new_ctx, test_rotbl_prefix_keys));
+}
+
+async fn test_rotbl_prefix_keys<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let kvs = (0..30)
+        .map(|i| {
+            (
+                format!("tenant/{}/objects/{:03}", i % 2, i),
+                SeqMarked::new_normal(i, format!("v{}", i).into_bytes()),
+            )
+        })
+        .collect::<std::collections::BTreeMap<_, _>>();
+
+    for compression in [Compression::None, Compression::Snappy] {
+        let mut config = ctx.config();
+        config.block_config.max_items = Some(10);
+        config.block_config.restart_interval = Some(4);
+        config.block_config.compression = Some(compression);
+
+        let path = format!("{}.rot", compression);
+        Rotbl::create_table(
+            ctx.storage(),
+            config.clone(),
+            &path,
+            RotblMeta::new(1, "hello"),
+            kvs.clone(),
+        )?;
+
+        let t = Arc::new(Rotbl::open(ctx.storage(), config, &path)?);
+
+        for (k, v) in kvs.iter() {
+            assert_eq!(Some(v.clone()), t.get(k).await?, "{}", k);
+        }
+        assert_eq!(None, t.get("tenant/0/objects/0011").await?);
+
+        let got = t.range(..).try_collect::<Vec<_>>().await?;
+        assert_eq!(kvs.clone().into_iter().collect::<Vec<_>>(), got);
+
+        let got = t
+            .range("tenant/1/".to_string().."tenant/10".to_string())
+            .map_ok(|(k, _v)| k)
+            .try_collect::<Vec<_>>()
+            .await?;
+        assert_eq!(15, got.len());
+        assert!(got.iter().all(|k| k.starts_with("tenant/1/")));
+    }
+
+    Ok(())
+}
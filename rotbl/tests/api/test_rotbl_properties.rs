@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::BlockIndexEntry;
+use rotbl::v001::Builder;
+use rotbl::v001::Properties;
+use rotbl::v001::PropertyCollector;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+
+use crate::context::TestContext;
+use crate::trials;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(trials!(
+        new_ctx,
+        test_rotbl_properties,
+        test_rotbl_properties_absent,
+        test_rotbl_properties_after_append
+    ));
+}
+
+/// Collects the max seq, the number of keys per tenant and the number of blocks.
+#[derive(Default)]
+struct TenantCollector {
+    max_seq: u64,
+    tenants: BTreeMap<String, u64>,
+    blocks: u64,
+}
+
+impl PropertyCollector for TenantCollector {
+    fn add_kv(&mut self, key: &[u8], value: &SeqMarked) {
+        self.max_seq = self.max_seq.max(value.user_seq());
+
+        let key = String::from_utf8_lossy(key);
+        let tenant = key.split('/').next().unwrap();
+        *self.tenants.entry(tenant.to_string()).or_default() += 1;
+    }
+
+    fn finish_block(&mut self, entry: &BlockIndexEntry) {
+        assert_eq!(self.blocks, entry.block_num() as u64);
+        self.blocks += 1;
+    }
+
+    fn finish(&mut self, properties: &mut Properties) {
+        properties.insert("max_seq", self.max_seq.to_string());
+        properties.insert("blocks", self.blocks.to_be_bytes().to_vec());
+        for (tenant, n) in self.tenants.iter() {
+            properties.insert(format!("tenant.{}", tenant), n.to_string());
+        }
+    }
+}
+
+fn kvs() -> Vec<(String, SeqMarked)> {
+    (0..10)
+        .map(|i| {
+            let k = format!("{}/{:03}", ["t1", "t2"][(i >= 7) as usize], i);
+            (k, SeqMarked::new_normal(i as u64 * 3, b"v".to_vec()))
+        })
+        .collect()
+}
+
+fn test_rotbl_properties<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut b = Builder::new(ctx.storage(), ctx.config(), "props.rot")?;
+    b.add_property_collector(TenantCollector::default())?;
+    for (k, v) in kvs() {
+        b.append_kv(k, v)?;
+    }
+    let t = b.commit(RotblMeta::new(1, "hello"))?;
+
+    let want = {
+        let mut p = Properties::default();
+        p.insert("max_seq", "27");
+        p.insert("blocks", 4u64.to_be_bytes().to_vec());
+        p.insert("tenant.t1", "7");
+        p.insert("tenant.t2", "3");
+        p
+    };
+
+    assert_eq!(Some(&want), t.properties());
+
+    let t: Rotbl = Rotbl::open(ctx.storage(), ctx.config(), "props.rot")?;
+    assert_eq!(Some(&want), t.properties());
+    assert_eq!(
+        Some(b"7".as_slice()),
+        t.properties().unwrap().get("tenant.t1")
+    );
+
+    Ok(())
+}
+
+fn test_rotbl_properties_absent<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    Rotbl::create_table(
+        ctx.storage(),
+        ctx.config(),
+        "plain.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+
+    let t: Rotbl = Rotbl::open(ctx.storage(), ctx.config(), "plain.rot")?;
+    assert_eq!(None, t.properties());
+
+    // An empty table still has the properties of its collectors.
+    let mut b = Builder::new(ctx.storage(), ctx.config(), "empty.rot")?;
+    b.add_property_collector(TenantCollector::default())?;
+    b.commit(RotblMeta::new(1, "hello"))?;
+
+    let t: Rotbl = Rotbl::open(ctx.storage(), ctx.config(), "empty.rot")?;
+    assert_eq!(
+        Some(b"0".as_slice()),
+        t.properties().unwrap().get("max_seq")
+    );
+
+    Ok(())
+}
+
+fn test_rotbl_properties_after_append<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let mut b = Builder::new(ctx.storage(), ctx.config(), "late.rot")?;
+    b.append_kv("a", SeqMarked::new_normal(1, b"A".to_vec()))?;
+
+    let res = b.add_property_collector(TenantCollector::default());
+    assert_eq!(io::ErrorKind::InvalidInput, res.unwrap_err().kind());
+
+    Ok(())
+}
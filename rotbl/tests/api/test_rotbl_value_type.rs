@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use futures::TryStreamExt;
+use libtest_mimic::Trial;
+use rotbl::storage::Storage;
+use rotbl::v001::Rotbl;
+use rotbl::v001::RotblMeta;
+use rotbl::v001::SeqMarked;
+use rotbl::v001::Value;
+
+use crate::async_trials;
+use crate::context::TestContext;
+use crate::utils::bb;
+use crate::utils::NewContext;
+
+pub fn tests<S: Storage>(new_ctx: impl NewContext<S>, trials: &mut Vec<Trial>) {
+    trials.extend(async_trials!(
+        new_ctx,
+        test_rotbl_raw_bytes_value,
+        test_rotbl_structured_value
+    ));
+}
+
+/// A table of raw bytes values does not store sequence numbers or tombstones.
+async fn test_rotbl_raw_bytes_value<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let kvs = || (0..20).map(|i| (bb(format!("k{:03}", i)), bb(format!("v{}", i))));
+
+    let raw = Rotbl::create_table(
+        ctx.storage(),
+        ctx.config(),
+        "raw.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+
+    let seq_marked = Rotbl::create_table(
+        ctx.storage(),
+        ctx.config(),
+        "seq_marked.rot",
+        RotblMeta::new(1, "hello"),
+        kvs().map(|(k, v)| (k, SeqMarked::new_normal(1, v))),
+    )?;
+
+    assert!(raw.stat().data_size < seq_marked.stat().data_size);
+
+    // Seq and tombstone stats are only recorded for `SeqMarked` values.
+    assert_eq!(None, raw.stat().max_seq);
+    assert_eq!(None, raw.stat().tombstone_num);
+    assert_eq!(Some(1), seq_marked.stat().max_seq);
+    assert_eq!(Some(0), seq_marked.stat().tombstone_num);
+    assert_eq!(raw.stat().key_size, seq_marked.stat().key_size);
+
+    let t = Arc::new(Rotbl::<Vec<u8>>::open(
+        ctx.storage(),
+        ctx.config(),
+        "raw.rot",
+    )?);
+    assert_eq!(raw.stat(), t.stat());
+
+    assert_eq!(Some(bb("v3")), t.get("k003").await?);
+    assert_eq!(None, t.get("k100").await?);
+
+    let got = t.range_bytes(..).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs().collect::<Vec<_>>(), got);
+
+    Ok(())
+}
+
+#[derive(Debug)]
+#[derive(Clone)]
+#[derive(PartialEq)]
+#[derive(bincode::Encode, bincode::Decode)]
+struct User {
+    id: u64,
+    name: String,
+    tags: Vec<String>,
+}
+
+impl Value for User {}
+
+/// A structured value is stored without being serialized by the application first.
+async fn test_rotbl_structured_value<S: Storage>(ctx: TestContext<S>) -> anyhow::Result<()> {
+    let user = |i: u64| User {
+        id: i,
+        name: format!("user-{}", i),
+        tags: vec!["a".to_string(); i as usize % 3],
+    };
+
+    let kvs = || (0..20).map(|i| (format!("u{:03}", i), user(i)));
+
+    let mut config = ctx.config();
+    config.block_config.restart_interval = Some(4);
+
+    Rotbl::create_table(
+        ctx.storage(),
+        config.clone(),
+        "user.rot",
+        RotblMeta::new(1, "hello"),
+        kvs(),
+    )?;
+
+    let t = Arc::new(Rotbl::<User>::open(ctx.storage(), config, "user.rot")?);
+
+    assert_eq!(Some(user(5)), t.get("u005").await?);
+    assert_eq!(None, t.get("u100").await?);
+
+    let got = t.range(..).try_collect::<Vec<_>>().await?;
+    assert_eq!(kvs().collect::<Vec<_>>(), got);
+
+    Ok(())
+}
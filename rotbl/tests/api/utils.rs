@@ -17,6 +17,11 @@ pub(crate) fn bb(x: impl ToString) -> Vec<u8> {
     x.to_string().into_bytes()
 }
 
+/// Create a vector of byte vectors
+pub(crate) fn bb_vec(x: impl IntoIterator<Item = impl ToString>) -> Vec<Vec<u8>> {
+    x.into_iter().map(bb).collect()
+}
+
 pub trait NewContext<S>
 where
     S: Storage,